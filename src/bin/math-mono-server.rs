@@ -0,0 +1,21 @@
+//! Hosts math battles for players on the local network.
+//!
+//! Usage: `math-mono-server [ADDRESS]`, where `ADDRESS` defaults to `127.0.0.1:7878`.
+//! Host on `0.0.0.0:7878` to let other computers join.
+
+#[cfg(not(target_family = "wasm"))]
+fn main() -> std::process::ExitCode {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| math_mono::DEFAULT_ADDRESS.to_string());
+    match math_mono::run_server(&address) {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("Couldn't host on {address}: {error}");
+            std::process::ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(target_family = "wasm")]
+fn main() {}
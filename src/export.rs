@@ -0,0 +1,280 @@
+//! Teacher reports: every profile's session logs as CSV and JSON.
+//!
+//! Native builds write both files to the downloads folder (or the documents folder
+//! if there is none). Web builds download them through the browser.
+//!
+//! # CSV schema
+//!
+//! One row per number eaten. A session in which nothing was eaten gets a single row
+//! with the answer columns left empty. The numbers that made a total are logged once
+//! the total is made, so they share its `seconds`. Online battles are checked by the
+//! server rather than the game, so their sessions have no answers and get a single row.
+//!
+//! | Column       | Meaning                                                  |
+//! |--------------|----------------------------------------------------------|
+//! | `profile`    | Profile name                                             |
+//! | `session`    | Session number within the profile, counting from 1       |
+//! | `date`       | Local start time, `YYYY-MM-DD HH:MM`                     |
+//! | `mode`       | `classic`, `daily` or `online`                           |
+//! | `level`      | Level id, e.g. `times-tables`                            |
+//! | `final_score`| Score at the end of the session                          |
+//! | `duration_s` | Seconds played, excluding pauses                         |
+//! | `hints`      | Hints used                                               |
+//! | `player`     | Who ate the number: 0 for the profile's player, 1 for a  |
+//! |              | second player in co-op or versus                         |
+//! | `round`      | Which question of the session, counting from 0           |
+//! | `question`   | The question as shown to the player                      |
+//! | `value`      | The number eaten, e.g. `7`, `-3`, `3/4`, `2.5`, `$1.25`, |
+//! |              | `3:45` or `150 cm`                                       |
+//! | `correct`    | `true` or `false`                                        |
+//! | `seconds`    | Seconds from the question being shown to the eat         |
+//!
+//! # JSON schema
+//!
+//! ```text
+//! {
+//!   "schema_version": 5,
+//!   "profiles": [{
+//!     "name": string,
+//!     "sessions": [{
+//!       "date": string, "mode": "classic" | "daily" | "online", "level": string,
+//!       "score": number, "duration_secs": number, "hints_used": number,
+//!       "answers": [{  // always empty for "online"
+//!         "player": number, "round": number, "question": string,
+//!         "value": number (whole numbers) | string (e.g. "3/4", "$1.25", "3:45"),
+//!         "correct": bool, "seconds": number
+//!       }]
+//!     }]
+//!   }]
+//! }
+//! ```
+
+use serde::Serialize;
+
+use crate::{
+    math_mono::session::SessionLog,
+    save::{Profiles, load_profile_sessions},
+};
+
+/// Bump this whenever either format changes.
+const REPORT_SCHEMA_VERSION: u32 = 5;
+const CSV_HEADER: &str = "profile,session,date,mode,level,final_score,duration_s,hints,player,round,question,value,correct,seconds";
+
+#[derive(Serialize)]
+struct Report<'a> {
+    schema_version: u32,
+    profiles: Vec<ProfileReport<'a>>,
+}
+
+#[derive(Serialize)]
+struct ProfileReport<'a> {
+    name: &'a str,
+    sessions: Vec<SessionLog>,
+}
+
+/// Write the report for every profile and describe where it went.
+pub fn export_report(profiles: &Profiles) -> Result<String, String> {
+    let report = Report {
+        schema_version: REPORT_SCHEMA_VERSION,
+        profiles: profiles
+            .profiles
+            .iter()
+            .map(|profile| ProfileReport {
+                name: &profile.name,
+                sessions: load_profile_sessions(profile),
+            })
+            .collect(),
+    };
+    let json = serde_json::to_string_pretty(&report).map_err(|error| error.to_string())?;
+    let csv = to_csv(&report);
+
+    let stem = format!(
+        "math-mono-report-{}",
+        chrono::Local::now().format("%Y-%m-%d")
+    );
+    backend::write(&format!("{stem}.csv"), &csv, "text/csv")?;
+    backend::write(&format!("{stem}.json"), &json, "application/json")?;
+    Ok(backend::location(&stem))
+}
+
+fn to_csv(report: &Report) -> String {
+    let mut csv = format!("{CSV_HEADER}\n");
+    for profile in &report.profiles {
+        for (index, session) in profile.sessions.iter().enumerate() {
+            let session_columns = [
+                csv_field(profile.name),
+                (index + 1).to_string(),
+                csv_field(&session.date),
+                csv_field(&session.mode),
+                csv_field(&session.level),
+                session.score.to_string(),
+                format!("{:.1}", session.duration_secs),
+                session.hints_used.to_string(),
+            ]
+            .join(",");
+            if session.answers.is_empty() {
+                csv += &format!("{session_columns},,,,,,\n");
+            }
+            for answer in &session.answers {
+                csv += &format!(
+                    "{session_columns},{},{},{},{},{},{:.2}\n",
+                    answer.player,
+                    answer.round,
+                    csv_field(&answer.question),
+                    answer.value,
+                    answer.correct,
+                    answer.seconds,
+                );
+            }
+        }
+    }
+    csv
+}
+
+/// Quote a field if it contains anything that would break the row.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+mod backend {
+    use std::{fs, path::PathBuf};
+
+    fn folder() -> Option<PathBuf> {
+        dirs::download_dir().or_else(dirs::document_dir)
+    }
+
+    pub fn write(file_name: &str, text: &str, _mime_type: &str) -> Result<(), String> {
+        let folder = folder().ok_or("no downloads folder on this platform")?;
+        fs::write(folder.join(file_name), text).map_err(|error| error.to_string())
+    }
+
+    pub fn location(stem: &str) -> String {
+        let folder = folder().unwrap_or_default();
+        format!("Saved {}.csv and .json", folder.join(stem).display())
+    }
+}
+
+#[cfg(target_family = "wasm")]
+mod backend {
+    use std::cell::Cell;
+
+    use wasm_bindgen::{JsCast, closure::Closure};
+    use web_sys::{Blob, BlobPropertyBag, HtmlAnchorElement, Url};
+
+    /// Browsers may block downloads started back to back, so each starts this many
+    /// milliseconds after the one before it.
+    const DOWNLOAD_SPACING_MS: f64 = 500.0;
+    /// Milliseconds a download's URL is kept after it starts. Revoking it right away can
+    /// cancel the download.
+    const REVOKE_DELAY_MS: f64 = 60_000.0;
+
+    thread_local! {
+        /// When the next download may start, in milliseconds since the epoch.
+        static NEXT_DOWNLOAD_AT: Cell<f64> = const { Cell::new(0.0) };
+    }
+
+    pub fn write(file_name: &str, text: &str, mime_type: &str) -> Result<(), String> {
+        let window = web_sys::window().ok_or("no window to download from")?;
+        let document = window.document().ok_or("no document to download from")?;
+
+        let parts = js_sys::Array::of1(&text.into());
+        let options = BlobPropertyBag::new();
+        options.set_type(mime_type);
+        let blob = Blob::new_with_str_sequence_and_options(&parts, &options)
+            .map_err(|error| format!("{error:?}"))?;
+        let url = Url::create_object_url_with_blob(&blob).map_err(|error| format!("{error:?}"))?;
+
+        let anchor: HtmlAnchorElement = document
+            .create_element("a")
+            .map_err(|error| format!("{error:?}"))?
+            .dyn_into()
+            .map_err(|_| "couldn't create a download link")?;
+        anchor.set_href(&url);
+        anchor.set_download(file_name);
+
+        let now = js_sys::Date::now();
+        let start = NEXT_DOWNLOAD_AT.get().max(now);
+        NEXT_DOWNLOAD_AT.set(start + DOWNLOAD_SPACING_MS);
+        let later = |delay_ms: f64, callback: wasm_bindgen::JsValue| {
+            window
+                .set_timeout_with_callback_and_timeout_and_arguments_0(
+                    callback.unchecked_ref(),
+                    delay_ms as i32,
+                )
+                .map(|_| ())
+                .map_err(|error| format!("{error:?}"))
+        };
+        later(start - now, Closure::once_into_js(move || anchor.click()))?;
+        later(
+            start - now + REVOKE_DELAY_MS,
+            Closure::once_into_js(move || {
+                let _ = Url::revoke_object_url(&url);
+            }),
+        )
+    }
+
+    pub fn location(stem: &str) -> String {
+        format!("Downloaded {stem}.csv and .json")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math_mono::{game::number::Number, session::AnswerLog};
+
+    #[test]
+    fn test_csv_has_a_row_per_answer() {
+        let session = SessionLog {
+            date: "2025-09-10 09:30".to_string(),
+            mode: "classic".to_string(),
+            level: "times-tables".to_string(),
+            score: 10,
+            duration_secs: 42.0,
+            hints_used: 1,
+            answers: vec![
+                AnswerLog {
+                    player: 0,
+                    round: 0,
+                    question: "Eat the multiples of 3".to_string(),
+                    value: Number::int(9),
+                    correct: true,
+                    seconds: 1.5,
+                },
+                AnswerLog {
+                    player: 1,
+                    round: 0,
+                    question: "Eat the multiples of 3".to_string(),
+                    value: Number::int(10),
+                    correct: false,
+                    seconds: 3.0,
+                },
+            ],
+        };
+        let report = Report {
+            schema_version: REPORT_SCHEMA_VERSION,
+            profiles: vec![ProfileReport {
+                name: "Ada, B.",
+                sessions: vec![session, SessionLog::default()],
+            }],
+        };
+
+        let csv = to_csv(&report);
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0], CSV_HEADER);
+        assert_eq!(
+            rows[2],
+            "\"Ada, B.\",1,2025-09-10 09:30,classic,times-tables,10,42.0,1,1,0,Eat the multiples of 3,10,false,3.00"
+        );
+        assert_eq!(
+            rows[3].split(',').count(),
+            CSV_HEADER.split(',').count() + 1
+        );
+    }
+}
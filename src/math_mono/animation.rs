@@ -35,25 +35,17 @@ impl Plugin for AnimationPlugin {
     }
 }
 
-/// Updates the single player's animation state based on movement.
+/// Updates each player's animation state based on movement.
 fn update_animation_movement(
-    // Wrap the conflicting queries in a ParamSet
-    mut player_queries: ParamSet<(
-        // Query 0: The specific query for a moved player
-        Query<&mut PlayerAnimation, (With<Player>, Changed<GridPosition>)>,
-        // Query 1: The general query to find any player
-        Query<&mut PlayerAnimation, With<Player>>,
-    )>,
+    mut player_query: Query<(&mut PlayerAnimation, Ref<GridPosition>), With<Player>>,
 ) {
-    // First, try to get the player from the "moved" query (index 0).
-    // We use .p0() to access the first query in the ParamSet.
-    if let Ok(mut animation) = player_queries.p0().single_mut() {
-        animation.update_state(PlayerAnimationState::Walking);
-    }
-    // If that fails, get the player from the general query (index 1).
-    // We use .p1() to access the second query.
-    else if let Ok(mut animation) = player_queries.p1().single_mut() {
-        animation.update_state(PlayerAnimationState::Idling);
+    for (mut animation, grid_pos) in &mut player_query {
+        // Players that moved this frame walk, the rest stand still.
+        if grid_pos.is_changed() {
+            animation.update_state(PlayerAnimationState::Walking);
+        } else {
+            animation.update_state(PlayerAnimationState::Idling);
+        }
     }
 }
 
@@ -1,5 +1,10 @@
 use bevy::prelude::*;
+use bevy::time::Stopwatch;
 use bevy::{ecs::component::Component, reflect::Reflect};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::math_mono::game::{number::Number, tiles::Direction};
 
 // Shared components
 #[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
@@ -8,6 +13,10 @@ pub struct Health {
     pub current: i32,
     pub max: i32,
     pub invulnerable_timer: f32, // Prevent rapid damage
+    /// Seconds left on a shield power-up, which takes the next hit instead.
+    pub shield_secs: f32,
+    /// Hits that did damage since the level started.
+    pub hits_taken: u32,
 }
 
 #[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
@@ -17,42 +26,76 @@ pub struct Damage {
 }
 
 // Player components
-#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Default, Reflect)]
 #[reflect(Component)]
 pub struct Player {
     pub score: i32,
+    /// 0 for player one, 1 for player two.
+    pub index: usize,
+    /// Right answers, less tiles stolen by the other player in versus.
+    pub correct_answers: u32,
+    /// Seconds left of being stunned by a wrong answer in versus.
+    pub stunned_secs: f32,
+}
+
+/// What a player asked to do this frame, read from their keys or gamepad.
+#[derive(Component, Debug, Clone, PartialEq, Eq, Default)]
+pub struct PlayerInput {
+    pub moves: Vec<Direction>,
+    pub eat: bool,
+}
+
+/// Right answers in a row, which multiply the points each one scores.
+#[derive(Component, Debug, Clone, Default)]
+pub struct Combo {
+    pub streak: u32,
+    /// Longest streak since the level started.
+    pub best_streak: u32,
+    /// Time since the last answer or new question, for the speed bonus.
+    pub since_last_answer: Stopwatch,
 }
 
-#[derive(Component, Reflect, Clone, Copy, PartialEq, Debug)]
+/// The sum of the numbers the player has chained so far on a make-a-total question.
+#[derive(Component, Debug, Clone, PartialEq, Eq, Default, Reflect)]
+#[reflect(Component)]
+pub struct RunningTotal {
+    pub total: i32,
+    /// Blocks eaten into the current chain, put back if it overshoots.
+    pub chain: Vec<Entity>,
+}
+
+#[derive(
+    Component, Reflect, Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize,
+)]
 pub struct GridPosition {
     pub row: i32,
     pub col: i32,
 }
 
 // Enemy components
-// #[derive(Component)]
-// pub struct Enemy {
-//     pub speed: f32,
-//     pub ai_state: EnemyState,
-// }
-
-// #[derive(Component)]
-// pub struct EnemySpawner {
-//     pub timer: f32,
-//     pub spawn_interval: f32,
-// }
-
-// Game state components
 #[derive(Component)]
-pub struct Question {
-    pub text: String,
-    pub answer: i32,
+pub struct Enemy {
+    pub step_timer: Timer,
+    // Each enemy walks with its own random stream so its path doesn't depend on query order
+    pub rng: ChaCha8Rng,
 }
 
 #[derive(Component, Reflect, Clone, Copy, PartialEq)]
 pub struct NumberBlock {
-    pub value: i32,
+    pub value: Number,
     pub is_eaten: bool,
+    /// Who ate it, in versus.
+    pub claim: Option<Claim>,
+}
+
+/// A right answer eaten in versus, which the other player can still take.
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Claim {
+    pub owner: Entity,
+    /// Stolen tiles can't be stolen back.
+    pub stolen: bool,
+    /// What the tile scored when it was eaten, which a steal moves to the thief.
+    pub points: i32,
 }
 
 // UI components
@@ -62,9 +105,16 @@ pub struct NumberBlock {
 #[derive(Component)]
 pub struct ScoreDisplay;
 
+/// Player two's half of the split versus HUD, on the far side of the board.
+#[derive(Component)]
+pub struct RivalScoreDisplay;
+
 #[derive(Component)]
 pub struct QuestionDisplay;
 
+#[derive(Component)]
+pub struct WorkedSolutionDisplay;
+
 // Collision components
 // #[derive(Component)]
 // pub struct CollisionBox {
@@ -92,6 +142,8 @@ impl Default for Health {
             current: 5,
             max: 5,
             invulnerable_timer: 0.0,
+            shield_secs: 0.0,
+            hits_taken: 0,
         }
     }
 }
@@ -101,33 +153,3 @@ impl Default for Damage {
         Self { amount: 1 }
     }
 }
-
-impl Default for Player {
-    fn default() -> Self {
-        Self { score: 0 }
-    }
-}
-
-impl Default for GridPosition {
-    fn default() -> Self {
-        Self { row: 0, col: 0 }
-    }
-}
-
-// impl Default for Enemy {
-//     fn default() -> Self {
-//         Self {
-//             speed: 50.0,
-//             ai_state: EnemyState::default(),
-//         }
-//     }
-// }
-
-// impl Default for EnemySpawner {
-//     fn default() -> Self {
-//         Self {
-//             timer: 0.0,
-//             spawn_interval: 10.0, // Spawn every 10 seconds
-//         }
-//     }
-// }
@@ -0,0 +1,157 @@
+//! The daily challenge: a board seeded from the local date, with one scored attempt per day.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    math_mono::run::{GameMode, RunSummary},
+    save::SaveData,
+    screens::Screen,
+};
+
+pub struct DailyPlugin;
+
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(Screen::Gameplay),
+            begin_daily_attempt.run_if(resource_equals(GameMode::Daily)),
+        );
+        app.add_systems(
+            Update,
+            record_daily_result.run_if(resource_exists_and_changed::<RunSummary>),
+        );
+    }
+}
+
+/// Today's local date formatted as `YYYY-MM-DD`.
+pub fn today_key() -> String {
+    chrono::Local::now()
+        .date_naive()
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+/// Derive the seed for a day from its date key.
+///
+/// This uses FNV-1a rather than the standard library's hasher, whose output
+/// may change between Rust versions.
+pub fn daily_seed(date_key: &str) -> u64 {
+    date_key.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// The player's result for every day they attempted the challenge.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DailyRecords {
+    pub results: Vec<DailyResult>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyResult {
+    /// The day this result is for, formatted as `YYYY-MM-DD`.
+    pub date: String,
+    pub score: i32,
+    pub rounds_cleared: u32,
+}
+
+impl DailyRecords {
+    pub fn get(&self, date_key: &str) -> Option<&DailyResult> {
+        self.results.iter().find(|result| result.date == date_key)
+    }
+
+    /// Insert or replace the result for `result.date`.
+    pub fn record(&mut self, result: DailyResult) {
+        match self
+            .results
+            .iter_mut()
+            .find(|existing| existing.date == result.date)
+        {
+            Some(existing) => *existing = result,
+            None => self.results.push(result),
+        }
+    }
+
+    pub fn best(&self) -> Option<&DailyResult> {
+        self.results.iter().max_by_key(|result| result.score)
+    }
+
+    /// The most recent results first, at most `count` of them.
+    pub fn recent(&self, count: usize) -> Vec<&DailyResult> {
+        let mut results: Vec<_> = self.results.iter().collect();
+        // `YYYY-MM-DD` keys sort chronologically.
+        results.sort_by(|a, b| b.date.cmp(&a.date));
+        results.truncate(count);
+        results
+    }
+}
+
+/// The date of the daily challenge being played, in case the run goes past midnight.
+#[derive(Resource, Debug, Clone, PartialEq)]
+struct DailyAttempt {
+    date: String,
+}
+
+/// Use up today's attempt as soon as it starts, so quitting early can't be retried.
+fn begin_daily_attempt(mut commands: Commands, mut save: ResMut<SaveData>) {
+    let date = today_key();
+    save.daily.record(DailyResult {
+        date: date.clone(),
+        score: 0,
+        rounds_cleared: 0,
+    });
+    commands.insert_resource(DailyAttempt { date });
+}
+
+fn record_daily_result(
+    summary: Res<RunSummary>,
+    attempt: Option<Res<DailyAttempt>>,
+    mut save: ResMut<SaveData>,
+) {
+    let Some(attempt) = attempt.filter(|_| summary.mode == GameMode::Daily) else {
+        return;
+    };
+    save.daily.record(DailyResult {
+        date: attempt.date.clone(),
+        score: summary.score,
+        rounds_cleared: summary.rounds_cleared,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+    use crate::math_mono::run::RunRng;
+
+    #[test]
+    fn test_daily_seed_is_stable() {
+        assert_eq!(daily_seed("2025-09-10"), daily_seed("2025-09-10"));
+        assert_ne!(daily_seed("2025-09-10"), daily_seed("2025-09-11"));
+    }
+
+    #[test]
+    fn test_daily_board_rolls_are_pinned() {
+        // Changes if the seed or the generator changes, which would change everyone's board.
+        let mut rng = RunRng::from_seed(daily_seed("2025-09-10"));
+        assert_eq!(rng.board.random::<u32>(), 4_090_874_189);
+    }
+
+    #[test]
+    fn test_record_replaces_same_day() {
+        let mut records = DailyRecords::default();
+        for (date, score) in [("2025-09-10", 0), ("2025-09-11", 40), ("2025-09-10", 70)] {
+            records.record(DailyResult {
+                date: date.to_string(),
+                score,
+                rounds_cleared: 0,
+            });
+        }
+
+        assert_eq!(records.results.len(), 2);
+        assert_eq!(records.best().unwrap().score, 70);
+        assert_eq!(records.recent(1)[0].date, "2025-09-11");
+    }
+}
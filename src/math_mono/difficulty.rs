@@ -0,0 +1,239 @@
+//! Adaptive difficulty: the game gets harder while the player answers quickly and
+//! correctly, and easier while they struggle.
+
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::{prelude::*, time::Stopwatch};
+
+use crate::{
+    AppSystems, PausableSystems,
+    math_mono::{
+        components::Enemy,
+        game::{
+            BoardParams, MAX_BLOCK_VALUE,
+            enemies::EnemySpawner,
+            player::AnswerEaten,
+            question::{CurrentQuestion, Question},
+        },
+        level::{ActiveLevel, LevelDefinition},
+    },
+    screens::Screen,
+};
+
+/// How many recent answers the director looks at.
+const WINDOW_SIZE: usize = 10;
+/// Answers needed in the window before the director makes a change.
+const MIN_ANSWERS: usize = 5;
+/// How much difficulty changes in one step.
+const DIFFICULTY_STEP: f32 = 0.05;
+/// Raise the difficulty when at least this share of recent answers were right...
+const RAISE_ACCURACY: f32 = 0.85;
+/// ...and they took at most this long on average.
+const FAST_ANSWER_SECS: f32 = 4.0;
+/// Lower the difficulty when fewer than this share of recent answers were right...
+const LOWER_ACCURACY: f32 = 0.6;
+/// ...or they took longer than this on average.
+const SLOW_ANSWER_SECS: f32 = 10.0;
+/// The smallest largest-number-on-the-board, used at difficulty 0.
+const MIN_BLOCK_RANGE: i32 = 40;
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Difficulty>();
+        app.add_systems(
+            Update,
+            (
+                tick_answer_clock.in_set(AppSystems::TickTimers),
+                (
+                    restart_answer_clock.run_if(resource_exists_and_changed::<CurrentQuestion>),
+                    adjust_difficulty,
+                    apply_difficulty_to_enemies.run_if(resource_changed::<Difficulty>),
+                )
+                    .chain()
+                    .in_set(AppSystems::Update),
+            )
+                .in_set(PausableSystems)
+                .run_if(in_state(Screen::Gameplay)),
+        );
+    }
+}
+
+/// How far the difficulty director may go for a level, from 0 (easiest) to 1 (hardest).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DifficultyRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl DifficultyRange {
+    pub const fn new(min: f32, max: f32) -> Self {
+        Self { min, max }
+    }
+
+    /// Levels start halfway between their easiest and hardest.
+    pub fn start(&self) -> f32 {
+        (self.min + self.max) / 2.0
+    }
+}
+
+/// The current difficulty, from 0 (easiest) to 1 (hardest). Inspect it to debug the director.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct Difficulty(pub f32);
+
+impl Difficulty {
+    pub fn for_level(level: &LevelDefinition) -> Self {
+        Self(level.difficulty.start())
+    }
+
+    /// How many questions, from the start of a level's pool, may be asked.
+    pub fn question_pool_len(&self, pool_len: usize) -> usize {
+        let share = 0.4 + 0.6 * self.0;
+        ((pool_len as f32 * share).ceil() as usize).clamp(1, pool_len)
+    }
+
+    pub fn board_params(&self, question: &Question) -> BoardParams {
+        let range = MIN_BLOCK_RANGE as f32 + (MAX_BLOCK_VALUE - MIN_BLOCK_RANGE) as f32 * self.0;
+        BoardParams {
+            max_value: (range.round() as i32).max(question.min_block_range()),
+            distractor_closeness: self.0 * 0.8,
+        }
+    }
+
+    pub fn enemy_spawn_interval_secs(&self, level: &LevelDefinition) -> f32 {
+        level.enemy_spawn_interval_secs * (1.5 - self.0)
+    }
+
+    pub fn enemy_step_interval_secs(&self, level: &LevelDefinition) -> f32 {
+        level.enemy_step_interval_secs * (1.4 - 0.8 * self.0)
+    }
+}
+
+/// Recent answers the difficulty director judges the player by.
+#[derive(Resource, Debug, Default)]
+pub struct DifficultyDirector {
+    /// Whether each answer was right, and how many seconds it took.
+    recent: VecDeque<(bool, f32)>,
+    /// Time since the last answer or new question.
+    answer_clock: Stopwatch,
+}
+
+impl DifficultyDirector {
+    fn record(&mut self, correct: bool, secs: f32) {
+        if self.recent.len() == WINDOW_SIZE {
+            self.recent.pop_front();
+        }
+        self.recent.push_back((correct, secs));
+    }
+
+    /// Which way the difficulty should move, if at all.
+    fn verdict(&self) -> Option<f32> {
+        if self.recent.len() < MIN_ANSWERS {
+            return None;
+        }
+        let count = self.recent.len() as f32;
+        let accuracy = self.recent.iter().filter(|(correct, _)| *correct).count() as f32 / count;
+        let average_secs = self.recent.iter().map(|(_, secs)| secs).sum::<f32>() / count;
+
+        if accuracy >= RAISE_ACCURACY && average_secs <= FAST_ANSWER_SECS {
+            Some(DIFFICULTY_STEP)
+        } else if accuracy < LOWER_ACCURACY || average_secs > SLOW_ANSWER_SECS {
+            Some(-DIFFICULTY_STEP)
+        } else {
+            None
+        }
+    }
+}
+
+fn tick_answer_clock(time: Res<Time>, director: Option<ResMut<DifficultyDirector>>) {
+    if let Some(mut director) = director {
+        director.answer_clock.tick(time.delta());
+    }
+}
+
+fn restart_answer_clock(mut director: ResMut<DifficultyDirector>) {
+    director.answer_clock.reset();
+}
+
+fn adjust_difficulty(
+    mut eaten_events: EventReader<AnswerEaten>,
+    level: Res<ActiveLevel>,
+    mut director: ResMut<DifficultyDirector>,
+    mut difficulty: ResMut<Difficulty>,
+) {
+    for event in eaten_events.read() {
+        let secs = director.answer_clock.elapsed_secs();
+        director.answer_clock.reset();
+        director.record(event.correct, secs);
+
+        let Some(step) = director.verdict() else {
+            continue;
+        };
+        let range = level.definition.difficulty;
+        let new_difficulty = (difficulty.0 + step).clamp(range.min, range.max);
+        if new_difficulty != difficulty.0 {
+            difficulty.0 = new_difficulty;
+            debug!("Difficulty is now {new_difficulty:.2}");
+        }
+        // Judge the new difficulty on fresh answers.
+        director.recent.clear();
+    }
+}
+
+fn apply_difficulty_to_enemies(
+    difficulty: Res<Difficulty>,
+    level: Res<ActiveLevel>,
+    spawner: Option<ResMut<EnemySpawner>>,
+    mut enemy_query: Query<&mut Enemy>,
+) {
+    let step_secs = difficulty.enemy_step_interval_secs(&level.definition);
+    if let Some(mut spawner) = spawner {
+        let spawn_secs = difficulty.enemy_spawn_interval_secs(&level.definition);
+        spawner
+            .timer
+            .set_duration(Duration::from_secs_f32(spawn_secs));
+        spawner.step_interval_secs = step_secs;
+    }
+    for mut enemy in &mut enemy_query {
+        enemy
+            .step_timer
+            .set_duration(Duration::from_secs_f32(step_secs));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math_mono::game::number::Number;
+
+    #[test]
+    fn test_director_raises_on_fast_correct_answers() {
+        let mut director = DifficultyDirector::default();
+        for _ in 0..MIN_ANSWERS - 1 {
+            director.record(true, 1.0);
+        }
+        assert_eq!(director.verdict(), None);
+
+        director.record(true, 1.0);
+        assert_eq!(director.verdict(), Some(DIFFICULTY_STEP));
+    }
+
+    #[test]
+    fn test_director_lowers_on_mistakes() {
+        let mut director = DifficultyDirector::default();
+        for correct in [true, false, false, true, false] {
+            director.record(correct, 2.0);
+        }
+        assert_eq!(director.verdict(), Some(-DIFFICULTY_STEP));
+    }
+
+    #[test]
+    fn test_board_params_keep_questions_answerable() {
+        let params = Difficulty(0.0).board_params(&Question::GreaterThan(Number::int(75)));
+        assert!(params.max_value > 75);
+        assert_eq!(Difficulty(0.0).question_pool_len(4), 2);
+        assert_eq!(Difficulty(1.0).question_pool_len(4), 4);
+    }
+}
@@ -0,0 +1,393 @@
+//! Bosses: a large enemy on boss levels that asks the level's questions one phase at a
+//! time, is hurt by right answers and attacks whole rows and columns.
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    AppSystems, PausableSystems,
+    math_mono::{
+        components::{GridPosition, Health, Player},
+        game::{
+            grid::{GridConfig, calculate_grid_layout, grid_position_to_world},
+            player::AnswerEaten,
+            question::{CurrentQuestion, Question},
+            sequence::SequenceProgress,
+        },
+        level::ActiveLevel,
+        review::RoundReview,
+        run::{RunRng, start_run},
+    },
+    screens::Screen,
+};
+
+/// Seconds an attack is shown on its row or column before it hits.
+const TELEGRAPH_SECS: f32 = 1.5;
+const BOSS_COLOR: Color = Color::srgb(0.55, 0.1, 0.45);
+const WARNING_COLOR: Color = Color::srgba(1.0, 0.2, 0.1, 0.35);
+const HEALTH_BAR_HEIGHT: f32 = 16.0;
+const HEALTH_BAR_COLOR: Color = Color::srgb(0.85, 0.15, 0.2);
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(Screen::Gameplay), spawn_boss.after(start_run));
+        app.add_systems(
+            Update,
+            (
+                tick_boss_timers.in_set(AppSystems::TickTimers),
+                (
+                    hurt_boss_on_right_answers,
+                    telegraph_attacks,
+                    land_attacks,
+                    damage_player_on_boss_contact,
+                    update_boss_health_bar,
+                )
+                    .chain()
+                    .in_set(AppSystems::Update),
+            )
+                .in_set(PausableSystems)
+                .run_if(in_state(Screen::Gameplay)),
+        );
+    }
+}
+
+/// A level's boss. Each phase asks the next of the level's questions, going back to the
+/// first after the last.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BossDefinition {
+    pub name: &'static str,
+    /// Bottom-left cell covered by the boss. The level's layout should leave floor under
+    /// every cell it covers.
+    pub row: i32,
+    pub col: i32,
+    pub rows: i32,
+    pub cols: i32,
+    /// Right answers needed to beat the boss. The level's goal should match.
+    pub health: u32,
+    /// Right answers before the boss moves on to its next phase.
+    pub hits_per_phase: u32,
+    pub attack_interval_secs: f32,
+}
+
+impl BossDefinition {
+    /// Whether the boss covers `pos`.
+    pub fn covers(&self, pos: &GridPosition) -> bool {
+        (self.row..self.row + self.rows).contains(&pos.row)
+            && (self.col..self.col + self.cols).contains(&pos.col)
+    }
+
+    /// The cells the boss covers.
+    pub fn cells(&self) -> impl Iterator<Item = GridPosition> + '_ {
+        (self.row..self.row + self.rows).flat_map(|row| {
+            (self.col..self.col + self.cols).map(move |col| GridPosition { row, col })
+        })
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Boss {
+    pub definition: BossDefinition,
+    pub health: u32,
+    attack_timer: Timer,
+}
+
+impl Boss {
+    fn new(definition: BossDefinition) -> Self {
+        Self {
+            definition,
+            health: definition.health,
+            attack_timer: Timer::from_seconds(
+                definition.attack_interval_secs,
+                TimerMode::Repeating,
+            ),
+        }
+    }
+
+    /// Index of the current phase, counting up forever; wrap it to pick a question.
+    pub fn phase(&self) -> u32 {
+        (self.definition.health - self.health) / self.definition.hits_per_phase
+    }
+
+    /// The question asked in the current phase.
+    pub fn question(&self, questions: &[Question]) -> Question {
+        questions[self.phase() as usize % questions.len()]
+    }
+}
+
+/// A row or column about to be hit by the boss.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Line {
+    Row(i32),
+    Column(i32),
+}
+
+impl Line {
+    fn contains(self, pos: &GridPosition) -> bool {
+        match self {
+            Self::Row(row) => pos.row == row,
+            Self::Column(col) => pos.col == col,
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+struct BossAttack {
+    line: Line,
+    warning: Timer,
+}
+
+#[derive(Component)]
+struct BossHealthBar;
+
+fn spawn_boss(
+    mut commands: Commands,
+    level: Res<ActiveLevel>,
+    config: Res<GridConfig>,
+    asset_server: Res<AssetServer>,
+) {
+    let Some(definition) = level.definition.boss else {
+        return;
+    };
+    // Stretch over the centres of every covered cell, then out to their edges.
+    let centers: Vec<Vec2> = definition
+        .cells()
+        .map(|cell| grid_position_to_world(&config, &cell))
+        .collect();
+    let bottom_left = centers.iter().copied().reduce(Vec2::min).unwrap();
+    let top_right = centers.iter().copied().reduce(Vec2::max).unwrap();
+    let size = top_right - bottom_left + config.block_size;
+
+    commands.spawn((
+        Name::new("Boss"),
+        Boss::new(definition),
+        Sprite::from_color(BOSS_COLOR, size),
+        // Above the blocks and enemies, below the player
+        Transform::from_translation(((bottom_left + top_right) / 2.0).extend(0.6)),
+        StateScoped(Screen::Gameplay),
+        children![(
+            Text2d::new(definition.name),
+            TextFont {
+                font: asset_server.load("fonts/MonofurNerdFont-Bold.ttf"),
+                font_size: 30.0,
+                ..default()
+            },
+            Transform::from_translation(Vec3::Z * 0.1),
+        )],
+    ));
+
+    // The health bar runs along the top of the board.
+    let (total_width, total_height, bottom_left_x, bottom_left_y) = calculate_grid_layout(&config);
+    let top = bottom_left_y + total_height + HEALTH_BAR_HEIGHT;
+    let center_x = bottom_left_x + total_width / 2.0;
+    commands.spawn((
+        Name::new("Boss Health Bar"),
+        Sprite::from_color(
+            Color::srgb(0.2, 0.2, 0.2),
+            Vec2::new(total_width, HEALTH_BAR_HEIGHT),
+        ),
+        Transform::from_translation(Vec3::new(center_x, top, 2.0)),
+        StateScoped(Screen::Gameplay),
+        children![(
+            BossHealthBar,
+            Sprite {
+                color: HEALTH_BAR_COLOR,
+                custom_size: Some(Vec2::new(total_width, HEALTH_BAR_HEIGHT)),
+                anchor: bevy::sprite::Anchor::CenterLeft,
+                ..default()
+            },
+            Transform::from_translation(Vec3::new(-total_width / 2.0, 0.0, 0.1)),
+        )],
+    ));
+}
+
+fn tick_boss_timers(
+    time: Res<Time>,
+    mut boss_query: Query<&mut Boss>,
+    mut attack_query: Query<&mut BossAttack>,
+) {
+    for mut boss in &mut boss_query {
+        boss.attack_timer.tick(time.delta());
+    }
+    for mut attack in &mut attack_query {
+        attack.warning.tick(time.delta());
+    }
+}
+
+/// Every right answer hurts the boss. Once it has taken enough hits it moves on to its
+/// next phase and asks a new question.
+fn hurt_boss_on_right_answers(
+    mut commands: Commands,
+    mut eaten_events: EventReader<AnswerEaten>,
+    level: Res<ActiveLevel>,
+    mut question: ResMut<CurrentQuestion>,
+    mut round: ResMut<RoundReview>,
+    mut sequence: ResMut<SequenceProgress>,
+    mut rng: ResMut<RunRng>,
+    mut boss_query: Query<(Entity, &mut Boss)>,
+) {
+    let Ok((entity, mut boss)) = boss_query.single_mut() else {
+        return;
+    };
+    for event in eaten_events.read().filter(|event| event.correct) {
+        let phase = boss.phase();
+        boss.health = boss.health.saturating_sub(event.blocks);
+        if boss.health == 0 {
+            commands.entity(entity).despawn();
+            return;
+        }
+        if boss.phase() != phase {
+            *round = RoundReview::default();
+            *sequence = SequenceProgress::default();
+            question.question = boss
+                .question(level.definition.questions)
+                .with_random_slots(&mut rng.questions);
+            question.round += 1;
+        }
+    }
+}
+
+/// Pick a row or column through the board to attack, and show where it will land.
+fn telegraph_attacks(
+    mut commands: Commands,
+    config: Res<GridConfig>,
+    mut rng: ResMut<RunRng>,
+    boss_query: Query<&Boss>,
+) {
+    let Ok(boss) = boss_query.single() else {
+        return;
+    };
+    if !boss.attack_timer.just_finished() {
+        return;
+    }
+    let (min, max) = config.layout.extent();
+    let line = if rng.enemies.random_bool(0.5) {
+        Line::Row(rng.enemies.random_range(min.row..=max.row))
+    } else {
+        Line::Column(rng.enemies.random_range(min.col..=max.col))
+    };
+    let (total_width, total_height, _, _) = calculate_grid_layout(&config);
+    let (position, size) = match line {
+        Line::Row(row) => {
+            let cell = grid_position_to_world(&config, &GridPosition { row, col: min.col });
+            let end = grid_position_to_world(&config, &GridPosition { row, col: max.col });
+            (
+                (cell + end) / 2.0,
+                Vec2::new(total_width, config.block_size.y),
+            )
+        }
+        Line::Column(col) => {
+            let cell = grid_position_to_world(&config, &GridPosition { row: min.row, col });
+            let end = grid_position_to_world(&config, &GridPosition { row: max.row, col });
+            (
+                (cell + end) / 2.0,
+                Vec2::new(config.block_size.x, total_height),
+            )
+        }
+    };
+
+    commands.spawn((
+        Name::new("Boss Attack"),
+        BossAttack {
+            line,
+            warning: Timer::from_seconds(TELEGRAPH_SECS, TimerMode::Once),
+        },
+        Sprite::from_color(WARNING_COLOR, size),
+        // Over everything but the player, who needs to see where to step
+        Transform::from_translation(position.extend(0.8)),
+        StateScoped(Screen::Gameplay),
+    ));
+}
+
+/// Hurt the player if they're still on an attacked row or column when the warning ends.
+fn land_attacks(
+    mut commands: Commands,
+    attack_query: Query<(Entity, &BossAttack)>,
+    mut player_query: Query<(&GridPosition, &mut Health), With<Player>>,
+) {
+    for (entity, attack) in &attack_query {
+        if !attack.warning.finished() {
+            continue;
+        }
+        for (player_pos, mut health) in &mut player_query {
+            if attack.line.contains(player_pos) {
+                health.take_damage(1);
+            }
+        }
+        commands.entity(entity).despawn();
+    }
+}
+
+fn damage_player_on_boss_contact(
+    boss_query: Query<&Boss>,
+    mut player_query: Query<(&GridPosition, &mut Health), With<Player>>,
+) {
+    let Ok(boss) = boss_query.single() else {
+        return;
+    };
+    for (player_pos, mut health) in &mut player_query {
+        if boss.definition.covers(player_pos) {
+            health.take_damage(1);
+        }
+    }
+}
+
+fn update_boss_health_bar(
+    boss_query: Query<&Boss>,
+    mut bar_query: Query<&mut Transform, With<BossHealthBar>>,
+) {
+    let fraction = boss_query.single().map_or(0.0, |boss| {
+        boss.health as f32 / boss.definition.health as f32
+    });
+    for mut transform in &mut bar_query {
+        transform.scale.x = fraction;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math_mono::{
+        game::{grid::GridConfig, tiles::Tile},
+        level::LEVELS,
+    };
+
+    #[test]
+    fn test_boss_phases_cycle() {
+        let questions = [Question::Even, Question::Odd];
+        let mut boss = Boss::new(BossDefinition {
+            name: "Test",
+            row: 0,
+            col: 0,
+            rows: 2,
+            cols: 2,
+            health: 9,
+            hits_per_phase: 3,
+            attack_interval_secs: 5.0,
+        });
+        assert_eq!(boss.question(&questions), Question::Even);
+        boss.health -= 3;
+        assert_eq!(boss.question(&questions), Question::Odd);
+        boss.health -= 3;
+        assert_eq!(boss.question(&questions), Question::Even);
+        assert!(boss.definition.covers(&GridPosition { row: 1, col: 1 }));
+        assert!(!boss.definition.covers(&GridPosition { row: 2, col: 1 }));
+    }
+
+    #[test]
+    fn test_boss_levels_fit_their_bosses() {
+        let boss_levels = LEVELS.iter().filter_map(|level| Some((level, level.boss?)));
+        let mut count = 0;
+        for (level, boss) in boss_levels {
+            count += 1;
+            assert_eq!(level.goal, Some(boss.health), "{}", level.id);
+            let config = GridConfig::for_level(level);
+            for cell in boss.cells() {
+                assert_eq!(config.layout.tile(cell), Some(Tile::Floor), "{}", level.id);
+            }
+            assert!(!boss.covers(&config.layout.start()), "{}", level.id);
+        }
+        assert!(count > 0);
+    }
+}
@@ -1 +1,197 @@
+//! Enemies that wander the grid and hurt the player on contact.
 
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng, seq::IndexedRandom};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    AppSystems, PausableSystems,
+    math_mono::{
+        components::{Damage, Enemy, GridPosition, Health, Player},
+        difficulty::Difficulty,
+        game::{
+            grid::{GridConfig, grid_position_to_world, is_valid_grid_position},
+            power_up::{ActivePowerUps, PowerUp},
+            tiles::Direction,
+        },
+        level::ActiveLevel,
+        run::{RunRng, online, start_run},
+    },
+    screens::Screen,
+};
+
+/// No more enemies spawn once this many are on the board.
+const MAX_ENEMIES: usize = 5;
+const ENEMY_COLOR: Color = Color::srgb(0.9, 0.2, 0.2);
+
+pub struct EnemyPlugin;
+
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(Screen::Gameplay),
+            reset_enemy_spawner.after(start_run),
+        )
+        .add_systems(
+            Update,
+            (
+                (tick_enemy_spawner, tick_enemy_steps).in_set(AppSystems::TickTimers),
+                (
+                    // Battles are a race between players only.
+                    spawn_enemies.run_if(not(online)),
+                    move_enemies,
+                    sync_enemies_to_grid_position,
+                    damage_player_on_contact,
+                )
+                    .chain()
+                    .in_set(AppSystems::Update),
+            )
+                .in_set(PausableSystems)
+                .run_if(in_state(Screen::Gameplay)),
+        );
+    }
+}
+
+#[derive(Resource)]
+pub struct EnemySpawner {
+    pub timer: Timer,
+    /// Seconds between each step a newly spawned enemy takes.
+    pub step_interval_secs: f32,
+}
+
+fn reset_enemy_spawner(
+    mut commands: Commands,
+    level: Res<ActiveLevel>,
+    difficulty: Res<Difficulty>,
+) {
+    commands.insert_resource(EnemySpawner {
+        timer: Timer::from_seconds(
+            difficulty.enemy_spawn_interval_secs(&level.definition),
+            TimerMode::Repeating,
+        ),
+        step_interval_secs: difficulty.enemy_step_interval_secs(&level.definition),
+    });
+}
+
+fn tick_enemy_spawner(time: Res<Time>, mut spawner: ResMut<EnemySpawner>) {
+    spawner.timer.tick(time.delta());
+}
+
+fn tick_enemy_steps(
+    time: Res<Time>,
+    power_ups: Res<ActivePowerUps>,
+    mut enemy_query: Query<&mut Enemy>,
+) {
+    // Frozen enemies hold still until the freeze wears off.
+    if power_ups.is_active(PowerUp::Freeze) {
+        return;
+    }
+    for mut enemy in &mut enemy_query {
+        enemy.step_timer.tick(time.delta());
+    }
+}
+
+/// Tries at finding an edge cell that is on the board and isn't a wall before giving up
+/// on a spawn.
+const SPAWN_ATTEMPTS: usize = 20;
+
+/// Pick a random cell along the outer edge of the grid.
+fn random_edge_position(rng: &mut impl Rng, config: &GridConfig) -> GridPosition {
+    match rng.random_range(0..4) {
+        0 => GridPosition {
+            row: 0,
+            col: rng.random_range(0..config.cols),
+        },
+        1 => GridPosition {
+            row: config.rows - 1,
+            col: rng.random_range(0..config.cols),
+        },
+        2 => GridPosition {
+            row: rng.random_range(0..config.rows),
+            col: 0,
+        },
+        _ => GridPosition {
+            row: rng.random_range(0..config.rows),
+            col: config.cols - 1,
+        },
+    }
+}
+
+fn spawn_enemies(
+    mut commands: Commands,
+    spawner: Res<EnemySpawner>,
+    mut rng: ResMut<RunRng>,
+    config: Res<GridConfig>,
+    enemy_query: Query<(), With<Enemy>>,
+) {
+    if !spawner.timer.just_finished() || enemy_query.iter().count() >= MAX_ENEMIES {
+        return;
+    }
+
+    let Some(grid_pos) = (0..SPAWN_ATTEMPTS)
+        .map(|_| random_edge_position(&mut rng.enemies, &config))
+        .find(|pos| is_valid_grid_position(pos, &config))
+    else {
+        return;
+    };
+    let walk_rng = ChaCha8Rng::seed_from_u64(rng.enemies.random());
+    let position = grid_position_to_world(&config, &grid_pos);
+
+    commands.spawn((
+        Name::new("Enemy"),
+        Enemy {
+            step_timer: Timer::from_seconds(spawner.step_interval_secs, TimerMode::Repeating),
+            rng: walk_rng,
+        },
+        Damage::default(),
+        grid_pos,
+        Sprite::from_color(ENEMY_COLOR, config.block_size * 0.6),
+        // Above the blocks, below the player
+        Transform::from_translation(position.extend(0.5)),
+        StateScoped(Screen::Gameplay),
+    ));
+}
+
+/// Random walk: each step moves in a random direction the layout allows, sliding on ice
+/// and taking teleporters like the player does.
+fn move_enemies(config: Res<GridConfig>, mut enemy_query: Query<(&mut Enemy, &mut GridPosition)>) {
+    for (mut enemy, mut grid_pos) in &mut enemy_query {
+        if !enemy.step_timer.just_finished() {
+            continue;
+        }
+        let steps: Vec<GridPosition> = [
+            Direction::Up,
+            Direction::Down,
+            Direction::Right,
+            Direction::Left,
+        ]
+        .into_iter()
+        .map(|direction| config.layout.step(*grid_pos, direction))
+        .filter(|pos| pos != &*grid_pos)
+        .collect();
+        if let Some(step) = steps.choose(&mut enemy.rng) {
+            *grid_pos = *step;
+        }
+    }
+}
+
+fn sync_enemies_to_grid_position(
+    config: Res<GridConfig>,
+    mut enemy_query: Query<(&GridPosition, &mut Transform), (With<Enemy>, Changed<GridPosition>)>,
+) {
+    for (grid_pos, mut transform) in &mut enemy_query {
+        let position = grid_position_to_world(&config, grid_pos);
+        transform.translation = position.extend(transform.translation.z);
+    }
+}
+
+fn damage_player_on_contact(
+    enemy_query: Query<(&GridPosition, &Damage), With<Enemy>>,
+    mut player_query: Query<(&GridPosition, &mut Health), With<Player>>,
+) {
+    for (player_pos, mut health) in &mut player_query {
+        if let Some((_, damage)) = enemy_query.iter().find(|(pos, _)| *pos == player_pos) {
+            health.take_damage(damage.amount);
+        }
+    }
+}
@@ -0,0 +1,434 @@
+//! Plain-language explanations of why a number does or doesn't answer a question, and
+//! step-by-step worked solutions for arithmetic.
+
+use crate::math_mono::game::{
+    math::{Expression, smallest_factor},
+    number::{Dimension, Number, Unit},
+    question::Question,
+};
+
+/// Explain why eating `value` is right or wrong for `question`.
+pub fn explain(question: &Question, value: Number) -> String {
+    let correct = question.is_correct(value);
+    let needs_whole_number = matches!(
+        question,
+        Question::Even | Question::Odd | Question::MultipleOf(_) | Question::Prime
+    );
+    let whole = match value.as_integer() {
+        Some(whole) => whole,
+        None if needs_whole_number => return format!("{value} is not a whole number"),
+        None => 0,
+    };
+    match *question {
+        Question::Even | Question::Odd => {
+            let parity = if *question == Question::Even {
+                "even"
+            } else {
+                "odd"
+            };
+            let not = if correct { "" } else { "not " };
+            match whole.rem_euclid(2) {
+                0 => format!(
+                    "{value} is {not}{parity} because {value} ÷ 2 = {}",
+                    whole / 2
+                ),
+                _ => format!("{value} is {not}{parity} because {value} ÷ 2 leaves remainder 1"),
+            }
+        }
+        Question::MultipleOf(n) if correct => {
+            format!(
+                "{value} is a multiple of {n} because {n} × {} = {value}",
+                whole / n
+            )
+        }
+        Question::MultipleOf(n) => format!(
+            "{value} is not a multiple of {n} because {value} ÷ {n} leaves remainder {}",
+            whole.rem_euclid(n)
+        ),
+        Question::Prime => match smallest_factor(whole) {
+            _ if whole < 2 => format!("{value} is not prime: primes are bigger than 1"),
+            Some(factor) => format!("{value} is not prime: {factor} × {}", whole / factor),
+            None => format!("{value} is prime: only 1 and {value} divide it"),
+        },
+        Question::GreaterThan(n) | Question::LessThan(n) => {
+            let dimension = n.unit().dimension();
+            let (relation, opposite) = match question {
+                Question::GreaterThan(_) => (dimension.greater_than(), dimension.less_than()),
+                _ => (dimension.less_than(), dimension.greater_than()),
+            };
+            if correct {
+                format!("{value} is {relation} {n}")
+            } else if value == n {
+                format!("{value} is not {relation} {n}: they are equal")
+            } else {
+                format!("{value} is not {relation} {n}: it is {opposite} {n}")
+            }
+        }
+        Question::Sum(a, b) if correct => format!("{a} + {b} = {value}"),
+        Question::Sum(a, b) => format!("{value} is not the answer: {a} + {b} = {}", a + b),
+        Question::Difference(a, b) if correct => format!("{a} - {b} = {value}"),
+        Question::Difference(a, b) => {
+            format!("{value} is not the answer: {a} - {b} = {}", a - b)
+        }
+        Question::Product(a, b) if correct => format!("{a} × {b} = {value}"),
+        Question::Product(a, b) => format!("{value} is not the answer: {a} × {b} = {}", a * b),
+        Question::WordProblem(problem) if correct => {
+            format!("{} = {value}", problem.working())
+        }
+        Question::WordProblem(problem) => format!(
+            "{value} is not the answer: {} = {}",
+            problem.working(),
+            problem.answer()
+        ),
+        Question::SolveFor { lhs, x } if correct => {
+            format!(
+                "x = {x} because {} = {}",
+                lhs.substituted(x),
+                lhs.evaluate(x)
+            )
+        }
+        Question::SolveFor { lhs, x } => format!(
+            "x is not {value}: {} = {}, not {}",
+            lhs.substituted(whole),
+            lhs.evaluate(whole),
+            lhs.evaluate(x)
+        ),
+        Question::Evaluate { x, .. } if correct => {
+            format!("That expression is {value} when x = {x}")
+        }
+        Question::Evaluate { x, value: target } => {
+            format!("That expression is {value} when x = {x}, not {target}")
+        }
+        Question::Sequence(sequence) => {
+            let terms = sequence.terms();
+            match terms.iter().position(|&term| Number::int(term) == value) {
+                Some(0) => format!("{value} comes first"),
+                Some(index) => format!("{value} comes after {}", terms[index - 1]),
+                None => {
+                    let terms: Vec<String> = terms.iter().map(i32::to_string).collect();
+                    format!("{value} is not in {}", terms.join(", "))
+                }
+            }
+        }
+        Question::MakeTotal(n) if value == Number::int(n) && correct => {
+            format!("The chain added up to {n}")
+        }
+        Question::MakeTotal(n) if whole > n => format!("{value} is more than {n} on its own"),
+        Question::MakeTotal(n) => format!("{value} took the running total past {n}"),
+        Question::EquivalentTo(n) if n.unit() != Unit::None => {
+            let not = if correct { "" } else { "not " };
+            match n.unit().dimension() {
+                Dimension::Length => format!("{value} is {not}equal to {n}: 1 m = 100 cm"),
+                _ => format!("{value} is {not}equal to {n}"),
+            }
+        }
+        Question::EquivalentTo(n) => {
+            let simplest = value.simplified();
+            let divisor = value.denom() / simplest.denom();
+            match (correct, divisor) {
+                (true, 1) => format!("{value} is equal to {n}"),
+                (true, _) => format!("{value} is equal to {n}: divide both by {divisor}"),
+                (false, 1) => format!("{value} is not equal to {n}"),
+                (false, _) => format!("{value} is not equal to {n}: it simplifies to {simplest}"),
+            }
+        }
+    }
+}
+
+/// Names of the digit places, from the right.
+const PLACES: [&str; 4] = ["ones", "tens", "hundreds", "thousands"];
+/// One of each digit place, as in "borrow 1 ten".
+const PLACE_UNITS: [&str; 4] = ["one", "ten", "hundred", "thousand"];
+
+/// Work out an arithmetic question one step at a time, ending with the answer.
+///
+/// Returns `None` for questions that aren't sums to work out.
+pub fn worked_solution(question: &Question) -> Option<Vec<String>> {
+    match *question {
+        Question::Sum(a, b) if a >= 0 && b >= 0 => Some(column_addition(a, b)),
+        Question::Difference(a, b) if a >= b && b >= 0 => Some(column_subtraction(a, b)),
+        Question::Product(a, b) if a > 0 && b > 0 => Some(times_table_decomposition(a, b)),
+        Question::SolveFor { lhs, x } => Some(balance_equation(lhs, x)),
+        _ => None,
+    }
+}
+
+fn digit(n: i32, place: usize) -> i32 {
+    n / 10_i32.pow(place as u32) % 10
+}
+
+fn place_count(n: i32) -> usize {
+    n.max(1).ilog10() as usize + 1
+}
+
+/// "Ones: 8 + 5 = 13. Write 3, carry 1", and so on up the places.
+fn column_addition(a: i32, b: i32) -> Vec<String> {
+    let places = place_count(a.max(b)).min(PLACES.len());
+    let mut steps = Vec::new();
+    let mut carry = 0;
+    for (place, name) in PLACES.iter().enumerate().take(places) {
+        let (top, bottom) = (digit(a, place), digit(b, place));
+        let total = top + bottom + carry;
+        let mut step = if carry > 0 {
+            format!("{}: {top} + {bottom} + 1 carried = {total}", title(name))
+        } else {
+            format!("{}: {top} + {bottom} = {total}", title(name))
+        };
+        carry = total / 10;
+        if carry > 0 && place + 1 < places {
+            step += &format!(". Write {}, carry 1", total % 10);
+        }
+        steps.push(step);
+    }
+    steps.push(format!("{a} + {b} = {}", a + b));
+    steps
+}
+
+/// "Ones: 2 is less than 6, so borrow 1 ten: 12 - 6 = 6", and so on up the places.
+fn column_subtraction(a: i32, b: i32) -> Vec<String> {
+    let places = place_count(a).min(PLACES.len());
+    let mut steps = Vec::new();
+    let mut borrowed = false;
+    for place in 0..places {
+        let (mut top, bottom) = (digit(a, place), digit(b, place));
+        let mut step = format!("{}: ", title(PLACES[place]));
+        if borrowed {
+            step += &format!("{top} - 1 borrowed = {}. ", top - 1);
+            top -= 1;
+        }
+        borrowed = top < bottom && place + 1 < places;
+        if borrowed {
+            step += &format!(
+                "{top} is less than {bottom}, so borrow 1 {}: {} - {bottom} = {}",
+                PLACE_UNITS[place + 1],
+                top + 10,
+                top + 10 - bottom
+            );
+        } else {
+            step += &format!("{top} - {bottom} = {}", top - bottom);
+        }
+        steps.push(step);
+    }
+    steps.push(format!("{a} - {b} = {}", a - b));
+    steps
+}
+
+/// Split the bigger factor into parts that are easy to multiply, then add the parts up.
+fn times_table_decomposition(a: i32, b: i32) -> Vec<String> {
+    let (big, small) = if a >= b { (a, b) } else { (b, a) };
+    let product = a * b;
+    let (first, second) = if big > 10 && big % 10 != 0 {
+        (big - big % 10, big % 10)
+    } else if big > 5 && big < 10 {
+        (5, big - 5)
+    } else if big % 10 == 0 {
+        return vec![
+            format!("{} × {small} = {}", big / 10, big / 10 * small),
+            format!("Add a zero: {a} × {b} = {product}"),
+        ];
+    } else {
+        // Small enough to add up.
+        let terms = vec![big.to_string(); small as usize].join(" + ");
+        return vec![
+            format!("{terms} = {product}"),
+            format!("{a} × {b} = {product}"),
+        ];
+    };
+    vec![
+        format!("Split {big} into {first} + {second}"),
+        format!("{first} × {small} = {}", first * small),
+        format!("{second} × {small} = {}", second * small),
+        format!("{} + {} = {product}", first * small, second * small),
+        format!("{a} × {b} = {product}"),
+    ]
+}
+
+/// Undo what was done to `x` one step at a time, doing the same to both sides.
+fn balance_equation(lhs: Expression, x: i32) -> Vec<String> {
+    let rhs = lhs.evaluate(x);
+    let (coefficient, constant) = (lhs.coefficient(), lhs.constant());
+    let mut steps = vec![format!("{lhs} = {rhs}")];
+    let undo_constant = |steps: &mut Vec<String>, side: Expression, rhs: i32| {
+        let balanced = rhs - constant;
+        match constant {
+            0 => return balanced,
+            c if c > 0 => steps.push(format!("Take {c} from both sides: {side} = {balanced}")),
+            c => steps.push(format!("Add {} to both sides: {side} = {balanced}", -c)),
+        }
+        balanced
+    };
+    match lhs {
+        Expression::Linear { .. } => {
+            let rhs = undo_constant(&mut steps, Expression::linear(coefficient, 0), rhs);
+            if coefficient != 1 {
+                steps.push(format!(
+                    "Divide both sides by {coefficient}: x = {}",
+                    rhs / coefficient
+                ));
+            }
+        }
+        Expression::Bracketed { .. } => {
+            let divided = rhs / coefficient;
+            steps.push(format!(
+                "Divide both sides by {coefficient}: {} = {divided}",
+                Expression::linear(1, constant)
+            ));
+            undo_constant(&mut steps, Expression::linear(1, 0), divided);
+        }
+    }
+    steps
+}
+
+/// Capitalize the first letter of `word`.
+fn title(word: &str) -> String {
+    let mut chars = word.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explain_mistakes() {
+        assert_eq!(
+            explain(&Question::Even, Number::int(21)),
+            "21 is not even because 21 ÷ 2 leaves remainder 1"
+        );
+        assert_eq!(
+            explain(&Question::Prime, Number::int(21)),
+            "21 is not prime: 3 × 7"
+        );
+        assert_eq!(
+            explain(&Question::MultipleOf(3), Number::int(22)),
+            "22 is not a multiple of 3 because 22 ÷ 3 leaves remainder 1"
+        );
+        assert_eq!(
+            explain(&Question::Sum(10, 10), Number::int(21)),
+            "21 is not the answer: 10 + 10 = 20"
+        );
+    }
+
+    #[test]
+    fn test_explain_correct_answers() {
+        assert_eq!(
+            explain(&Question::Odd, Number::int(21)),
+            "21 is odd because 21 ÷ 2 leaves remainder 1"
+        );
+        assert_eq!(
+            explain(&Question::MultipleOf(3), Number::int(21)),
+            "21 is a multiple of 3 because 3 × 7 = 21"
+        );
+        assert_eq!(
+            explain(&Question::Prime, Number::int(13)),
+            "13 is prime: only 1 and 13 divide it"
+        );
+    }
+
+    #[test]
+    fn test_worked_addition_carries() {
+        assert_eq!(
+            worked_solution(&Question::Sum(38, 45)).unwrap(),
+            [
+                "Ones: 8 + 5 = 13. Write 3, carry 1",
+                "Tens: 3 + 4 + 1 carried = 8",
+                "38 + 45 = 83",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_worked_subtraction_borrows() {
+        assert_eq!(
+            worked_solution(&Question::Difference(72, 36)).unwrap(),
+            [
+                "Ones: 2 is less than 6, so borrow 1 ten: 12 - 6 = 6",
+                "Tens: 7 - 1 borrowed = 6. 6 - 3 = 3",
+                "72 - 36 = 36",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_worked_multiplication_decomposes() {
+        assert_eq!(
+            worked_solution(&Question::Product(8, 7)).unwrap(),
+            [
+                "Split 8 into 5 + 3",
+                "5 × 7 = 35",
+                "3 × 7 = 21",
+                "35 + 21 = 56",
+                "8 × 7 = 56",
+            ]
+        );
+        assert_eq!(worked_solution(&Question::Even), None);
+    }
+
+    #[test]
+    fn test_explain_fractions() {
+        let half = Question::EquivalentTo(Number::fraction(1, 2));
+        assert_eq!(
+            explain(&half, Number::fraction(3, 6)),
+            "3/6 is equal to 1/2: divide both by 3"
+        );
+        assert_eq!(
+            explain(&half, Number::fraction(2, 6)),
+            "2/6 is not equal to 1/2: it simplifies to 1/3"
+        );
+        assert_eq!(
+            explain(&Question::Even, Number::fraction(1, 2)),
+            "1/2 is not a whole number"
+        );
+    }
+
+    #[test]
+    fn test_solving_equations() {
+        let question = Question::SolveFor {
+            lhs: Expression::linear(3, 4),
+            x: 5,
+        };
+        assert_eq!(
+            explain(&question, Number::int(6)),
+            "x is not 6: 3 × 6 + 4 = 22, not 19"
+        );
+        assert_eq!(
+            worked_solution(&question).unwrap(),
+            [
+                "3x + 4 = 19",
+                "Take 4 from both sides: 3x = 15",
+                "Divide both sides by 3: x = 5",
+            ]
+        );
+        let bracketed = Question::SolveFor {
+            lhs: Expression::bracketed(2, -3),
+            x: 7,
+        };
+        assert_eq!(
+            worked_solution(&bracketed).unwrap(),
+            [
+                "2(x - 3) = 8",
+                "Divide both sides by 2: x - 3 = 4",
+                "Add 3 to both sides: x = 7",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_explain_measurements() {
+        let later = Question::GreaterThan(Number::clock(2, 30));
+        assert_eq!(
+            explain(&later, Number::clock(2, 15)),
+            "2:15 is not later than 2:30: it is earlier than 2:30"
+        );
+        let metre_and_a_half = Question::EquivalentTo(Number::decimal(15, 1).in_unit(Unit::Metres));
+        assert_eq!(
+            explain(
+                &metre_and_a_half,
+                Number::int(150).in_unit(Unit::Centimetres)
+            ),
+            "150 cm is equal to 1.5 m: 1 m = 100 cm"
+        );
+    }
+}
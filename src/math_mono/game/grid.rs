@@ -3,10 +3,17 @@ use bevy::prelude::*;
 use rand::Rng;
 
 use crate::math_mono::common::{Position, get_primary_window_size};
+use crate::math_mono::difficulty::Difficulty;
+use crate::math_mono::game::number::Number;
+use crate::math_mono::game::question::{CurrentQuestion, Question};
+use crate::math_mono::game::sum_target::random_combination;
+use crate::math_mono::game::tiles::{Layout, Tile};
+use crate::math_mono::level::LevelDefinition;
+use crate::math_mono::run::RunRng;
 
 use crate::screens::Screen;
 
-use crate::math_mono::components::{NumberBlock, GridPosition};
+use crate::math_mono::components::{GridPosition, NumberBlock};
 
 /// Grid configuration constants
 pub const GRID_ROWS: i32 = 7;
@@ -16,6 +23,15 @@ pub const BLOCK_WIDTH: f32 = 100.;
 pub const BLOCK_SIZE: Vec2 = Vec2::new(BLOCK_WIDTH, BLOCK_HEIGHT);
 
 pub const GAP_BETWEEN_BLOCKS: f32 = 3.0;
+/// Boards smaller than the default grid scale their blocks up by at most this much.
+const MAX_BLOCK_SCALE: f32 = 1.25;
+
+/// Range of the whole numbers written on blocks. Other kinds of numbers are scaled to it,
+/// see [`Question::random_tile`].
+pub const MIN_BLOCK_VALUE: i32 = 1;
+pub const MAX_BLOCK_VALUE: i32 = 100;
+/// Every board has at least this many correct answers on it
+pub const MIN_CORRECT_BLOCKS: usize = 5;
 
 pub struct GridPlugin;
 
@@ -23,7 +39,12 @@ impl Plugin for GridPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<GridConfig>().add_systems(
             Update,
-            animate_colors_to_music.run_if(in_state(Screen::Gameplay)),
+            (
+                animate_colors_to_music,
+                refill_board_for_new_question
+                    .run_if(resource_exists_and_changed::<CurrentQuestion>),
+            )
+                .run_if(in_state(Screen::Gameplay)),
         );
     }
 }
@@ -34,6 +55,9 @@ pub struct GridConfig {
     pub cols: i32,
     pub block_size: Vec2,
     pub gap_between_blocks: f32,
+    /// Which cells hold number blocks, which walls or special floor, and which are
+    /// missing from the board.
+    pub layout: Layout,
 }
 
 impl Default for GridConfig {
@@ -43,36 +67,208 @@ impl Default for GridConfig {
             cols: GRID_COLS,
             block_size: BLOCK_SIZE,
             gap_between_blocks: GAP_BETWEEN_BLOCKS,
+            layout: Layout::open(GRID_ROWS, GRID_COLS),
         }
     }
 }
 
-/// Calculate the total size of the grid and bottom-left coordinate to center it
+impl GridConfig {
+    /// The grid laid out the way `level` draws it, with blocks scaled so the board takes
+    /// up about as much room as the default grid.
+    pub fn for_level(level: &LevelDefinition) -> Self {
+        if level.layout.is_empty() {
+            return Self::default();
+        }
+        let layout = Layout::parse(level.layout)
+            .unwrap_or_else(|error| panic!("layout of level `{}`: {error}", level.id));
+        let (min, max) = layout.extent();
+        let span = (max.row - min.row + 1).max(max.col - min.col + 1);
+        let scale = (GRID_ROWS.max(GRID_COLS) as f32 / span as f32).min(MAX_BLOCK_SCALE);
+        Self {
+            rows: layout.rows(),
+            cols: layout.cols(),
+            block_size: BLOCK_SIZE * scale,
+            gap_between_blocks: GAP_BETWEEN_BLOCKS * scale,
+            layout,
+        }
+    }
+
+    fn tile(&self, row: i32, col: i32) -> Option<Tile> {
+        self.layout.tile(GridPosition { row, col })
+    }
+}
+
+/// Calculate the total size of the board and bottom-left coordinate to center it.
+///
+/// Only the cells on the board count, so a board with missing cells is centred on the
+/// cells it has. See [`calculate_block_center`].
 pub fn calculate_grid_layout(config: &GridConfig) -> (f32, f32, f32, f32) {
-    let total_width = config.cols as f32 * (config.block_size.x + config.gap_between_blocks)
-        - config.gap_between_blocks;
-    let total_height = config.rows as f32 * (config.block_size.y + config.gap_between_blocks)
-        - config.gap_between_blocks;
+    let (min, max) = config.layout.extent();
+    let cols = max.col - min.col + 1;
+    let rows = max.row - min.row + 1;
+    let total_width =
+        cols as f32 * (config.block_size.x + config.gap_between_blocks) - config.gap_between_blocks;
+    let total_height =
+        rows as f32 * (config.block_size.y + config.gap_between_blocks) - config.gap_between_blocks;
     let bottom_left_x = -total_width / 2.0;
     let bottom_left_y = -total_height / 2.0;
     (total_width, total_height, bottom_left_x, bottom_left_y)
 }
 
 /// Calculates the center position of a single block based on its row and column.
+///
+/// `grid_bottom_left` is the corner of the board's bottom-left cell, which is not
+/// `(0, 0)` when rows or columns at the edge of the layout are left out.
 pub fn calculate_block_center(
     config: &GridConfig,
     grid_bottom_left: Vec2,
     row: i32,
     col: i32,
 ) -> Vec2 {
+    let (min, _) = config.layout.extent();
     let bottom_left_of_block = grid_bottom_left
         + Vec2::new(
-            col as f32 * (config.block_size.x + config.gap_between_blocks),
-            row as f32 * (config.block_size.y + config.gap_between_blocks),
+            (col - min.col) as f32 * (config.block_size.x + config.gap_between_blocks),
+            (row - min.row) as f32 * (config.block_size.y + config.gap_between_blocks),
         );
     bottom_left_of_block + config.block_size / 2.0
 }
 
+/// Calculates the world position of the block under a grid position.
+pub fn grid_position_to_world(config: &GridConfig, pos: &GridPosition) -> Vec2 {
+    let (_, _, bottom_left_x, bottom_left_y) = calculate_grid_layout(config);
+    calculate_block_center(
+        config,
+        Vec2::new(bottom_left_x, bottom_left_y),
+        pos.row,
+        pos.col,
+    )
+}
+
+/// How a board's numbers are rolled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoardParams {
+    /// Largest whole number on the board, at most [`MAX_BLOCK_VALUE`].
+    pub max_value: i32,
+    /// Chance, from 0 to 1, that a wrong number is placed just next to a right one.
+    pub distractor_closeness: f32,
+}
+
+impl Default for BoardParams {
+    fn default() -> Self {
+        Self {
+            max_value: MAX_BLOCK_VALUE,
+            distractor_closeness: 0.0,
+        }
+    }
+}
+
+/// How far from a right answer a close distractor may be.
+const MAX_DISTRACTOR_DISTANCE: i32 = 3;
+
+/// Roll the numbers for a board of `count` blocks, making sure enough of them answer `question`.
+pub fn generate_board_values(
+    question: &Question,
+    count: usize,
+    params: &BoardParams,
+    rng: &mut impl Rng,
+) -> Vec<Number> {
+    let mut values: Vec<Number> = (0..count)
+        .map(|_| question.random_tile(params.max_value, rng))
+        .collect();
+
+    if let Some(ordered) = question.ordered_answers() {
+        // Each number in a sequence goes on the board once, so eating them all in order
+        // clears it.
+        for value in &mut values {
+            while question.is_correct(*value) {
+                *value = question.random_tile(params.max_value, rng);
+            }
+        }
+        let mut free: Vec<usize> = (0..count).collect();
+        for answer in ordered.into_iter().take(count) {
+            values[free.swap_remove(rng.random_range(0..free.len()))] = answer;
+        }
+    } else {
+        let min_correct = MIN_CORRECT_BLOCKS.min(count);
+        while values
+            .iter()
+            .filter(|&&value| question.is_correct(value))
+            .count()
+            < min_correct
+        {
+            let index = rng.random_range(0..count);
+            if !question.is_correct(values[index]) {
+                values[index] = question.random_answer(params.max_value, rng);
+            }
+        }
+    }
+
+    if let Some(target) = question.target_total() {
+        // Make sure at least one chain adds up to the total.
+        let mut free: Vec<usize> = (0..count).collect();
+        for part in random_combination(target, rng).into_iter().take(count) {
+            values[free.swap_remove(rng.random_range(0..free.len()))] = Number::int(part);
+        }
+    }
+
+    // Move some wrong numbers right next to right ones, where they are easier to mistake.
+    let answers: Vec<Number> = values
+        .iter()
+        .copied()
+        .filter(|&value| question.is_correct(value))
+        .collect();
+    for value in &mut values {
+        if question.is_correct(*value) || !rng.random_bool(params.distractor_closeness.into()) {
+            continue;
+        }
+        let answer = answers[rng.random_range(0..answers.len())];
+        let offset = rng.random_range(1..=MAX_DISTRACTOR_DISTANCE);
+        let steps = if rng.random_bool(0.5) {
+            offset
+        } else {
+            -offset
+        };
+        let near = question.nudged(answer, steps);
+        if question.fits_board(near, params.max_value) && !question.is_correct(near) {
+            *value = near;
+        }
+    }
+
+    // Put likely slips on the board too, in place of wrong numbers picked at random.
+    for near_miss in question.near_misses() {
+        if !question.fits_board(near_miss, params.max_value) || question.is_correct(near_miss) {
+            continue;
+        }
+        let index = rng.random_range(0..count);
+        if !question.is_correct(values[index]) {
+            values[index] = near_miss;
+        }
+    }
+    values
+}
+
+/// Roll a fresh board for `question`: the number and text of every number block, in
+/// grid order. The battle server rolls boards the same way, so a seed gives everyone
+/// the same board.
+pub fn roll_board(
+    config: &GridConfig,
+    question: &Question,
+    params: &BoardParams,
+    rng: &mut impl Rng,
+) -> Vec<(GridPosition, Number, String)> {
+    let cells: Vec<GridPosition> = (0..config.rows)
+        .flat_map(|row| (0..config.cols).map(move |col| GridPosition { row, col }))
+        .filter(|pos| config.tile(pos.row, pos.col) == Some(Tile::Number))
+        .collect();
+    let values = generate_board_values(question, cells.len(), params, rng);
+    cells
+        .into_iter()
+        .zip(values)
+        .map(|(pos, value)| (pos, value, question.tile_text(value, rng)))
+        .collect()
+}
+
 // Renamed the function from spawn_ball to spawn_blocks
 pub fn spawn_grid(
     mut commands: Commands,
@@ -81,35 +277,77 @@ pub fn spawn_grid(
     windows: Query<&Window>,
     config: Res<GridConfig>,
     asset_server: Res<AssetServer>,
+    question: Res<CurrentQuestion>,
+    difficulty: Res<Difficulty>,
+    mut rng: ResMut<RunRng>,
 ) {
-    println!("Spawning blocks...");
-
     let window_size = get_primary_window_size(windows.single().unwrap());
 
-
-    let (total_width, total_height, bottom_left_x, bottom_left_y) = calculate_grid_layout(&config);
-    let total_size = Vec2::new(total_width, total_height);
+    let (_, _, bottom_left_x, bottom_left_y) = calculate_grid_layout(&config);
     let grid_bottom_left = Vec2::new(bottom_left_x, bottom_left_y);
-    println!("Total grid size: {:?}", total_size);
-    println!("Grid bottom-left corner: {:?}", grid_bottom_left);
 
     spawn_background(&mut commands, &mut meshes, &mut materials, &window_size);
 
     let font: Handle<Font> = asset_server.load("fonts/MonofurNerdFont-Bold.ttf");
-    let mut rng = rand::rng();
+    let params = difficulty.board_params(&question.question);
+    let mut board = roll_board(&config, &question.question, &params, &mut rng.board).into_iter();
 
     // --- Loop to spawn multiple blocks in a grid ---
-    for row in 0..GRID_ROWS {
-        for col in 0..GRID_COLS {
-            let value = rng.random_range(1..=100);
+    for row in 0..config.rows {
+        for col in 0..config.cols {
+            let Some(tile) = config.tile(row, col) else {
+                continue;
+            };
+            if tile != Tile::Number {
+                let position = calculate_block_center(&config, grid_bottom_left, row, col);
+                spawn_tile(&mut commands, &font, position, config.block_size, tile);
+                continue;
+            }
+            let (_, value, text) = board.next().unwrap();
             let block_center_position = calculate_block_center(&config, grid_bottom_left, row, col);
-            println!("Block center position: {:?}", block_center_position);
 
-            spawn_block(&mut commands, &font, block_center_position, value, row, col);
+            spawn_block(
+                &mut commands,
+                &font,
+                block_center_position,
+                value,
+                text,
+                config.block_size,
+                row,
+                col,
+            );
         }
     }
 }
 
+/// Spawn a tile that isn't a number block: a wall, floor, ice and so on.
+fn spawn_tile(
+    commands: &mut Commands,
+    font: &Handle<Font>,
+    position: Vec2,
+    size: Vec2,
+    tile: Tile,
+) {
+    let mut entity = commands.spawn((
+        Name::new(format!("{tile:?} Tile")),
+        Sprite::from_color(tile.color(), size),
+        Transform::from_translation(position.extend(0.0)),
+        StateScoped(Screen::Gameplay),
+    ));
+    if let Some(label) = tile.label() {
+        entity.with_child((
+            Text2d::new(label),
+            TextFont {
+                font: font.clone(),
+                font_size: 40.0,
+                ..default()
+            },
+            TextColor(Color::BLACK),
+            Transform::from_translation(Vec3::Z * 0.1),
+        ));
+    }
+}
+
 /// Helper function to spawn just the background.
 fn spawn_background(
     commands: &mut Commands,
@@ -134,7 +372,9 @@ fn spawn_block(
     commands: &mut Commands,
     font: &Handle<Font>,
     position: Vec2,
-    value: i32,
+    value: Number,
+    text: String,
+    size: Vec2,
     row: i32,
     col: i32,
 ) {
@@ -149,10 +389,14 @@ fn spawn_block(
 
     commands
         .spawn((
-            NumberBlock { value, is_eaten: false },
+            NumberBlock {
+                value,
+                is_eaten: false,
+                claim: None,
+            },
             Sprite {
                 color,
-                custom_size: Some(Vec2::new(100., 100.)),
+                custom_size: Some(size),
                 ..default()
             },
             Transform::from_translation(p_vec3),
@@ -162,7 +406,7 @@ fn spawn_block(
         ))
         .with_children(|builder| {
             builder.spawn((
-                Text2d::new(value.to_string()),
+                Text2d::new(text),
                 TextFont {
                     font: font.clone(),
                     ..default()
@@ -173,25 +417,56 @@ fn spawn_block(
         });
 }
 
-fn animate_colors_to_music(mut query: Query<(&NumberBlock, &mut Sprite)>, time: Res<Time>) {
+/// Put fresh numbers on every block when a new question starts.
+fn refill_board_for_new_question(
+    config: Res<GridConfig>,
+    question: Res<CurrentQuestion>,
+    difficulty: Res<Difficulty>,
+    mut rng: ResMut<RunRng>,
+    mut block_query: Query<(&mut NumberBlock, &GridPosition, &Children)>,
+    mut text_query: Query<&mut Text2d>,
+) {
+    // The board spawned with the first question already has numbers for it.
+    if question.is_added() {
+        return;
+    }
+
+    // Fill in grid order so the same seed always produces the same board.
+    let mut blocks: Vec<_> = block_query.iter_mut().collect();
+    blocks.sort_by_key(|(_, pos, _)| (pos.row, pos.col));
+    let params = difficulty.board_params(&question.question);
+    let board = roll_board(&config, &question.question, &params, &mut rng.board);
+
+    for ((mut block, _, children), (_, value, tile_text)) in blocks.into_iter().zip(board) {
+        *block = NumberBlock {
+            value,
+            is_eaten: false,
+            claim: None,
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(child) {
+                text.0.clone_from(&tile_text);
+            }
+        }
+    }
+}
+
+pub fn animate_colors_to_music(mut query: Query<(&NumberBlock, &mut Sprite)>, time: Res<Time>) {
     for (block, mut sprite) in &mut query {
         let time_value = time.elapsed_secs();
-        let red = (time_value + block.value as f32 * 0.1).sin() * 0.5 + 0.5;
-        let green = (time_value + block.value as f32 * 0.2).sin() * 0.5 + 0.5;
+        let red = (time_value + block.value.to_f32() * 0.1).sin() * 0.5 + 0.5;
+        let green = (time_value + block.value.to_f32() * 0.2).sin() * 0.5 + 0.5;
 
         sprite.color = Color::srgb(red, green, 0.8);
     }
 }
 
-// /// Check if a grid position is valid (within bounds)
-// pub fn is_valid_grid_position(pos: &GridPosition, config: &GridConfig) -> bool {
-//     pos.row >= 0 && pos.row < config.rows && pos.col >= 0 && pos.col < config.cols
-// }
-
-/// Clamp a grid position to valid bounds
-pub fn clamp_grid_position(pos: &mut GridPosition, config: &GridConfig) {
-    pos.row = pos.row.clamp(0, config.rows - 1);
-    pos.col = pos.col.clamp(0, config.cols - 1);
+/// Check if a grid position is valid (on the board, and not a wall)
+pub fn is_valid_grid_position(pos: &GridPosition, config: &GridConfig) -> bool {
+    config
+        .layout
+        .tile(*pos)
+        .is_some_and(|tile| tile != Tile::Wall)
 }
 
 // /// Generate a random number for a grid cell
@@ -211,6 +486,7 @@ mod tests {
             cols: 2,
             block_size: Vec2::new(100.0, 100.0),
             gap_between_blocks: 10.0,
+            layout: Layout::open(2, 2),
         };
         // Total size should be (2 * 100 + 1 * 10) = 210
         let (total_width, total_height, bottom_left_x, bottom_left_y) =
@@ -241,17 +517,85 @@ mod tests {
         assert!((pos_0_0 - expected_pos).length() < 1e-6);
     }
 
-    // #[test]
-    // fn test_is_valid_grid_position() {
-    //     let config = GridConfig::default();
+    #[test]
+    fn test_generate_board_values_is_seeded_and_answerable() {
+        use rand::{SeedableRng, rngs::StdRng};
 
-    //     let valid_pos = GridPosition { row: 3, col: 3 };
-    //     assert!(is_valid_grid_position(&valid_pos, &config));
+        let question = Question::Sum(10, 10);
+        let params = BoardParams {
+            max_value: 40,
+            distractor_closeness: 0.5,
+        };
+        let first = generate_board_values(&question, 49, &params, &mut StdRng::seed_from_u64(42));
+        let second = generate_board_values(&question, 49, &params, &mut StdRng::seed_from_u64(42));
+
+        assert_eq!(first, second);
+        assert!(
+            first
+                .iter()
+                .filter(|&&value| value == Number::int(20))
+                .count()
+                >= MIN_CORRECT_BLOCKS
+        );
+        assert!(first.iter().all(|&value| question.fits_board(value, 40)));
+    }
 
-    //     let invalid_pos = GridPosition { row: -1, col: 3 };
-    //     assert!(!is_valid_grid_position(&invalid_pos, &config));
+    #[test]
+    fn test_level_layouts_are_playable() {
+        use crate::math_mono::level::{DAILY_LEVEL, LEVELS};
+
+        for level in LEVELS.iter().chain([&DAILY_LEVEL]) {
+            let config = GridConfig::for_level(level);
+            let start = config.layout.start();
+            assert!(is_valid_grid_position(&start, &config), "{}", level.id);
+            let blocks = config
+                .layout
+                .cells()
+                .filter(|&pos| config.layout.tile(pos) == Some(Tile::Number));
+            assert!(blocks.count() >= 2 * MIN_CORRECT_BLOCKS, "{}", level.id);
+        }
+    }
 
-    //     let invalid_pos2 = GridPosition { row: 3, col: 10 };
-    //     assert!(!is_valid_grid_position(&invalid_pos2, &config));
-    // }
+    #[test]
+    fn test_masked_board_is_centred_and_scaled() {
+        let config = GridConfig {
+            layout: Layout::parse(&[
+                "       ", //
+                "   .   ", //
+                "  ...  ", //
+                "   .   ", //
+                "       ",
+            ])
+            .unwrap(),
+            ..default()
+        };
+        let (total_width, total_height, _, _) = calculate_grid_layout(&config);
+        assert_eq!(total_width, 3.0 * BLOCK_WIDTH + 2.0 * GAP_BETWEEN_BLOCKS);
+        assert_eq!(total_height, total_width);
+        // The middle of the cross is the middle of the screen.
+        let middle = grid_position_to_world(&config, &GridPosition { row: 2, col: 3 });
+        assert!(middle.length() < 1e-4);
+
+        let level = LevelDefinition {
+            layout: &["  .  ", " ... ", "..#..", " ... ", "  .  "],
+            ..crate::math_mono::level::LEVELS[0]
+        };
+        let scaled = GridConfig::for_level(&level);
+        assert_eq!(scaled.block_size, BLOCK_SIZE * MAX_BLOCK_SCALE);
+        assert_eq!(scaled.layout.start(), GridPosition { row: 1, col: 2 });
+    }
+
+    #[test]
+    fn test_is_valid_grid_position() {
+        let config = GridConfig::default();
+
+        let valid_pos = GridPosition { row: 3, col: 3 };
+        assert!(is_valid_grid_position(&valid_pos, &config));
+
+        let invalid_pos = GridPosition { row: -1, col: 3 };
+        assert!(!is_valid_grid_position(&invalid_pos, &config));
+
+        let invalid_pos2 = GridPosition { row: 3, col: 10 };
+        assert!(!is_valid_grid_position(&invalid_pos2, &config));
+    }
 }
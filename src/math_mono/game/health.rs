@@ -1 +1,72 @@
+//! Player health, damage and running out of it.
 
+use bevy::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    math_mono::components::{Health, Player},
+    screens::Screen,
+};
+
+/// Seconds the player can't be hurt again after taking damage.
+const INVULNERABILITY_SECS: f32 = 1.0;
+
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                tick_invulnerability.in_set(AppSystems::TickTimers),
+                end_run_on_death.in_set(AppSystems::Update),
+            )
+                .in_set(PausableSystems)
+                .run_if(in_state(Screen::Gameplay)),
+        );
+    }
+}
+
+impl Health {
+    /// Take `amount` damage unless recently hurt or shielded. A shield breaks on the hit.
+    /// Returns whether any damage was taken.
+    pub fn take_damage(&mut self, amount: i32) -> bool {
+        if self.invulnerable_timer > 0.0 {
+            return false;
+        }
+        if self.shield_secs > 0.0 {
+            self.shield_secs = 0.0;
+            self.invulnerable_timer = INVULNERABILITY_SECS;
+            return false;
+        }
+        self.current = (self.current - amount).max(0);
+        self.hits_taken += 1;
+        self.invulnerable_timer = INVULNERABILITY_SECS;
+        true
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0
+    }
+}
+
+fn tick_invulnerability(time: Res<Time>, mut health_query: Query<&mut Health>) {
+    for mut health in &mut health_query {
+        if health.invulnerable_timer > 0.0 {
+            health.invulnerable_timer = (health.invulnerable_timer - time.delta_secs()).max(0.0);
+        }
+        if health.shield_secs > 0.0 {
+            health.shield_secs = (health.shield_secs - time.delta_secs()).max(0.0);
+        }
+    }
+}
+
+fn end_run_on_death(
+    player_query: Query<&Health, With<Player>>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    // The run goes on while anyone is still standing.
+    if !player_query.is_empty() && player_query.iter().all(Health::is_dead) {
+        next_screen.set(Screen::GameOver);
+    }
+}
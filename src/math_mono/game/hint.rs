@@ -0,0 +1,263 @@
+//! Hints: point the player at the nearest right answer on the board.
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+
+use crate::{
+    AppSystems, PausableSystems,
+    math_mono::{
+        components::{GridPosition, Health, NumberBlock, Player, RunningTotal},
+        game::{
+            grid::{GridConfig, grid_position_to_world},
+            number::Number,
+            question::CurrentQuestion,
+            sequence::SequenceProgress,
+            sum_target::can_finish_with,
+            tiles::Layout,
+        },
+    },
+    screens::Screen,
+};
+
+/// Hints the player may use in each level.
+pub const HINTS_PER_LEVEL: u32 = 3;
+/// Seconds to wait after a hint before the next one.
+const HINT_COOLDOWN_SECS: f32 = 5.0;
+const HINT_COLOR: Color = Color::srgb(1.0, 0.85, 0.1);
+
+pub struct HintPlugin;
+
+impl Plugin for HintPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HintUsed>();
+        app.add_systems(OnEnter(Screen::Gameplay), reset_hints);
+        app.add_systems(
+            Update,
+            (
+                tick_hint_cooldown.in_set(AppSystems::TickTimers),
+                use_hint
+                    .run_if(input_just_pressed(KeyCode::KeyH))
+                    .in_set(AppSystems::RecordInput),
+                (
+                    drop_hint_when_answered,
+                    drop_hint_on_new_question.run_if(resource_changed::<CurrentQuestion>),
+                    draw_hint,
+                )
+                    .chain()
+                    .in_set(AppSystems::Update),
+            )
+                .in_set(PausableSystems)
+                .run_if(in_state(Screen::Gameplay)),
+        );
+    }
+}
+
+/// Sent whenever the player uses a hint.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HintUsed;
+
+/// Hints left in the current level, and the block being pointed at.
+#[derive(Resource, Debug)]
+pub struct Hints {
+    pub uses_left: u32,
+    cooldown: Timer,
+    target: Option<Entity>,
+}
+
+impl Hints {
+    pub fn is_ready(&self) -> bool {
+        self.uses_left > 0 && self.cooldown.finished()
+    }
+}
+
+impl Default for Hints {
+    fn default() -> Self {
+        let mut cooldown = Timer::from_seconds(HINT_COOLDOWN_SECS, TimerMode::Once);
+        // The first hint is available straight away.
+        cooldown.set_elapsed(cooldown.duration());
+        Self {
+            uses_left: HINTS_PER_LEVEL,
+            cooldown,
+            target: None,
+        }
+    }
+}
+
+fn reset_hints(mut commands: Commands) {
+    commands.insert_resource(Hints::default());
+}
+
+fn tick_hint_cooldown(time: Res<Time>, hints: Option<ResMut<Hints>>) {
+    if let Some(mut hints) = hints {
+        hints.cooldown.tick(time.delta());
+    }
+}
+
+/// The closest uneaten block to `from` that `accepts`, counting moves around the layout.
+/// Blocks that can't be reached are skipped.
+fn nearest_correct_block<'a>(
+    accepts: impl Fn(Number) -> bool,
+    layout: &Layout,
+    from: &GridPosition,
+    blocks: impl IntoIterator<Item = (Entity, &'a NumberBlock, &'a GridPosition)>,
+) -> Option<Entity> {
+    blocks
+        .into_iter()
+        .filter(|(_, block, _)| !block.is_eaten && accepts(block.value))
+        .filter_map(|(entity, _, pos)| {
+            let moves = layout.path(*from, *pos)?.len();
+            Some((entity, (moves, pos.row, pos.col)))
+        })
+        .min_by_key(|(_, key)| *key)
+        .map(|(entity, _)| entity)
+}
+
+fn use_hint(
+    config: Res<GridConfig>,
+    question: Res<CurrentQuestion>,
+    sequence: Res<SequenceProgress>,
+    mut hints: ResMut<Hints>,
+    player_query: Query<(&GridPosition, &RunningTotal, &Health), With<Player>>,
+    block_query: Query<(Entity, &NumberBlock, &GridPosition)>,
+    mut hint_events: EventWriter<HintUsed>,
+) {
+    if !hints.is_ready() {
+        return;
+    }
+    let remaining: Vec<i32> = block_query
+        .iter()
+        .filter(|(_, block, _)| !block.is_eaten)
+        .filter_map(|(_, block, _)| block.value.as_integer())
+        .collect();
+    // Point at whichever block is nearest to any player still standing.
+    let Some(target) = player_query
+        .iter()
+        .filter(|(_, _, health)| !health.is_dead())
+        .filter_map(|(player_pos, running, _)| {
+            // For sequences that's the next number to eat, and for totals a number that
+            // can still finish the player's chain.
+            let accepts = |value: Number| match question.question.target_total() {
+                Some(target) => value.as_integer().is_some_and(|value| {
+                    can_finish_with(value, &remaining, target - running.total)
+                }),
+                None => sequence.accepts(&question.question, value),
+            };
+            let target =
+                nearest_correct_block(accepts, &config.layout, player_pos, block_query.iter())?;
+            let target_pos = block_query.get(target).ok()?.2;
+            let moves = config.layout.path(*player_pos, *target_pos)?.len();
+            Some((target, moves))
+        })
+        .min_by_key(|(_, moves)| *moves)
+        .map(|(target, _)| target)
+    else {
+        return;
+    };
+    hints.target = Some(target);
+    hints.uses_left -= 1;
+    hints.cooldown.reset();
+    hint_events.write(HintUsed);
+}
+
+fn drop_hint_when_answered(mut hints: ResMut<Hints>, block_query: Query<&NumberBlock>) {
+    let Some(target) = hints.target else {
+        return;
+    };
+    if block_query.get(target).is_ok_and(|block| block.is_eaten) {
+        hints.target = None;
+    }
+}
+
+fn drop_hint_on_new_question(mut hints: ResMut<Hints>) {
+    hints.target = None;
+}
+
+/// Outline the hinted block and draw the shortest way to it around the layout.
+fn draw_hint(
+    mut gizmos: Gizmos,
+    config: Res<GridConfig>,
+    hints: Res<Hints>,
+    player_query: Query<(&GridPosition, &Health), With<Player>>,
+    block_query: Query<&GridPosition, With<NumberBlock>>,
+) {
+    let Some(target_pos) = hints.target.and_then(|target| block_query.get(target).ok()) else {
+        return;
+    };
+    let end = grid_position_to_world(&config, target_pos);
+    gizmos.rect_2d(end, config.block_size, HINT_COLOR);
+
+    // The way there is drawn from the closest player still standing.
+    let Some((player_pos, path)) = player_query
+        .iter()
+        .filter(|(_, health)| !health.is_dead())
+        .filter_map(|(player_pos, _)| {
+            Some((player_pos, config.layout.path(*player_pos, *target_pos)?))
+        })
+        .min_by_key(|(_, path)| path.len())
+    else {
+        return;
+    };
+    let mut points = vec![grid_position_to_world(&config, player_pos)];
+    points.extend(path.iter().map(|pos| grid_position_to_world(&config, pos)));
+    if let [.., before_end, end] = points[..] {
+        gizmos.linestrip_2d(points[..points.len() - 1].iter().copied(), HINT_COLOR);
+        gizmos.arrow_2d(before_end, end, HINT_COLOR);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math_mono::game::question::Question;
+
+    #[test]
+    fn test_nearest_correct_block() {
+        let blocks = [
+            (5, 0, 0, false),
+            (4, 2, 2, false),
+            (6, 0, 1, true),
+            (8, 4, 4, false),
+        ]
+        .map(|(value, row, col, is_eaten)| {
+            (
+                NumberBlock {
+                    value: Number::int(value),
+                    is_eaten,
+                    claim: None,
+                },
+                GridPosition { row, col },
+            )
+        });
+        let entities: Vec<_> = blocks
+            .iter()
+            .enumerate()
+            .map(|(index, (block, pos))| (Entity::from_raw(index as u32), block, pos))
+            .collect();
+
+        let from = GridPosition { row: 0, col: 0 };
+        let open = Layout::open(5, 5);
+        let even = |value| Question::Even.is_correct(value);
+        let nearest = nearest_correct_block(even, &open, &from, entities.clone());
+        assert_eq!(nearest, Some(Entity::from_raw(1)));
+        assert_eq!(
+            nearest_correct_block(
+                |value| Question::MultipleOf(10).is_correct(value),
+                &open,
+                &from,
+                entities.clone()
+            ),
+            None
+        );
+
+        // Walled in, (2, 2) can't be reached and (4, 4) is the only choice.
+        let walled = Layout::parse(&[
+            ".....", //
+            ".###.", //
+            ".#.#.", //
+            ".###.", //
+            ".....",
+        ])
+        .unwrap();
+        let nearest = nearest_correct_block(even, &walled, &from, entities);
+        assert_eq!(nearest, Some(Entity::from_raw(3)));
+    }
+}
@@ -1 +1,176 @@
+//! Number theory helpers used to check answers, and the expressions in `x` used by
+//! algebra questions.
 
+use std::fmt;
+
+use bevy::prelude::*;
+
+/// Whether `n` is a prime number.
+pub fn is_prime(n: i32) -> bool {
+    if n < 2 {
+        return false;
+    }
+    let mut divisor = 2;
+    while divisor * divisor <= n {
+        if n % divisor == 0 {
+            return false;
+        }
+        divisor += 1;
+    }
+    true
+}
+
+/// The smallest factor of `n` other than 1, if `n` isn't prime.
+pub fn smallest_factor(n: i32) -> Option<i32> {
+    if n < 2 {
+        return None;
+    }
+    (2..)
+        .take_while(|divisor| divisor * divisor <= n)
+        .find(|divisor| n % divisor == 0)
+}
+
+/// An expression in `x`, small enough to copy around with the question that asks it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum Expression {
+    /// `coefficient·x + constant`, like `3x + 4`.
+    Linear { coefficient: i32, constant: i32 },
+    /// `coefficient(x + constant)`, like `2(x - 3)`.
+    Bracketed { coefficient: i32, constant: i32 },
+}
+
+impl Expression {
+    pub const fn linear(coefficient: i32, constant: i32) -> Self {
+        Self::Linear {
+            coefficient,
+            constant,
+        }
+    }
+
+    pub const fn bracketed(coefficient: i32, constant: i32) -> Self {
+        Self::Bracketed {
+            coefficient,
+            constant,
+        }
+    }
+
+    pub fn coefficient(&self) -> i32 {
+        match *self {
+            Self::Linear { coefficient, .. } | Self::Bracketed { coefficient, .. } => coefficient,
+        }
+    }
+
+    pub fn constant(&self) -> i32 {
+        match *self {
+            Self::Linear { constant, .. } | Self::Bracketed { constant, .. } => constant,
+        }
+    }
+
+    /// The same shape with different numbers.
+    pub fn with_numbers(&self, coefficient: i32, constant: i32) -> Self {
+        match self {
+            Self::Linear { .. } => Self::linear(coefficient, constant),
+            Self::Bracketed { .. } => Self::bracketed(coefficient, constant),
+        }
+    }
+
+    /// The value of the expression when `x` is `x`.
+    pub fn evaluate(&self, x: i32) -> i32 {
+        match *self {
+            Self::Linear {
+                coefficient,
+                constant,
+            } => coefficient * x + constant,
+            Self::Bracketed {
+                coefficient,
+                constant,
+            } => coefficient * (x + constant),
+        }
+    }
+
+    /// The whole number `x` that makes the expression equal `value`, if there is one.
+    pub fn solve(&self, value: i32) -> Option<i32> {
+        let coefficient = self.coefficient();
+        if coefficient == 0 {
+            return None;
+        }
+        match *self {
+            Self::Linear { constant, .. } => {
+                let scaled = value - constant;
+                (scaled % coefficient == 0).then(|| scaled / coefficient)
+            }
+            Self::Bracketed { constant, .. } => {
+                (value % coefficient == 0).then(|| value / coefficient - constant)
+            }
+        }
+    }
+
+    /// The expression with `x` written in as a number, like `3 × 5 + 4`.
+    pub fn substituted(&self, x: i32) -> String {
+        let constant = signed_term(self.constant());
+        match *self {
+            Self::Linear { coefficient: 1, .. } => format!("{x}{constant}"),
+            Self::Linear { coefficient, .. } => format!("{coefficient} × {x}{constant}"),
+            Self::Bracketed { coefficient, .. } => format!("{coefficient} × ({x}{constant})"),
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let term = match self.coefficient() {
+            1 => "x".to_string(),
+            -1 => "-x".to_string(),
+            coefficient => format!("{coefficient}x"),
+        };
+        let constant = signed_term(self.constant());
+        match self {
+            Self::Linear { .. } => write!(f, "{term}{constant}"),
+            Self::Bracketed { coefficient, .. } => write!(f, "{coefficient}(x{constant})"),
+        }
+    }
+}
+
+/// ` + n`, ` - n`, or nothing for zero.
+fn signed_term(n: i32) -> String {
+    match n {
+        0 => String::new(),
+        n if n < 0 => format!(" - {}", -n),
+        n => format!(" + {n}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_prime() {
+        let primes: Vec<i32> = (0..30).filter(|&n| is_prime(n)).collect();
+        assert_eq!(primes, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        assert!(!is_prime(-7));
+    }
+
+    #[test]
+    fn test_smallest_factor() {
+        assert_eq!(smallest_factor(21), Some(3));
+        assert_eq!(smallest_factor(49), Some(7));
+        assert_eq!(smallest_factor(13), None);
+    }
+
+    #[test]
+    fn test_expressions() {
+        let linear = Expression::linear(3, 4);
+        assert_eq!(linear.to_string(), "3x + 4");
+        assert_eq!(linear.evaluate(5), 19);
+        assert_eq!(linear.solve(19), Some(5));
+        assert_eq!(linear.solve(20), None);
+        assert_eq!(linear.substituted(5), "3 × 5 + 4");
+
+        let bracketed = Expression::bracketed(2, -3);
+        assert_eq!(bracketed.to_string(), "2(x - 3)");
+        assert_eq!(bracketed.evaluate(7), 8);
+        assert_eq!(bracketed.solve(8), Some(7));
+        assert_eq!(Expression::linear(1, 0).to_string(), "x");
+    }
+}
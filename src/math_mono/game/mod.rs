@@ -1,14 +1,25 @@
+pub mod boss;
 pub mod enemies;
+pub mod explanation;
 pub mod grid;
 pub mod health;
+pub mod hint;
 pub mod math;
+pub mod number;
 pub mod player;
+pub mod power_up;
 pub mod question;
+pub mod scoring;
+pub mod sequence;
+pub mod sum_target;
+pub mod tiles;
+pub mod versus;
+pub mod word_problem;
 
 use bevy::app::{App, Plugin};
-// pub use enemies::*;
+pub use enemies::*;
 pub use grid::*;
-// pub use health::*;
+pub use health::*;
 // pub use math::*;
 pub use player::*;
 pub use question::*;
@@ -17,6 +28,18 @@ pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((GridPlugin, PlayerPlugin, QuestionPlugin));
+        app.add_plugins((
+            GridPlugin,
+            PlayerPlugin,
+            QuestionPlugin,
+            HealthPlugin,
+            EnemyPlugin,
+            hint::HintPlugin,
+            sequence::SequencePlugin,
+            power_up::PowerUpPlugin,
+            scoring::ScoringPlugin,
+            boss::BossPlugin,
+            versus::VersusPlugin,
+        ));
     }
 }
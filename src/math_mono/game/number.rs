@@ -0,0 +1,450 @@
+//! Tile values: whole numbers, fractions and decimals, any of which may be negative, and
+//! measurements of money, length and time.
+
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+
+/// An exact number, kept as it was written so `2/4` still shows as `2/4`, but compared by
+/// value so `2/4`, `1/2` and `0.5` are all equal, as are `150 cm` and `1.5 m`.
+#[derive(Debug, Clone, Copy, Reflect)]
+pub struct Number {
+    numer: i32,
+    /// Always positive.
+    denom: i32,
+    /// Written as a decimal, in which case `denom` is a power of ten.
+    decimal: bool,
+    unit: Unit,
+}
+
+/// What a [`Number`] measures, and how it is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum Unit {
+    #[default]
+    None,
+    /// Written `$1.25`.
+    Dollars,
+    /// Written `150 cm`.
+    Centimetres,
+    /// Written `1.5 m`.
+    Metres,
+    /// Minutes after midnight, written `3:45`.
+    Clock,
+}
+
+/// Numbers of different dimensions are never equal, and only compare with each other
+/// by dimension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Dimension {
+    Number,
+    Money,
+    Length,
+    Time,
+}
+
+impl Unit {
+    pub fn dimension(self) -> Dimension {
+        match self {
+            Self::None => Dimension::Number,
+            Self::Dollars => Dimension::Money,
+            Self::Centimetres | Self::Metres => Dimension::Length,
+            Self::Clock => Dimension::Time,
+        }
+    }
+
+    /// How many of the dimension's smallest unit make one of this unit: cents for money,
+    /// centimetres for length.
+    pub fn base_scale(self) -> i32 {
+        match self {
+            Self::Dollars | Self::Metres => 100,
+            Self::None | Self::Centimetres | Self::Clock => 1,
+        }
+    }
+}
+
+impl Dimension {
+    /// What the player is asked to eat: "the amounts more than $1".
+    pub fn noun(self) -> &'static str {
+        match self {
+            Self::Number => "numbers",
+            Self::Money => "amounts",
+            Self::Length => "lengths",
+            Self::Time => "times",
+        }
+    }
+
+    pub fn greater_than(self) -> &'static str {
+        match self {
+            Self::Number => "greater than",
+            Self::Money => "more than",
+            Self::Length => "longer than",
+            Self::Time => "later than",
+        }
+    }
+
+    pub fn less_than(self) -> &'static str {
+        match self {
+            Self::Number | Self::Money => "less than",
+            Self::Length => "shorter than",
+            Self::Time => "earlier than",
+        }
+    }
+}
+
+impl Number {
+    pub const fn int(n: i32) -> Self {
+        Self {
+            numer: n,
+            denom: 1,
+            decimal: false,
+            unit: Unit::None,
+        }
+    }
+
+    /// `numer / denom`, not simplified.
+    pub const fn fraction(numer: i32, denom: i32) -> Self {
+        assert!(denom != 0, "a fraction can't have a denominator of 0");
+        let sign = if denom < 0 { -1 } else { 1 };
+        Self {
+            numer: numer * sign,
+            denom: denom * sign,
+            decimal: false,
+            unit: Unit::None,
+        }
+    }
+
+    /// `units` tenths, hundredths and so on, depending on `places`: `decimal(25, 1)` is 2.5.
+    pub const fn decimal(units: i32, places: u32) -> Self {
+        Self {
+            numer: units,
+            denom: 10_i32.pow(places),
+            decimal: true,
+            unit: Unit::None,
+        }
+    }
+
+    /// An amount of money, written in dollars.
+    pub const fn dollars(cents: i32) -> Self {
+        Self::decimal(cents, 2).in_unit(Unit::Dollars)
+    }
+
+    /// A time of day on a 12-hour clock.
+    pub const fn clock(hours: i32, minutes: i32) -> Self {
+        Self::int(hours * 60 + minutes).in_unit(Unit::Clock)
+    }
+
+    /// The same number, measuring `unit`.
+    pub const fn in_unit(self, unit: Unit) -> Self {
+        Self { unit, ..self }
+    }
+
+    pub fn unit(&self) -> Unit {
+        self.unit
+    }
+
+    pub fn numer(&self) -> i32 {
+        self.numer
+    }
+
+    pub fn denom(&self) -> i32 {
+        self.denom
+    }
+
+    /// Digits after the decimal point, if this is written as a decimal.
+    pub fn decimal_places(&self) -> Option<u32> {
+        self.decimal.then(|| self.denom.ilog10())
+    }
+
+    /// The value as a whole number, if it is one.
+    pub fn as_integer(&self) -> Option<i32> {
+        (self.numer % self.denom == 0).then_some(self.numer / self.denom)
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.numer < 0
+    }
+
+    pub fn to_f32(self) -> f32 {
+        self.numer as f32 / self.denom as f32
+    }
+
+    /// The same value as a fraction in lowest terms.
+    pub fn simplified(&self) -> Self {
+        let divisor = gcd(self.numer, self.denom);
+        Self::fraction(self.numer / divisor, self.denom / divisor).in_unit(self.unit)
+    }
+
+    /// Add `steps` to the numerator, keeping the same denominator: one more tenth of a
+    /// decimal, one more quarter of `3/4`, or one more of a whole number.
+    pub fn nudged(&self, steps: i32) -> Self {
+        Self {
+            numer: self.numer + steps,
+            ..*self
+        }
+    }
+
+    /// How the number is drawn on a tile: fractions are stacked over a bar.
+    pub fn stacked(&self) -> String {
+        if self.decimal || self.denom == 1 || self.unit != Unit::None {
+            self.to_string()
+        } else {
+            format!("{}\n—\n{}", self.numer, self.denom)
+        }
+    }
+}
+
+fn gcd(a: i32, b: i32) -> i32 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.max(1)
+}
+
+impl Default for Number {
+    fn default() -> Self {
+        Self::int(0)
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Number {}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Number {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let base = |number: &Self, denom: i32| {
+            number.numer as i64 * number.unit.base_scale() as i64 * denom as i64
+        };
+        self.unit
+            .dimension()
+            .cmp(&other.unit.dimension())
+            .then_with(|| base(self, other.denom).cmp(&base(other, self.denom)))
+    }
+}
+
+impl Hash for Number {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let base = Self::fraction(self.numer * self.unit.base_scale(), self.denom).simplified();
+        (self.unit.dimension(), base.numer, base.denom).hash(state);
+    }
+}
+
+impl Number {
+    /// The number without its unit.
+    fn fmt_plain(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.decimal_places() {
+            Some(places) if places > 0 => {
+                let units = self.numer.unsigned_abs();
+                let denom = self.denom.unsigned_abs();
+                let places = places as usize;
+                write!(f, "{}.{:0places$}", units / denom, units % denom)
+            }
+            _ if self.denom == 1 => write!(f, "{}", self.numer.unsigned_abs()),
+            _ => write!(f, "{}/{}", self.numer.unsigned_abs(), self.denom),
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_negative() {
+            f.write_str("-")?;
+        }
+        match self.unit {
+            Unit::None => self.fmt_plain(f),
+            Unit::Dollars => {
+                f.write_str("$")?;
+                self.fmt_plain(f)
+            }
+            Unit::Centimetres => {
+                self.fmt_plain(f)?;
+                f.write_str(" cm")
+            }
+            Unit::Metres => {
+                self.fmt_plain(f)?;
+                f.write_str(" m")
+            }
+            Unit::Clock => {
+                let minutes = (self.numer / self.denom).abs();
+                write!(f, "{}:{:02}", minutes / 60, minutes % 60)
+            }
+        }
+    }
+}
+
+impl FromStr for Number {
+    type Err = String;
+
+    /// Parse `-3`, `3/4`, `2.5`, `$1.25`, `150 cm`, `1.5 m` or `3:45`.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = |_| format!("not a number: {text:?}");
+        if let Some((hours, minutes)) = text.split_once(':') {
+            return Ok(Self::clock(
+                hours.parse().map_err(invalid)?,
+                minutes.parse().map_err(invalid)?,
+            ));
+        }
+        let (sign, unsigned) = match text.strip_prefix('-') {
+            Some(unsigned) => ("-", unsigned),
+            None => ("", text),
+        };
+        let with_unit = |plain: &str, unit| {
+            format!("{sign}{plain}")
+                .parse::<Self>()
+                .map(|number| number.in_unit(unit))
+        };
+        if let Some(plain) = unsigned.strip_prefix('$') {
+            return with_unit(plain, Unit::Dollars);
+        }
+        if let Some(plain) = unsigned.strip_suffix(" cm") {
+            return with_unit(plain, Unit::Centimetres);
+        }
+        if let Some(plain) = unsigned.strip_suffix(" m") {
+            return with_unit(plain, Unit::Metres);
+        }
+        if let Some((numer, denom)) = text.split_once('/') {
+            let denom: i32 = denom.trim().parse().map_err(invalid)?;
+            if denom == 0 {
+                return Err(format!("zero denominator: {text:?}"));
+            }
+            return Ok(Self::fraction(
+                numer.trim().parse().map_err(invalid)?,
+                denom,
+            ));
+        }
+        if let Some((_, fraction)) = text.split_once('.') {
+            let units = text.replace('.', "").parse().map_err(invalid)?;
+            return Ok(Self::decimal(units, fraction.len() as u32));
+        }
+        text.parse().map(Self::int).map_err(invalid)
+    }
+}
+
+/// Whole numbers are saved as JSON numbers, as they were before fractions existed.
+/// Anything else is saved as a string like `"3/4"`, `"2.5"` or `"$1.25"`.
+impl Serialize for Number {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.denom == 1 && !self.decimal && self.unit == Unit::None {
+            serializer.serialize_i32(self.numer)
+        } else {
+            serializer.collect_str(self)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Number {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NumberVisitor;
+
+        impl de::Visitor<'_> for NumberVisitor {
+            type Value = Number;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a whole number, or a string like \"3/4\" or \"2.5\"")
+            }
+
+            fn visit_i64<E: de::Error>(self, n: i64) -> Result<Number, E> {
+                i32::try_from(n).map(Number::int).map_err(E::custom)
+            }
+
+            fn visit_u64<E: de::Error>(self, n: u64) -> Result<Number, E> {
+                i32::try_from(n).map(Number::int).map_err(E::custom)
+            }
+
+            fn visit_str<E: de::Error>(self, text: &str) -> Result<Number, E> {
+                text.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(NumberVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn test_numbers_compare_by_value() {
+        assert_eq!(Number::fraction(2, 4), Number::fraction(1, 2));
+        assert_eq!(Number::fraction(1, 2), Number::decimal(5, 1));
+        assert_eq!(Number::fraction(6, -3), Number::int(-2));
+        assert!(Number::int(-4) < Number::int(-3));
+        assert!(Number::decimal(25, 1) > Number::fraction(7, 3));
+        assert_eq!(Number::fraction(6, 8).simplified().to_string(), "3/4");
+    }
+
+    #[test]
+    fn test_numbers_format_as_written() {
+        assert_eq!(Number::fraction(2, 4).to_string(), "2/4");
+        assert_eq!(Number::fraction(2, 4).stacked(), "2\n—\n4");
+        assert_eq!(Number::decimal(-5, 1).to_string(), "-0.5");
+        assert_eq!(Number::decimal(205, 2).to_string(), "2.05");
+        assert_eq!(Number::int(-3).stacked(), "-3");
+        assert_eq!(Number::dollars(125).to_string(), "$1.25");
+        assert_eq!(Number::dollars(-50).to_string(), "-$0.50");
+        assert_eq!(Number::clock(3, 5).to_string(), "3:05");
+        assert_eq!(
+            Number::int(150).in_unit(Unit::Centimetres).stacked(),
+            "150 cm"
+        );
+    }
+
+    #[test]
+    fn test_measurements_compare_within_a_dimension() {
+        let metres = Number::decimal(15, 1).in_unit(Unit::Metres);
+        assert_eq!(metres, Number::int(150).in_unit(Unit::Centimetres));
+        assert_ne!(
+            Number::int(150),
+            Number::int(150).in_unit(Unit::Centimetres)
+        );
+        assert!(Number::clock(3, 45) > Number::clock(2, 30));
+        assert!(Number::dollars(125) < Number::dollars(200));
+        for number in [
+            metres,
+            Number::dollars(-125),
+            Number::clock(12, 0),
+            Number::int(150).in_unit(Unit::Centimetres),
+        ] {
+            assert_eq!(number.to_string().parse::<Number>(), Ok(number));
+        }
+    }
+
+    #[test]
+    fn test_numbers_round_trip_through_json() {
+        let mut counts = BTreeMap::new();
+        for number in [
+            Number::int(7),
+            Number::fraction(3, 4),
+            Number::decimal(25, 1),
+        ] {
+            counts.insert(number, 1);
+        }
+        let json = serde_json::to_string(&counts).unwrap();
+        assert_eq!(json, r#"{"3/4":1,"2.5":1,"7":1}"#);
+        let parsed: BTreeMap<Number, u32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, counts);
+
+        let old: Vec<Number> = serde_json::from_str("[4, -9]").unwrap();
+        assert_eq!(old, [Number::int(4), Number::int(-9)]);
+    }
+}
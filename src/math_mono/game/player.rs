@@ -6,72 +6,208 @@ use bevy::{
 };
 
 use crate::{
+    AppSystems, PausableSystems,
     math_mono::{
         animation::PlayerAnimation,
-        components::{GridPosition, NumberBlock, Question},
-        game::{calculate_block_center, calculate_grid_layout, clamp_grid_position, grid::GridConfig},
+        components::{
+            Claim, Combo, GridPosition, Health, NumberBlock, Player, PlayerInput, RunningTotal,
+        },
+        game::{
+            calculate_block_center, calculate_grid_layout,
+            grid::{BLOCK_WIDTH, GridConfig, is_valid_grid_position},
+            number::Number,
+            question::{CurrentQuestion, Question},
+            sequence::SequenceProgress,
+            sum_target::ChainStep,
+            tiles::Direction,
+            versus::{STUN_SECS, TileStolen},
+        },
+        run::{MAX_PLAYERS, Party, online, start_run},
     },
     screens::Screen,
 };
 
+/// Points awarded for eating a correct number.
+pub const CORRECT_ANSWER_POINTS: i32 = 10;
+
+/// Sprite tint of each player, so two players can tell their ducks apart.
+pub const PLAYER_TINTS: [Color; MAX_PLAYERS] = [Color::WHITE, Color::srgb(0.55, 0.8, 1.0)];
+/// How see-through a player is once out of health.
+const KNOCKED_OUT_ALPHA: f32 = 0.3;
+/// How much darker a stunned player is drawn.
+const STUNNED_DARKENING: f32 = 0.4;
+/// Gamepad buttons that move, in [`Direction::ALL`] order.
+const DPAD: [GamepadButton; 4] = [
+    GamepadButton::DPadUp,
+    GamepadButton::DPadDown,
+    GamepadButton::DPadLeft,
+    GamepadButton::DPadRight,
+];
+
+/// Keys that move a player, in [`Direction::ALL`] order, and eat.
+struct KeyBindings {
+    moves: [&'static [KeyCode]; 4],
+    eat: &'static [KeyCode],
+}
+
+/// Playing alone, the arrows and WASD both work.
+const SOLO_KEYS: KeyBindings = KeyBindings {
+    moves: [
+        &[KeyCode::KeyW, KeyCode::ArrowUp],
+        &[KeyCode::KeyS, KeyCode::ArrowDown],
+        &[KeyCode::KeyA, KeyCode::ArrowLeft],
+        &[KeyCode::KeyD, KeyCode::ArrowRight],
+    ],
+    eat: &[KeyCode::Space],
+};
+
+/// Sharing a keyboard, player one has WASD and Space and player two the arrows and Enter.
+const SHARED_KEYS: [KeyBindings; MAX_PLAYERS] = [
+    KeyBindings {
+        moves: [
+            &[KeyCode::KeyW],
+            &[KeyCode::KeyS],
+            &[KeyCode::KeyA],
+            &[KeyCode::KeyD],
+        ],
+        eat: &[KeyCode::Space],
+    },
+    KeyBindings {
+        moves: [
+            &[KeyCode::ArrowUp],
+            &[KeyCode::ArrowDown],
+            &[KeyCode::ArrowLeft],
+            &[KeyCode::ArrowRight],
+        ],
+        eat: &[KeyCode::Enter, KeyCode::NumpadEnter],
+    },
+];
+
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<PlayerAssets>()
-            .add_systems(OnEnter(Screen::Gameplay), spawn_player)
+            .add_event::<AnswerEaten>()
+            .add_systems(OnEnter(Screen::Gameplay), spawn_player.after(start_run))
             .add_systems(
                 Update,
                 (
-                    move_player_on_grid,
+                    read_player_input.in_set(AppSystems::RecordInput),
+                    move_player_on_grid.after(read_player_input),
                     sync_player_to_grid_position.after(move_player_on_grid),
-                    eat_number_on_spacebar,
+                    // In a battle the server decides what gets eaten.
+                    eat_number_on_spacebar
+                        .run_if(not(online))
+                        .after(read_player_input)
+                        .in_set(AppSystems::RecordInput),
                     update_block_visuals,
+                    tint_players,
                 )
+                    .in_set(PausableSystems)
                     .run_if(in_state(Screen::Gameplay)),
             );
     }
 }
 
+/// Sent whenever a player eats a number, right or wrong, or finishes making a total.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct AnswerEaten {
+    /// The player who ate it.
+    pub player: Entity,
+    /// [`Player::index`] of the player who ate it.
+    pub player_index: usize,
+    /// The question being answered when the number was eaten.
+    pub question: Question,
+    /// [`CurrentQuestion::round`] when the number was eaten.
+    pub round: u32,
+    /// The number eaten, or the total a chain made.
+    pub value: Number,
+    pub correct: bool,
+    /// Blocks the answer took: one, or the whole chain that made a total.
+    pub blocks: u32,
+    /// The numbers a chain made its total from, in the order eaten. Empty unless a total
+    /// was made.
+    pub chain: Vec<Number>,
+    /// The block eaten, or the last of the chain.
+    pub block: Entity,
+}
+
+/// Where each player starts: the middle of the board, then the nearest free cells to it.
+pub fn start_positions(config: &GridConfig, count: usize) -> Vec<GridPosition> {
+    let start = config.layout.start();
+    let mut others: Vec<GridPosition> = config
+        .layout
+        .cells()
+        .filter(|pos| *pos != start && is_valid_grid_position(pos, config))
+        .collect();
+    others.sort_by_key(|pos| {
+        (
+            (pos.row - start.row).abs() + (pos.col - start.col).abs(),
+            pos.col,
+        )
+    });
+    [start].into_iter().chain(others).take(count).collect()
+}
+
 pub fn spawn_player(
     mut commands: Commands,
+    party: Res<Party>,
     player_assets: Res<PlayerAssets>,
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
     config: Res<GridConfig>,
 ) {
-    // Start the player in the middle of the grid
-    let start_row = config.rows / 2;
-    let start_col = config.cols / 2;
+    let starts = start_positions(&config, party.player_count());
+    for (index, start) in starts.into_iter().enumerate() {
+        commands.spawn((
+            Name::new(format!("Player {}", index + 1)),
+            Player { index, ..default() },
+            PlayerInput::default(),
+            Health::default(),
+            RunningTotal::default(),
+            Combo::default(),
+            start,
+            duck(
+                &player_assets,
+                &mut texture_atlas_layouts,
+                &config,
+                PLAYER_TINTS[index],
+            ),
+            StateScoped(Screen::Gameplay),
+        ));
+    }
+}
+
+/// A duck's animated sprite, tinted and sized for the board.
+pub fn duck(
+    player_assets: &PlayerAssets,
+    texture_atlas_layouts: &mut Assets<TextureAtlasLayout>,
+    config: &GridConfig,
+    tint: Color,
+) -> impl Bundle {
+    // The sprite is drawn at three times its size on default-sized blocks.
+    let scale = 3.0 * config.block_size.x / BLOCK_WIDTH;
 
     let layout: TextureAtlasLayout =
         TextureAtlasLayout::from_grid(UVec2::splat(32), 6, 2, Some(UVec2::splat(1)), None);
     let texture_atlas_layout = texture_atlas_layouts.add(layout);
     let player_animation = PlayerAnimation::new();
-
-    commands.spawn((
-        Name::new("Player"),
-        Player,
-        GridPosition {
-            row: start_row,
-            col: start_col,
+    let mut sprite = Sprite::from_atlas_image(
+        player_assets.ducky.clone(),
+        TextureAtlas {
+            layout: texture_atlas_layout,
+            index: player_animation.get_atlas_index(),
         },
-        Sprite::from_atlas_image(
-            player_assets.ducky.clone(),
-            TextureAtlas {
-                layout: texture_atlas_layout,
-                index: player_animation.get_atlas_index(),
-            },
-        ),
+    );
+    sprite.color = tint;
+
+    (
+        sprite,
         player_animation,
-        Transform::from_scale(Vec2::splat(3.0).extend(0.2)),
-        StateScoped(Screen::Gameplay),
-    ));
+        Transform::from_scale(Vec2::splat(scale).extend(0.2)),
+    )
 }
 
-#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
-#[reflect(Component)]
-pub struct Player;
-
 #[derive(Resource, Asset, Clone, Reflect)]
 #[reflect(Resource)]
 pub struct PlayerAssets {
@@ -102,95 +238,266 @@ impl FromWorld for PlayerAssets {
     }
 }
 
-pub fn move_player_on_grid(
+/// Read each player's keys and gamepad. When playing alone any gamepad works; otherwise
+/// player one has the first gamepad connected and player two the second.
+pub fn read_player_input(
+    party: Res<Party>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut player_query: Query<&mut GridPosition, With<Player>>,
-    config: Res<GridConfig>,
+    gamepad_query: Query<(Entity, &Gamepad)>,
+    mut player_query: Query<(&Player, &Health, &mut PlayerInput)>,
 ) {
-    let Ok(mut grid_pos) = player_query.single_mut() else {
-        return;
-    };
+    let mut gamepads: Vec<(Entity, &Gamepad)> = gamepad_query.iter().collect();
+    gamepads.sort_by_key(|(entity, _)| *entity);
 
-    let mut moved = false;
-
-    if keyboard_input.just_pressed(KeyCode::KeyW) || keyboard_input.just_pressed(KeyCode::ArrowUp) {
-        grid_pos.row += 1;
-        moved = true;
-    }
-    if keyboard_input.just_pressed(KeyCode::KeyS) || keyboard_input.just_pressed(KeyCode::ArrowDown) {
-        grid_pos.row -= 1;
-        moved = true;
-    }
-    if keyboard_input.just_pressed(KeyCode::KeyA) || keyboard_input.just_pressed(KeyCode::ArrowLeft) {
-        grid_pos.col -= 1;
-        moved = true;
-    }
-    if keyboard_input.just_pressed(KeyCode::KeyD) || keyboard_input.just_pressed(KeyCode::ArrowRight) {
-        grid_pos.col += 1;
-        moved = true;
+    for (player, health, mut input) in &mut player_query {
+        *input = PlayerInput::default();
+        // Players out of health sit the rest of the level out, and stunned ones wait.
+        if health.is_dead() || player.stunned_secs > 0.0 {
+            continue;
+        }
+        let (keys, pads) = match *party {
+            Party::Solo => (&SOLO_KEYS, &gamepads[..]),
+            Party::Coop | Party::Versus => (
+                &SHARED_KEYS[player.index],
+                gamepads
+                    .get(player.index..=player.index)
+                    .unwrap_or_default(),
+            ),
+        };
+        for ((direction, move_keys), button) in Direction::ALL.into_iter().zip(keys.moves).zip(DPAD)
+        {
+            if keyboard_input.any_just_pressed(move_keys.iter().copied())
+                || pads.iter().any(|(_, pad)| pad.just_pressed(button))
+            {
+                input.moves.push(direction);
+            }
+        }
+        input.eat = keyboard_input.any_just_pressed(keys.eat.iter().copied())
+            || pads
+                .iter()
+                .any(|(_, pad)| pad.just_pressed(GamepadButton::South));
     }
+}
 
-    if moved {
-        // Clamp the position to stay within the grid bounds
-        clamp_grid_position(&mut grid_pos, &config);
+pub fn move_player_on_grid(
+    mut player_query: Query<(&PlayerInput, &mut GridPosition), With<Player>>,
+    config: Res<GridConfig>,
+) {
+    for (input, mut grid_pos) in &mut player_query {
+        for &direction in &input.moves {
+            // The layout keeps the player on the grid and out of walls.
+            *grid_pos = config.layout.step(*grid_pos, direction);
+        }
     }
 }
 
-// Updates the player's Transform to match its GridPosition
+// Updates each player's Transform to match its GridPosition
 pub fn sync_player_to_grid_position(
     config: Res<GridConfig>,
     mut player_query: Query<(&GridPosition, &mut Transform), (With<Player>, Changed<GridPosition>)>,
 ) {
-    let Ok((grid_pos, mut transform)) = player_query.single_mut() else {
-        return;
-    };
-
     // We can reuse the grid calculation logic we already wrote!
     let (_, _, bottom_left_x, bottom_left_y) = calculate_grid_layout(&config);
     let bottom_left = Vec2::new(bottom_left_x, bottom_left_y);
-    let new_world_pos = calculate_block_center(&config, bottom_left, grid_pos.row, grid_pos.col);
 
-    // Update the player's actual world position.
-    // We give it a higher Z value to make sure it renders on top of the grid.
-    transform.translation = new_world_pos.extend(1.0);
+    for (grid_pos, mut transform) in &mut player_query {
+        let new_world_pos =
+            calculate_block_center(&config, bottom_left, grid_pos.row, grid_pos.col);
+
+        // Update the player's actual world position.
+        // We give it a higher Z value to make sure it renders on top of the grid.
+        transform.translation = new_world_pos.extend(1.0);
+    }
+}
+
+/// Players out of health fade out, and stunned players darken.
+fn tint_players(
+    mut player_query: Query<
+        (&Player, &Health, &mut Sprite),
+        Or<(Changed<Player>, Changed<Health>)>,
+    >,
+) {
+    for (player, health, mut sprite) in &mut player_query {
+        let tint = PLAYER_TINTS[player.index];
+        sprite.color = if health.is_dead() {
+            tint.with_alpha(KNOCKED_OUT_ALPHA)
+        } else if player.stunned_secs > 0.0 {
+            tint.darker(STUNNED_DARKENING)
+        } else {
+            tint
+        };
+    }
 }
 
 pub fn eat_number_on_spacebar(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    player_query: Query<&GridPosition, With<Player>>,
-    mut block_query: Query<(&mut NumberBlock, &GridPosition), Without<Player>>,
+    party: Res<Party>,
+    question: Res<CurrentQuestion>,
+    mut sequence: ResMut<SequenceProgress>,
+    mut player_query: Query<(
+        Entity,
+        &mut Player,
+        &PlayerInput,
+        &GridPosition,
+        &mut Health,
+        &mut RunningTotal,
+    )>,
+    mut block_query: Query<(Entity, &mut NumberBlock, &GridPosition), Without<Player>>,
+    mut eaten_events: EventWriter<AnswerEaten>,
+    mut stolen_events: EventWriter<TileStolen>,
 ) {
-    // Only eat when spacebar is pressed
-    if !keyboard_input.just_pressed(KeyCode::Space) {
-        return;
-    }
+    for (player, mut stats, input, player_pos, mut health, mut running) in &mut player_query {
+        // Only eat when the player's eat button is pressed
+        if !input.eat {
+            continue;
+        }
 
-    let Ok(player_pos) = player_query.single() else {
-        return;
-    };
+        // Find the block at the player's position. In versus that can be a right answer
+        // the other player ate, to steal.
+        let stealable = |block: &NumberBlock| {
+            block
+                .claim
+                .is_some_and(|claim| claim.owner != player && !claim.stolen)
+        };
+        let Some(entity) = block_query.iter().find_map(|(entity, block, block_pos)| {
+            (player_pos == block_pos && (!block.is_eaten || stealable(block))).then_some(entity)
+        }) else {
+            continue;
+        };
 
+        let (_, block, _) = block_query.get(entity).unwrap();
+        if let Some(claim) = block.claim.filter(|_| block.is_eaten) {
+            block_query.get_mut(entity).unwrap().1.claim = Some(Claim {
+                owner: player,
+                stolen: true,
+                ..claim
+            });
+            stolen_events.write(TileStolen {
+                thief: player,
+                victim: claim.owner,
+                points: claim.points,
+            });
+            continue;
+        }
 
-    // Find the block at the player's position
-    for (mut block, block_pos) in block_query.iter_mut() {
-        if player_pos == block_pos && !block.is_eaten {
+        if let Some(target) = question.question.target_total() {
+            let step = eat_into_total(&mut running, target, entity, &mut block_query);
+            let value = block_query.get(entity).unwrap().1.value;
+            match step {
+                ChainStep::Building => {}
+                ChainStep::Made => {
+                    let chain = running
+                        .chain
+                        .iter()
+                        .filter_map(|&eaten| block_query.get(eaten).ok())
+                        .map(|(_, block, _)| block.value)
+                        .collect();
+                    eaten_events.write(AnswerEaten {
+                        player,
+                        player_index: stats.index,
+                        question: question.question,
+                        round: question.round,
+                        value: Number::int(target),
+                        correct: true,
+                        blocks: running.chain.len() as u32,
+                        chain,
+                        block: entity,
+                    });
+                    running.chain.clear();
+                }
+                ChainStep::Overshot => {
+                    penalise_wrong_answer(*party, &mut stats, &mut health);
+                    eaten_events.write(AnswerEaten {
+                        player,
+                        player_index: stats.index,
+                        question: question.question,
+                        round: question.round,
+                        value,
+                        correct: false,
+                        blocks: 1,
+                        chain: Vec::new(),
+                        block: entity,
+                    });
+                }
+            }
+            continue;
+        }
+
+        let (_, mut block, _) = block_query.get_mut(entity).unwrap();
+
+        // Right numbers eaten out of order count as wrong.
+        let correct = sequence.accepts(&question.question, block.value);
+        eaten_events.write(AnswerEaten {
+            player,
+            player_index: stats.index,
+            question: question.question,
+            round: question.round,
+            value: block.value,
+            correct,
+            blocks: 1,
+            chain: Vec::new(),
+            block: entity,
+        });
+        if correct {
+            if question.question.ordered_answers().is_some() {
+                sequence.eaten.push(block.value);
+            }
             block.is_eaten = true;
-            println!("Player position: {:?}", player_pos);
-            break; // Only eat one block per spacebar press
+            if *party == Party::Versus {
+                // Scoring fills in the points.
+                block.claim = Some(Claim {
+                    owner: player,
+                    stolen: false,
+                    points: 0,
+                });
+            }
+        } else {
+            // Wrong answers stay on the board
+            penalise_wrong_answer(*party, &mut stats, &mut health);
         }
     }
 }
 
+/// Wrong answers cost health, or in versus stun the player for a moment.
+fn penalise_wrong_answer(party: Party, player: &mut Player, health: &mut Health) {
+    if party == Party::Versus {
+        player.stunned_secs = STUN_SECS;
+    } else {
+        health.take_damage(1);
+    }
+}
+
+/// Add the block to the player's chain. A chain that overshoots goes back on the board,
+/// along with the block that overshot it.
+fn eat_into_total(
+    running: &mut RunningTotal,
+    target: i32,
+    entity: Entity,
+    block_query: &mut Query<(Entity, &mut NumberBlock, &GridPosition), Without<Player>>,
+) -> ChainStep {
+    let value = block_query.get(entity).unwrap().1.value;
+    let step = running.add(value.as_integer().unwrap_or_default(), target);
+    if step == ChainStep::Overshot {
+        for eaten in running.chain.drain(..) {
+            if let Ok((_, mut block, _)) = block_query.get_mut(eaten) {
+                block.is_eaten = false;
+            }
+        }
+        return step;
+    }
+    block_query.get_mut(entity).unwrap().1.is_eaten = true;
+    running.chain.push(entity);
+    step
+}
+
 pub fn update_block_visuals(
     block_query: Query<(&NumberBlock, &Children), Changed<NumberBlock>>,
     mut text_query: Query<&mut Visibility>,
 ) {
-
     for (block, children) in block_query.iter() {
         for child in children.iter() {
             if let Ok(mut visibility) = text_query.get_mut(child) {
                 // Hide text if block is eaten
                 *visibility = if block.is_eaten {
-                    println!("Updating block visuals");
                     Visibility::Hidden
                 } else {
                     Visibility::Visible
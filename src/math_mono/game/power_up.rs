@@ -0,0 +1,418 @@
+//! Power-ups that turn up on the board now and then and help the player for a while.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::{Rng, seq::IndexedRandom};
+
+use crate::{
+    AppSystems, PausableSystems,
+    asset_tracking::LoadResource,
+    audio::{SoundEffect, sound_effect},
+    math_mono::{
+        components::{GridPosition, Health, NumberBlock, Player},
+        game::{
+            grid::{GridConfig, animate_colors_to_music, grid_position_to_world},
+            is_valid_grid_position,
+            question::CurrentQuestion,
+            sequence::SequenceProgress,
+        },
+        run::{RunRng, online},
+    },
+    screens::Screen,
+};
+
+/// Seconds between chances for a power-up to turn up.
+const SPAWN_INTERVAL_SECS: f32 = 12.0;
+/// Chance, from 0 to 1, that a power-up turns up each time the spawn timer finishes.
+const SPAWN_CHANCE: f64 = 0.6;
+/// No more power-ups turn up while this many are waiting on the board.
+const MAX_PICKUPS: usize = 2;
+/// Seconds a power-up waits on the board before vanishing.
+const PICKUP_LIFETIME_SECS: f32 = 8.0;
+const REVEAL_COLOR: Color = Color::srgb(1.0, 0.85, 0.1);
+
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<PowerUpAssets>();
+        app.load_resource::<PowerUpAssets>();
+        app.add_event::<PowerUpCollected>();
+        app.add_systems(OnEnter(Screen::Gameplay), reset_power_ups);
+        app.add_systems(
+            Update,
+            (
+                (tick_power_ups, tick_pickups).in_set(AppSystems::TickTimers),
+                (
+                    spawn_pickups.run_if(not(online)),
+                    collect_pickups,
+                    despawn_expired_pickups,
+                    reveal_correct_blocks.after(animate_colors_to_music),
+                )
+                    .chain()
+                    .in_set(AppSystems::Update),
+            )
+                .in_set(PausableSystems)
+                .run_if(in_state(Screen::Gameplay)),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum PowerUp {
+    /// Enemies stop where they are.
+    Freeze,
+    /// The next hit does no damage.
+    Shield,
+    /// Right answers light up.
+    Reveal,
+    /// Right answers score twice the points.
+    DoubleScore,
+    /// One more heart, even past full health.
+    ExtraHeart,
+}
+
+impl PowerUp {
+    pub const ALL: [Self; 5] = [
+        Self::Freeze,
+        Self::Shield,
+        Self::Reveal,
+        Self::DoubleScore,
+        Self::ExtraHeart,
+    ];
+
+    /// Seconds the power-up lasts once picked up. Extra hearts last for good.
+    pub fn duration_secs(self) -> f32 {
+        match self {
+            Self::Freeze => 5.0,
+            Self::Shield => 15.0,
+            Self::Reveal => 3.0,
+            Self::DoubleScore => 10.0,
+            Self::ExtraHeart => 0.0,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Freeze => "Freeze",
+            Self::Shield => "Shield",
+            Self::Reveal => "Reveal",
+            Self::DoubleScore => "Double score",
+            Self::ExtraHeart => "Extra heart",
+        }
+    }
+
+    /// Text drawn on the pickup.
+    fn label(self) -> &'static str {
+        match self {
+            Self::Freeze => "F",
+            Self::Shield => "S",
+            Self::Reveal => "?",
+            Self::DoubleScore => "x2",
+            Self::ExtraHeart => "+1",
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Self::Freeze => Color::srgb(0.5, 0.85, 1.0),
+            Self::Shield => Color::srgb(0.75, 0.75, 0.8),
+            Self::Reveal => REVEAL_COLOR,
+            Self::DoubleScore => Color::srgb(0.3, 0.9, 0.4),
+            Self::ExtraHeart => Color::srgb(1.0, 0.4, 0.55),
+        }
+    }
+
+    /// Speed the pickup chime plays at, so each power-up has its own pitch.
+    fn sound_speed(self) -> f32 {
+        match self {
+            Self::Freeze => 0.7,
+            Self::Shield => 0.85,
+            Self::Reveal => 1.0,
+            Self::DoubleScore => 1.2,
+            Self::ExtraHeart => 1.4,
+        }
+    }
+}
+
+/// Sent whenever the player picks up a power-up.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerUpCollected(pub PowerUp);
+
+/// A power-up waiting on the board to be picked up.
+#[derive(Component, Debug)]
+pub struct PowerUpPickup {
+    pub power_up: PowerUp,
+    lifetime: Timer,
+}
+
+/// The power-ups running out over time. Shields are kept on [`Health`], and extra hearts
+/// take effect straight away.
+#[derive(Resource, Debug, Default)]
+pub struct ActivePowerUps {
+    timers: Vec<(PowerUp, Timer)>,
+}
+
+impl ActivePowerUps {
+    pub fn is_active(&self, power_up: PowerUp) -> bool {
+        self.timers.iter().any(|(active, _)| *active == power_up)
+    }
+
+    /// Start `power_up`, or start it over if it's already running.
+    fn activate(&mut self, power_up: PowerUp) {
+        self.timers.retain(|(active, _)| *active != power_up);
+        let timer = Timer::from_seconds(power_up.duration_secs(), TimerMode::Once);
+        self.timers.push((power_up, timer));
+    }
+
+    /// Advance every timer, returning the power-ups that just ran out.
+    fn tick(&mut self, delta: Duration) -> Vec<PowerUp> {
+        let mut finished = Vec::new();
+        self.timers.retain_mut(|(power_up, timer)| {
+            timer.tick(delta);
+            if timer.finished() {
+                finished.push(*power_up);
+            }
+            !timer.finished()
+        });
+        finished
+    }
+
+    /// Points for a right answer are multiplied by this.
+    pub fn score_multiplier(&self) -> i32 {
+        if self.is_active(PowerUp::DoubleScore) {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// The running power-ups with the seconds each has left, like `Freeze 3s, Shield 9s`.
+    /// Shields are only listed for `health`, if given. `None` if nothing is running.
+    pub fn summary(&self, health: Option<&Health>) -> Option<String> {
+        let mut running: Vec<String> = self
+            .timers
+            .iter()
+            .map(|(power_up, timer)| {
+                let secs = timer.remaining_secs().ceil();
+                format!("{} {secs}s", power_up.name())
+            })
+            .collect();
+        if let Some(health) = health.filter(|health| health.shield_secs > 0.0) {
+            running.push(format!("Shield {}s", health.shield_secs.ceil()));
+        }
+        (!running.is_empty()).then(|| running.join(", "))
+    }
+}
+
+#[derive(Resource)]
+struct PowerUpSpawner {
+    timer: Timer,
+}
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct PowerUpAssets {
+    #[dependency]
+    pickup: Handle<AudioSource>,
+    #[dependency]
+    wear_off: Handle<AudioSource>,
+}
+
+impl FromWorld for PowerUpAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            pickup: assets.load("audio/sound_effects/power_up_pickup.wav"),
+            wear_off: assets.load("audio/sound_effects/power_up_wear_off.wav"),
+        }
+    }
+}
+
+fn reset_power_ups(mut commands: Commands) {
+    commands.insert_resource(ActivePowerUps::default());
+    commands.insert_resource(PowerUpSpawner {
+        timer: Timer::from_seconds(SPAWN_INTERVAL_SECS, TimerMode::Repeating),
+    });
+}
+
+fn tick_power_ups(
+    mut commands: Commands,
+    time: Res<Time>,
+    assets: Res<PowerUpAssets>,
+    mut power_ups: ResMut<ActivePowerUps>,
+    mut spawner: ResMut<PowerUpSpawner>,
+) {
+    spawner.timer.tick(time.delta());
+    for _ in power_ups.tick(time.delta()) {
+        commands.spawn(sound_effect(assets.wear_off.clone()));
+    }
+}
+
+fn tick_pickups(time: Res<Time>, mut pickup_query: Query<&mut PowerUpPickup>) {
+    for mut pickup in &mut pickup_query {
+        pickup.lifetime.tick(time.delta());
+    }
+}
+
+/// Now and then put a random power-up on a random free cell.
+fn spawn_pickups(
+    mut commands: Commands,
+    spawner: Res<PowerUpSpawner>,
+    mut rng: ResMut<RunRng>,
+    config: Res<GridConfig>,
+    asset_server: Res<AssetServer>,
+    player_query: Query<&GridPosition, With<Player>>,
+    pickup_query: Query<&GridPosition, With<PowerUpPickup>>,
+) {
+    if !spawner.timer.just_finished() || pickup_query.iter().count() >= MAX_PICKUPS {
+        return;
+    }
+    if !rng.power_ups.random_bool(SPAWN_CHANCE) {
+        return;
+    }
+    let free: Vec<GridPosition> = config
+        .layout
+        .cells()
+        .filter(|pos| is_valid_grid_position(pos, &config))
+        .filter(|pos| !player_query.iter().any(|player| player == pos))
+        .filter(|pos| !pickup_query.iter().any(|pickup| pickup == pos))
+        .collect();
+    let Some(&grid_pos) = free.choose(&mut rng.power_ups) else {
+        return;
+    };
+    let power_up = *PowerUp::ALL.choose(&mut rng.power_ups).unwrap();
+    let position = grid_position_to_world(&config, &grid_pos);
+
+    commands
+        .spawn((
+            Name::new(format!("{power_up:?} Power-up")),
+            PowerUpPickup {
+                power_up,
+                lifetime: Timer::from_seconds(PICKUP_LIFETIME_SECS, TimerMode::Once),
+            },
+            grid_pos,
+            Sprite::from_color(power_up.color(), config.block_size * 0.45),
+            // Above the blocks, below enemies and the player
+            Transform::from_translation(position.extend(0.4)),
+            StateScoped(Screen::Gameplay),
+        ))
+        .with_child((
+            Text2d::new(power_up.label()),
+            TextFont {
+                font: asset_server.load("fonts/MonofurNerdFont-Bold.ttf"),
+                font_size: 28.0,
+                ..default()
+            },
+            TextColor(Color::BLACK),
+            Transform::from_translation(Vec3::Z * 0.1),
+        ));
+}
+
+fn collect_pickups(
+    mut commands: Commands,
+    assets: Res<PowerUpAssets>,
+    mut power_ups: ResMut<ActivePowerUps>,
+    mut player_query: Query<(&GridPosition, &mut Health), With<Player>>,
+    pickup_query: Query<(Entity, &PowerUpPickup, &GridPosition)>,
+    mut collected_events: EventWriter<PowerUpCollected>,
+) {
+    for (entity, pickup, pickup_pos) in &pickup_query {
+        // Whoever stands on a pickup first takes it.
+        let Some(mut health) = player_query
+            .iter_mut()
+            .find_map(|(player_pos, health)| (player_pos == pickup_pos).then_some(health))
+        else {
+            continue;
+        };
+        let power_up = pickup.power_up;
+        match power_up {
+            PowerUp::Shield => health.shield_secs = power_up.duration_secs(),
+            PowerUp::ExtraHeart => {
+                health.current += 1;
+                health.max = health.max.max(health.current);
+            }
+            PowerUp::Freeze | PowerUp::Reveal | PowerUp::DoubleScore => {
+                power_ups.activate(power_up);
+            }
+        }
+        commands.entity(entity).despawn();
+        commands.spawn((
+            AudioPlayer(assets.pickup.clone()),
+            PlaybackSettings::DESPAWN.with_speed(power_up.sound_speed()),
+            SoundEffect,
+        ));
+        collected_events.write(PowerUpCollected(power_up));
+    }
+}
+
+fn despawn_expired_pickups(mut commands: Commands, pickup_query: Query<(Entity, &PowerUpPickup)>) {
+    for (entity, pickup) in &pickup_query {
+        if pickup.lifetime.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// While revealing, light up every block that is a right answer to eat now.
+fn reveal_correct_blocks(
+    power_ups: Res<ActivePowerUps>,
+    question: Res<CurrentQuestion>,
+    sequence: Res<SequenceProgress>,
+    mut block_query: Query<(&NumberBlock, &mut Sprite)>,
+) {
+    if !power_ups.is_active(PowerUp::Reveal) {
+        return;
+    }
+    for (block, mut sprite) in &mut block_query {
+        if !block.is_eaten && sequence.accepts(&question.question, block.value) {
+            sprite.color = REVEAL_COLOR;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_power_ups_run_out() {
+        let mut power_ups = ActivePowerUps::default();
+        power_ups.activate(PowerUp::DoubleScore);
+        assert_eq!(power_ups.score_multiplier(), 2);
+
+        assert!(power_ups.tick(Duration::from_secs(6)).is_empty());
+        // Picking it up again starts it over.
+        power_ups.activate(PowerUp::DoubleScore);
+        assert!(power_ups.tick(Duration::from_secs(6)).is_empty());
+        assert_eq!(power_ups.summary(None).unwrap(), "Double score 4s");
+
+        assert_eq!(
+            power_ups.tick(Duration::from_secs(5)),
+            [PowerUp::DoubleScore]
+        );
+        assert_eq!(power_ups.score_multiplier(), 1);
+        let shielded = Health {
+            shield_secs: 2.5,
+            ..default()
+        };
+        assert_eq!(power_ups.summary(Some(&shielded)).unwrap(), "Shield 3s");
+        assert_eq!(power_ups.summary(None), None);
+    }
+
+    #[test]
+    fn test_shield_absorbs_one_hit() {
+        let mut health = Health {
+            shield_secs: PowerUp::Shield.duration_secs(),
+            ..default()
+        };
+        assert!(!health.take_damage(1));
+        assert_eq!(health.current, health.max);
+        assert_eq!(health.shield_secs, 0.0);
+
+        health.invulnerable_timer = 0.0;
+        assert!(health.take_damage(1));
+        assert_eq!(health.current, health.max - 1);
+    }
+}
@@ -1,69 +1,749 @@
 //! Question-specific behavior.
 
 use bevy::{prelude::*, sprite::Anchor, text::TextBounds};
+use rand::{Rng, seq::IndexedRandom};
 
-use crate::{math_mono::{common::get_primary_window_size, components::{Question, QuestionDisplay}, game::{calculate_grid_layout, GridConfig}}, screens::Screen};
+use crate::{
+    AppSystems, PausableSystems,
+    math_mono::{
+        common::get_primary_window_size,
+        components::{
+            Combo, Health, NumberBlock, Player, QuestionDisplay, RivalScoreDisplay, RunningTotal,
+            ScoreDisplay, WorkedSolutionDisplay,
+        },
+        difficulty::Difficulty,
+        game::{
+            GridConfig, MAX_BLOCK_VALUE, MIN_BLOCK_VALUE,
+            boss::Boss,
+            calculate_grid_layout,
+            explanation::worked_solution,
+            hint::Hints,
+            math::{Expression, is_prime},
+            number::{Dimension, Number, Unit},
+            power_up::ActivePowerUps,
+            sequence::{Sequence, SequenceProgress},
+            sum_target::can_make,
+            word_problem::WordProblem,
+        },
+        level::{ActiveLevel, LevelProgress},
+        review::{RoundReview, next_question},
+        run::{GameMode, Party, RunRng, online, start_run},
+    },
+    save::SaveData,
+    screens::Screen,
+};
+
+/// Wrong answers to one question before its worked solution is shown.
+const WORKED_SOLUTION_AFTER_MISTAKES: u32 = 2;
 
 pub struct QuestionPlugin;
 
 impl Plugin for QuestionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, update_question)
-            .add_systems(OnEnter(Screen::Gameplay), spawn_question);
+        app.add_systems(
+            Update,
+            (
+                update_question.run_if(resource_exists_and_changed::<CurrentQuestion>),
+                update_worked_solution.run_if(
+                    resource_exists_and_changed::<CurrentQuestion>
+                        .or(resource_exists_and_changed::<RoundReview>),
+                ),
+                update_score_display,
+                // In a battle the server says when to move on.
+                advance_question_when_cleared
+                    .run_if(not(online))
+                    .in_set(AppSystems::Update)
+                    .in_set(PausableSystems),
+            )
+                .run_if(in_state(Screen::Gameplay)),
+        )
+        .add_systems(OnEnter(Screen::Gameplay), spawn_question.after(start_run));
+    }
+}
+
+/// A rule that decides which numbers on the board are correct answers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum Question {
+    Even,
+    Odd,
+    MultipleOf(i32),
+    Prime,
+    GreaterThan(Number),
+    LessThan(Number),
+    Sum(i32, i32),
+    Difference(i32, i32),
+    Product(i32, i32),
+    /// Fractions with the same value, like `2/4` and `3/6` for `1/2`.
+    EquivalentTo(Number),
+    /// A story to work out, like "Sam has 3 bags of 4 apples. How many apples?".
+    WordProblem(WordProblem),
+    /// An equation to solve, like `3x + 4 = 19`: eat the value of `x`. The right-hand
+    /// side is worked out from `x`, so there is always a whole-number answer.
+    SolveFor {
+        lhs: Expression,
+        x: i32,
+    },
+    /// Expressions to work out, like `2x + 6` when `x = 4`: eat the ones equal to `value`.
+    Evaluate {
+        x: i32,
+        value: i32,
+    },
+    /// Numbers that have to be eaten in order, see [`SequenceProgress`].
+    Sequence(Sequence),
+    /// A total to make by eating a chain of numbers that adds up to it, see [`RunningTotal`].
+    MakeTotal(i32),
+}
+
+/// What sort of numbers a question puts on the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TileKind {
+    /// From [`MIN_BLOCK_VALUE`] up to the board's largest number.
+    Whole,
+    /// Whole numbers from minus half the board's largest number to plus half, for
+    /// comparing with zero or below.
+    Negative,
+    /// Decimals with this many places, from 0 up to a tenth of the board's largest number.
+    Decimal(u32),
+    /// Fractions from `1/2` to `12/12`.
+    Fraction,
+    /// Dollars in steps of 5 cents, from `$0.00` up to a twentieth of the board's largest number.
+    Money,
+    /// Lengths in steps of 10 cm, some written in metres, from `10 cm` up to a tenth of
+    /// the board's largest number in metres.
+    Length,
+    /// Times in steps of 5 minutes, starting at `1:00`.
+    Clock,
+}
+
+/// The largest denominator on a board of fractions.
+const MAX_DENOMINATOR: i32 = 12;
+
+impl TileKind {
+    /// Smallest and largest tile, counted in [`Self::units_of`].
+    fn unit_range(&self, max_value: i32) -> (i32, i32) {
+        match *self {
+            Self::Whole | Self::Fraction | Self::Length => (MIN_BLOCK_VALUE, max_value),
+            Self::Negative => (-max_value / 2, max_value / 2),
+            Self::Decimal(places) => (0, max_value * 10_i32.pow(places - 1)),
+            Self::Money => (0, max_value),
+            Self::Clock => (12, 12 + max_value),
+        }
+    }
+
+    /// Count `value` in the smallest step between two tiles: ones, tenths, 5 cents...
+    /// Rounds down if `value` falls between two steps.
+    fn units_of(&self, value: Number) -> i32 {
+        let (scale, step) = match *self {
+            Self::Decimal(places) => (10_i32.pow(places), 1),
+            Self::Money => (value.unit().base_scale(), 5),
+            Self::Length => (value.unit().base_scale(), 10),
+            Self::Clock => (1, 5),
+            Self::Whole | Self::Negative | Self::Fraction => (1, 1),
+        };
+        (value.numer() * scale).div_euclid(value.denom() * step)
+    }
+
+    /// The tile `units` steps up, written in `unit` if this kind can be written in more than one.
+    fn with_units(&self, units: i32, unit: Unit) -> Number {
+        match *self {
+            Self::Decimal(places) => Number::decimal(units, places),
+            Self::Money => Number::dollars(units * 5),
+            Self::Length if unit == Unit::Metres && units % 10 == 0 => {
+                Number::int(units / 10).in_unit(Unit::Metres)
+            }
+            Self::Length if unit == Unit::Metres => Number::decimal(units, 1).in_unit(Unit::Metres),
+            Self::Length => Number::int(units * 10).in_unit(Unit::Centimetres),
+            Self::Clock => Number::int(units * 5).in_unit(Unit::Clock),
+            Self::Whole | Self::Negative | Self::Fraction => Number::int(units),
+        }
+    }
+
+    /// A unit to write a tile in, picked at random where there is a choice.
+    fn random_unit(&self, rng: &mut impl Rng) -> Unit {
+        match *self {
+            Self::Length if rng.random_bool(0.5) => Unit::Metres,
+            Self::Length => Unit::Centimetres,
+            _ => Unit::None,
+        }
+    }
+
+    fn random_fraction(rng: &mut impl Rng) -> Number {
+        let denom = rng.random_range(2..=MAX_DENOMINATOR);
+        Number::fraction(rng.random_range(1..=denom), denom)
+    }
+}
+
+impl Question {
+    pub fn text(&self) -> String {
+        match self {
+            Self::Even => "Eat the even numbers".to_string(),
+            Self::Odd => "Eat the odd numbers".to_string(),
+            Self::MultipleOf(n) => format!("Eat the multiples of {n}"),
+            Self::Prime => "Eat the prime numbers".to_string(),
+            Self::GreaterThan(n) => {
+                let dimension = n.unit().dimension();
+                format!(
+                    "Eat the {} {} {n}",
+                    dimension.noun(),
+                    dimension.greater_than()
+                )
+            }
+            Self::LessThan(n) => {
+                let dimension = n.unit().dimension();
+                format!("Eat the {} {} {n}", dimension.noun(), dimension.less_than())
+            }
+            Self::Sum(a, b) => format!("What is {a} + {b} ?"),
+            Self::Difference(a, b) => format!("What is {a} - {b} ?"),
+            Self::Product(a, b) => format!("What is {a} × {b} ?"),
+            Self::EquivalentTo(n) if n.unit() == Unit::None => {
+                format!("Eat the fractions equal to {n}")
+            }
+            Self::EquivalentTo(n) => {
+                format!("Eat the {} equal to {n}", n.unit().dimension().noun())
+            }
+            Self::WordProblem(problem) => problem.text(),
+            Self::SolveFor { lhs, x } => {
+                format!("Solve {lhs} = {}\nEat the value of x", lhs.evaluate(*x))
+            }
+            Self::Evaluate { x, value } => {
+                format!("x = {x}\nEat the expressions equal to {value}")
+            }
+            Self::Sequence(sequence) => sequence.text(),
+            Self::MakeTotal(n) => format!("Make {n}: eat numbers that add up to {n}"),
+        }
+    }
+
+    /// The skill this question practices, used to key learning statistics.
+    /// Multiples are tracked per times table, measurements per dimension; the other
+    /// questions ignore their numbers.
+    pub fn skill(&self) -> String {
+        let measurement = match self {
+            Self::GreaterThan(n) | Self::LessThan(n) | Self::EquivalentTo(n) => {
+                match n.unit().dimension() {
+                    Dimension::Number => None,
+                    Dimension::Money => Some("money"),
+                    Dimension::Length => Some("length"),
+                    Dimension::Time => Some("time"),
+                }
+            }
+            _ => None,
+        };
+        if let Some(skill) = measurement {
+            return skill.to_string();
+        }
+        match self {
+            Self::Even => "even".to_string(),
+            Self::Odd => "odd".to_string(),
+            Self::MultipleOf(n) => format!("multiple-of-{n}"),
+            Self::Prime => "prime".to_string(),
+            Self::GreaterThan(_) => "greater-than".to_string(),
+            Self::LessThan(_) => "less-than".to_string(),
+            Self::Sum(..) => "addition".to_string(),
+            Self::Difference(..) => "subtraction".to_string(),
+            Self::Product(..) => "multiplication".to_string(),
+            Self::EquivalentTo(_) => "equivalent-fractions".to_string(),
+            Self::WordProblem(_) => "word-problems".to_string(),
+            Self::SolveFor { .. } => "solve-for-x".to_string(),
+            Self::Evaluate { .. } => "evaluate-expressions".to_string(),
+            Self::Sequence(sequence) => sequence.skill().to_string(),
+            Self::MakeTotal(_) => "making-totals".to_string(),
+        }
+    }
+
+    fn tile_kind(&self) -> TileKind {
+        let (Self::GreaterThan(n) | Self::LessThan(n) | Self::EquivalentTo(n)) = *self else {
+            return TileKind::Whole;
+        };
+        match n.unit().dimension() {
+            Dimension::Money => TileKind::Money,
+            Dimension::Length => TileKind::Length,
+            Dimension::Time => TileKind::Clock,
+            Dimension::Number if matches!(self, Self::EquivalentTo(_)) => TileKind::Fraction,
+            Dimension::Number => match n.decimal_places() {
+                Some(places) if places > 0 => TileKind::Decimal(places),
+                _ if n <= Number::int(0) => TileKind::Negative,
+                _ => TileKind::Whole,
+            },
+        }
+    }
+
+    /// Whether eating `value` answers this question correctly.
+    pub fn is_correct(&self, value: Number) -> bool {
+        let whole = value.as_integer().filter(|_| value.unit() == Unit::None);
+        match *self {
+            Self::Even => whole.is_some_and(|value| value % 2 == 0),
+            Self::Odd => whole.is_some_and(|value| value % 2 != 0),
+            Self::MultipleOf(n) => whole.is_some_and(|value| value % n == 0),
+            Self::Prime => whole.is_some_and(is_prime),
+            Self::GreaterThan(n) => value.unit().dimension() == n.unit().dimension() && value > n,
+            Self::LessThan(n) => value.unit().dimension() == n.unit().dimension() && value < n,
+            Self::Sum(a, b) => value == Number::int(a + b),
+            Self::Difference(a, b) => value == Number::int(a - b),
+            Self::Product(a, b) => value == Number::int(a * b),
+            Self::EquivalentTo(n) => value == n,
+            Self::WordProblem(problem) => value == Number::int(problem.answer()),
+            Self::SolveFor { x, .. } => value == Number::int(x),
+            Self::Evaluate { value: target, .. } => value == Number::int(target),
+            Self::Sequence(sequence) => {
+                whole.is_some_and(|value| sequence.terms().contains(&value))
+            }
+            // Any number that doesn't overshoot on its own could be part of a chain.
+            Self::MakeTotal(n) => whole.is_some_and(|value| (1..=n).contains(&value)),
+        }
+    }
+
+    /// The total to make, for questions answered by a chain of numbers rather than one.
+    pub fn target_total(&self) -> Option<i32> {
+        match *self {
+            Self::MakeTotal(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Whether `remaining`, the numbers still on the board, leave nothing to answer.
+    pub fn is_cleared(&self, remaining: &[Number]) -> bool {
+        match *self {
+            Self::MakeTotal(n) => {
+                !can_make(remaining.iter().filter_map(|value| value.as_integer()), n)
+            }
+            _ => !remaining.iter().any(|&value| self.is_correct(value)),
+        }
+    }
+
+    /// The right answers in the order they must be eaten, for questions where order
+    /// matters. Each one is put on the board exactly once.
+    pub fn ordered_answers(&self) -> Option<Vec<Number>> {
+        match self {
+            Self::Sequence(sequence) => {
+                Some(sequence.terms().into_iter().map(Number::int).collect())
+            }
+            _ => None,
+        }
+    }
+
+    /// The smallest largest-number-on-the-board that still makes a fair board for this
+    /// question: enough right answers, and enough wrong ones.
+    pub fn min_block_range(&self) -> i32 {
+        let range = match (*self, self.tile_kind()) {
+            (Self::Even | Self::Odd, _) => 20,
+            (Self::MultipleOf(n), _) => n * 6,
+            (Self::Prime, _) => 30,
+            // Fraction boards don't depend on the largest number.
+            (_, TileKind::Fraction) => MIN_BLOCK_VALUE,
+            (
+                Self::GreaterThan(n) | Self::LessThan(n) | Self::EquivalentTo(n),
+                kind
+                @ (TileKind::Decimal(_) | TileKind::Money | TileKind::Length | TileKind::Clock),
+            ) => kind.units_of(n) - kind.unit_range(0).0 + 20,
+            (Self::GreaterThan(n) | Self::LessThan(n), TileKind::Negative) => {
+                (n.to_f32().abs().ceil() as i32 + 10) * 2
+            }
+            (Self::GreaterThan(n), _) => n.to_f32().ceil() as i32 + 20,
+            (Self::LessThan(n), _) => n.to_f32().ceil() as i32 * 2,
+            (Self::Sum(a, b), _) => a + b + 10,
+            (Self::Difference(a, b), _) => a - b + 10,
+            (Self::Product(a, b), _) => a * b + 10,
+            (Self::EquivalentTo(_), _) => MIN_BLOCK_VALUE,
+            (Self::WordProblem(problem), _) => problem.answer() + 10,
+            (Self::SolveFor { x, .. }, _) => x + 20,
+            (Self::Evaluate { value, .. }, _) => value + 20,
+            (Self::Sequence(sequence), _) => sequence.terms().into_iter().max().unwrap_or(0) + 10,
+            (Self::MakeTotal(n), _) => n + 10,
+        };
+        range.min(MAX_BLOCK_VALUE)
+    }
+
+    /// Pick a random number for a board whose largest whole number is `max_value`.
+    pub fn random_tile(&self, max_value: i32, rng: &mut impl Rng) -> Number {
+        let kind = self.tile_kind();
+        if kind == TileKind::Fraction {
+            return TileKind::random_fraction(rng);
+        }
+        let (min, max) = kind.unit_range(max_value);
+        let units = rng.random_range(min..=max);
+        kind.with_units(units, kind.random_unit(rng))
+    }
+
+    /// Whether `value` could appear on a board whose largest whole number is `max_value`.
+    pub fn fits_board(&self, value: Number, max_value: i32) -> bool {
+        let kind = self.tile_kind();
+        if kind == TileKind::Fraction {
+            return value.unit() == Unit::None
+                && (1..=value.denom()).contains(&value.numer())
+                && value.denom() <= MAX_DENOMINATOR;
+        }
+        let (min, max) = kind.unit_range(max_value);
+        let units = kind.units_of(value);
+        value == kind.with_units(units, value.unit()) && (min..=max).contains(&units)
+    }
+
+    /// A wrong-looking number `steps` away from `value`, written the same way.
+    pub fn nudged(&self, value: Number, steps: i32) -> Number {
+        match self.tile_kind() {
+            TileKind::Fraction => value.nudged(steps),
+            kind => kind.with_units(kind.units_of(value) + steps, value.unit()),
+        }
+    }
+
+    /// Pick a random correct answer that fits a board whose largest whole number is `max_value`.
+    pub fn random_answer(&self, max_value: i32, rng: &mut impl Rng) -> Number {
+        let kind = self.tile_kind();
+        let (min, max) = kind.unit_range(max_value);
+        let unit = kind.random_unit(rng);
+        match *self {
+            Self::Even => Number::int(rng.random_range(1..=max_value / 2) * 2),
+            Self::Odd => Number::int(rng.random_range(0..(max_value + 1) / 2) * 2 + 1),
+            Self::MultipleOf(n) => Number::int(rng.random_range(1..=max_value / n) * n),
+            Self::Prime => {
+                let primes: Vec<i32> = (MIN_BLOCK_VALUE..=max_value)
+                    .filter(|&n| is_prime(n))
+                    .collect();
+                Number::int(*primes.choose(rng).unwrap())
+            }
+            Self::GreaterThan(n) => {
+                kind.with_units(rng.random_range(kind.units_of(n) + 1..=max), unit)
+            }
+            Self::LessThan(n) => {
+                // Count up to the last step below `n`, even when `n` falls between steps.
+                let at = kind.units_of(n);
+                let below = at - i32::from(kind.with_units(at, n.unit()) == n);
+                kind.with_units(rng.random_range(min..=below), unit)
+            }
+            Self::Sum(a, b) => Number::int(a + b),
+            Self::Difference(a, b) => Number::int(a - b),
+            Self::Product(a, b) => Number::int(a * b),
+            Self::EquivalentTo(n) if kind == TileKind::Fraction => {
+                let simplest = n.simplified();
+                let scale = rng.random_range(1..=MAX_DENOMINATOR / simplest.denom());
+                Number::fraction(simplest.numer() * scale, simplest.denom() * scale)
+            }
+            Self::EquivalentTo(n) => kind.with_units(kind.units_of(n), unit),
+            Self::WordProblem(problem) => Number::int(problem.answer()),
+            Self::SolveFor { x, .. } => Number::int(x),
+            Self::Evaluate { value, .. } => Number::int(value),
+            Self::Sequence(sequence) => Number::int(*sequence.terms().choose(rng).unwrap()),
+            Self::MakeTotal(n) => Number::int(rng.random_range(1..=n)),
+        }
+    }
+
+    /// What to write on a tile worth `value`: usually the number itself, but on
+    /// expression boards an expression in `x` that works out to it.
+    pub fn tile_text(&self, value: Number, rng: &mut impl Rng) -> String {
+        let (Self::Evaluate { x, .. }, Some(value)) = (*self, value.as_integer()) else {
+            return value.stacked();
+        };
+        random_expression(x, value, rng).to_string()
+    }
+
+    /// Wrong answers worth putting on the board because they come from a likely slip,
+    /// rather than just sitting near a right answer.
+    pub fn near_misses(&self) -> Vec<Number> {
+        match *self {
+            Self::WordProblem(problem) => problem.near_misses(),
+            Self::SolveFor { lhs, x } => {
+                let rhs = lhs.evaluate(x);
+                // The right-hand side itself, then undoing the constant the wrong way, then
+                // forgetting to divide.
+                let slips = [
+                    Some(rhs),
+                    lhs.with_numbers(lhs.coefficient(), -lhs.constant())
+                        .solve(rhs),
+                    lhs.with_numbers(1, lhs.constant()).solve(rhs),
+                ];
+                slips
+                    .into_iter()
+                    .flatten()
+                    .filter(|&slip| slip != x)
+                    .map(Number::int)
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// This question ready to ask: word problems get their numbers and name filled in, and
+    /// algebra questions keep their shape but get new numbers. Other questions are asked
+    /// as they are, without using `rng`.
+    pub fn with_random_slots(self, rng: &mut impl Rng) -> Self {
+        match self {
+            Self::WordProblem(problem) => Self::WordProblem(problem.with_random_slots(rng)),
+            Self::SolveFor { lhs, .. } => random_equation(lhs, rng),
+            Self::Evaluate { .. } => Self::Evaluate {
+                x: rng.random_range(2..=9),
+                value: rng.random_range(10..=40),
+            },
+            question => question,
+        }
+    }
+}
+
+/// The largest number `x` is multiplied by in generated algebra.
+const MAX_COEFFICIENT: i32 = 5;
+
+/// A new equation shaped like `lhs`: a coefficient of 1 stays 1, a missing constant stays
+/// missing, and a constant taken away is still taken away. The right-hand side is always
+/// positive.
+fn random_equation(lhs: Expression, rng: &mut impl Rng) -> Question {
+    let coefficient = match lhs.coefficient() {
+        1 => 1,
+        _ => rng.random_range(2..=MAX_COEFFICIENT),
+    };
+    let max_constant = match lhs {
+        Expression::Linear { .. } => 20,
+        Expression::Bracketed { .. } => 6,
+    };
+    let constant = lhs.constant().signum() * rng.random_range(1..=max_constant);
+    let lhs = lhs.with_numbers(coefficient, constant);
+    // The smallest positive `x` that keeps the right-hand side above zero.
+    let min_x = (1..).find(|&x| lhs.evaluate(x) > 0).unwrap();
+    Question::SolveFor {
+        lhs,
+        x: rng.random_range(min_x..min_x + 12),
+    }
+}
+
+/// A random expression worth `value` when `x` is `x`.
+fn random_expression(x: i32, value: i32, rng: &mut impl Rng) -> Expression {
+    let coefficient = rng.random_range(1..=MAX_COEFFICIENT);
+    let bracketed = Expression::bracketed(coefficient, value / coefficient - x);
+    if coefficient > 1
+        && bracketed.constant() != 0
+        && bracketed.evaluate(x) == value
+        && rng.random_bool(0.5)
+    {
+        bracketed
+    } else {
+        Expression::linear(coefficient, value - coefficient * x)
     }
 }
 
-pub fn update_question(mut question_query: Query<&mut Question>) {
-    for mut question in question_query.iter_mut() {
-        question.text = "Hello, world!".to_string();
+/// The question the player is currently answering, and how many came before it this run.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct CurrentQuestion {
+    pub question: Question,
+    pub round: u32,
+}
+
+pub fn update_question(
+    question: Res<CurrentQuestion>,
+    mut display_query: Query<&mut Text2d, With<QuestionDisplay>>,
+) {
+    for mut text in display_query.iter_mut() {
+        text.0 = question.question.text();
     }
 }
 
-#[derive(Component)]
-struct AnimateScale;
+/// Show how to work out the answer once the player has struggled with it.
+fn update_worked_solution(
+    question: Res<CurrentQuestion>,
+    round: Res<RoundReview>,
+    mut display_query: Query<&mut Text2d, With<WorkedSolutionDisplay>>,
+) {
+    let steps = worked_solution(&question.question)
+        .filter(|_| round.mistakes >= WORKED_SOLUTION_AFTER_MISTAKES)
+        .unwrap_or_default();
+    for mut text in display_query.iter_mut() {
+        text.0 = steps.join("\n");
+    }
+}
+
+fn update_score_display(
+    level: Res<ActiveLevel>,
+    progress: Res<LevelProgress>,
+    hints: Option<Res<Hints>>,
+    question: Res<CurrentQuestion>,
+    sequence: Res<SequenceProgress>,
+    power_ups: Option<Res<ActivePowerUps>>,
+    party: Res<Party>,
+    player_query: Query<(&Player, &Health, &RunningTotal, &Combo)>,
+    mut display_query: Query<&mut Text2d, (With<ScoreDisplay>, Without<RivalScoreDisplay>)>,
+    mut rival_display_query: Query<&mut Text2d, With<RivalScoreDisplay>>,
+) {
+    let mut players: Vec<_> = player_query.iter().collect();
+    players.sort_by_key(|(player, ..)| player.index);
+    let solo = match players[..] {
+        [] => return,
+        [(_, health, ..)] => Some(health),
+        _ => None,
+    };
+    // With two players each line about one of them starts with who it's about.
+    let prefix = |player: &Player| match solo {
+        Some(_) => String::new(),
+        None => format!("P{} ", player.index + 1),
+    };
+
+    // In versus each player's lines sit on their own side of the board.
+    let versus = *party == Party::Versus;
+    let mut display = String::new();
+    let mut rival_display = String::new();
+    for (player, health, running, combo) in &players {
+        let prefix = prefix(player);
+        let lines = if versus && player.index > 0 {
+            &mut rival_display
+        } else {
+            &mut display
+        };
+        *lines += &format!(
+            "{prefix}Score: {}\n{prefix}Health: {}/{}\n",
+            player.score, health.current, health.max
+        );
+        if versus {
+            *lines += &format!("{prefix}Right: {}\n", player.correct_answers);
+            if player.stunned_secs > 0.0 {
+                *lines += &format!("{prefix}Stunned!\n");
+            }
+        }
+        if solo.is_none() && health.shield_secs > 0.0 {
+            *lines += &format!("{prefix}Shield: {}s\n", health.shield_secs.ceil());
+        }
+        if combo.streak > 0 {
+            *lines += &format!(
+                "{prefix}Streak: {} (x{})\n",
+                combo.streak,
+                combo.multiplier()
+            );
+        }
+        if let Some(target) = question.question.target_total().filter(|_| versus) {
+            *lines += &format!("{prefix}Total: {}/{target}\n", running.total);
+        }
+    }
+    display.pop();
+    rival_display.pop();
+    if let Some(goal) = level.definition.goal {
+        display += &format!("\nGoal: {}/{}", progress.correct_answers, goal);
+    }
+    // The stars completing the level now would earn, scored together like at the end.
+    if level.index.is_some() && level.definition.goal.is_some() && !versus {
+        let thresholds = level.definition.stars.for_players(party.player_count());
+        let score = players.iter().map(|(player, ..)| player.score).sum();
+        let stars = thresholds.stars(score);
+        display += &format!("\nStars: {stars}/3");
+        if let Some(next) = thresholds.next(stars) {
+            display += &format!(" ({} at {next})", stars + 1);
+        }
+    }
+    if let Some(hints) = hints {
+        let ready = if hints.is_ready() { " (H)" } else { "" };
+        display += &format!("\nHints: {}{ready}", hints.uses_left);
+    }
+    if let Some(summary) = sequence.summary(&question.question) {
+        display += &format!("\nSo far: {summary}");
+    }
+    if let Some(target) = question.question.target_total().filter(|_| !versus) {
+        for (player, _, running, _) in &players {
+            display += &format!("\n{}Total: {}/{target}", prefix(player), running.total);
+        }
+    }
+    if let Some(summary) = power_ups.and_then(|power_ups| power_ups.summary(solo)) {
+        display += &format!("\nPower-ups: {summary}");
+    }
+    // Only touch the text when it changes, so it isn't laid out again every frame.
+    for mut text in display_query.iter_mut() {
+        if text.0 != display {
+            text.0.clone_from(&display);
+        }
+    }
+    if versus {
+        for mut text in rival_display_query.iter_mut() {
+            if text.0 != rival_display {
+                text.0.clone_from(&rival_display);
+            }
+        }
+    }
+}
+
+/// Move on to the next question once every correct number on the board has been eaten,
+/// or no chain of numbers left can make the total.
+fn advance_question_when_cleared(
+    mode: Res<GameMode>,
+    party: Res<Party>,
+    level: Res<ActiveLevel>,
+    difficulty: Res<Difficulty>,
+    mut question: ResMut<CurrentQuestion>,
+    mut round: ResMut<RoundReview>,
+    mut sequence: ResMut<SequenceProgress>,
+    mut save: ResMut<SaveData>,
+    mut rng: ResMut<RunRng>,
+    block_query: Query<&NumberBlock>,
+    running_query: Query<&RunningTotal>,
+    boss_query: Query<&Boss>,
+) {
+    // Wait for a chain in progress to finish.
+    if block_query.is_empty() || running_query.iter().any(|running| running.total != 0) {
+        return;
+    }
+    let remaining: Vec<Number> = block_query
+        .iter()
+        .filter(|block| !block.is_eaten)
+        .map(|block| block.value)
+        .collect();
+    if question.question.is_cleared(&remaining) {
+        // Rounds cleared together can't say which player knew the answers, so only
+        // solo rounds move a skill between boxes.
+        if *mode == GameMode::Classic && *party == Party::Solo {
+            save.review
+                .record(question.question.skill(), round.mistakes == 0);
+        }
+        *round = RoundReview::default();
+        *sequence = SequenceProgress::default();
+        question.question = match boss_query.single() {
+            // A boss keeps asking its current phase's question.
+            Ok(boss) => boss
+                .question(level.definition.questions)
+                .with_random_slots(&mut rng.questions),
+            Err(_) => next_question(
+                *mode,
+                &level.definition,
+                &difficulty,
+                &save,
+                &mut rng.questions,
+            ),
+        };
+        question.round += 1;
+    }
+}
 
 pub fn spawn_question(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     config: Res<GridConfig>,
+    mode: Res<GameMode>,
+    party: Res<Party>,
     windows: Query<&Window>,
 ) {
     let font = asset_server.load("fonts/MonofurNerdFont-Bold.ttf");
     let window_size = get_primary_window_size(windows.single().unwrap());
 
     // Calculate grid layout to determine available space
-    let (total_width, total_height, bottom_left_x, bottom_left_y) = calculate_grid_layout(&config);
-    println!("total_width: {}", total_width);
-    println!("total_height: {}", total_height);
-    println!("bottom_left_x: {}", bottom_left_x);
-    println!("bottom_left_y: {}", bottom_left_y);
-
+    let (total_width, total_height, bottom_left_x, _) = calculate_grid_layout(&config);
 
     let unplayable_margin = calculate_unplayable_margin(window_size.x, total_width);
-    println!("unplayable_margin: {}", unplayable_margin);
     let unplayable_margin_percentage = 0.01;
-    let box_size = calculate_text_box_size(unplayable_margin, total_height, unplayable_margin_percentage);
+    let box_size = calculate_text_box_size(
+        unplayable_margin,
+        total_height,
+        unplayable_margin_percentage,
+    );
     let box_position = calculate_text_box_position(bottom_left_x, unplayable_margin);
 
-
-
-
-    let question_text = "What is 10 + 10 ?";
-
     let slightly_smaller_text_font = TextFont {
         font,
         font_size: 30.0,
         ..default()
     };
+    let rival_text_font = slightly_smaller_text_font.clone();
 
     commands
         .spawn((
+            Name::new("Question Panel"),
             Sprite::from_color(Color::BLACK, box_size),
             Transform::from_translation(box_position.extend(0.0)),
+            StateScoped(Screen::Gameplay),
         ))
         .with_children(|builder| {
             builder.spawn((
-                Text2d::new(question_text),
+                Text2d::default(),
+                QuestionDisplay,
                 slightly_smaller_text_font.clone(),
                 TextLayout::new(JustifyText::Center, LineBreak::WordBoundary),
                 // Wrap text in the rectangle
@@ -73,7 +753,49 @@ pub fn spawn_question(
                 // anchor
                 Anchor::Center,
             ));
+            builder.spawn((
+                Text2d::default(),
+                ScoreDisplay,
+                slightly_smaller_text_font.clone(),
+                TextLayout::new(JustifyText::Center, LineBreak::WordBoundary),
+                // Sit just below the question box
+                Transform::from_translation(Vec3::new(0.0, -box_size.y / 2.0, 1.0)),
+                Anchor::TopCenter,
+            ));
+            builder.spawn((
+                Text2d::default(),
+                WorkedSolutionDisplay,
+                TextFont {
+                    font_size: 20.0,
+                    ..slightly_smaller_text_font
+                },
+                TextLayout::new(JustifyText::Left, LineBreak::WordBoundary),
+                TextBounds::new_horizontal(box_size.x),
+                // Sit just above the question box
+                Transform::from_translation(Vec3::new(0.0, box_size.y / 2.0, 1.0)),
+                Anchor::BottomCenter,
+            ));
         });
+
+    // Versus and battles show the other players on the far side of the board.
+    if *party == Party::Versus || matches!(*mode, GameMode::Online { .. }) {
+        // Mirror the question panel.
+        commands.spawn((
+            Name::new("Rival Panel"),
+            Sprite::from_color(Color::BLACK, box_size),
+            Transform::from_translation(Vec3::new(-box_position.x, box_position.y, 0.0)),
+            StateScoped(Screen::Gameplay),
+            children![(
+                Text2d::default(),
+                RivalScoreDisplay,
+                rival_text_font,
+                TextLayout::new(JustifyText::Center, LineBreak::WordBoundary),
+                TextBounds::from(box_size),
+                Transform::from_translation(Vec3::Z),
+                Anchor::Center,
+            )],
+        ));
+    }
 }
 
 fn calculate_unplayable_margin(totol_window_width: f32, total_grid_width: f32) -> f32 {
@@ -82,7 +804,11 @@ fn calculate_unplayable_margin(totol_window_width: f32, total_grid_width: f32) -
     (totol_window_width - total_grid_width) / 2.0
 }
 
-fn calculate_text_box_size(unplayable_margin: f32, total_height: f32, unplayable_margin_percentage: f32) -> Vec2 {
+fn calculate_text_box_size(
+    unplayable_margin: f32,
+    total_height: f32,
+    unplayable_margin_percentage: f32,
+) -> Vec2 {
     // margin_percentage is the percentage of the margin to use for the text box
     // 1.0 means use the entire margin
     // 0.5 means use half of the margin
@@ -105,6 +831,101 @@ fn calculate_text_box_position(bottom_left_x: f32, unplayable_margin: f32) -> Ve
     Vec2::new(left_edge_x + offset, 0.0)
 }
 
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::math_mono::level::{DAILY_LEVEL, LEVELS};
+
+    #[test]
+    fn test_random_answers_are_correct() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let questions = LEVELS
+            .iter()
+            .chain([&DAILY_LEVEL])
+            .flat_map(|level| level.questions);
+        for &question in questions {
+            let question = question.with_random_slots(&mut rng);
+            let max_value = question.min_block_range();
+            let answer = question.random_answer(max_value, &mut rng);
+            assert!(
+                question.is_correct(answer),
+                "{question:?} rejected {answer}"
+            );
+            assert!(
+                question.fits_board(answer, max_value),
+                "{answer} is off the board"
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_correct() {
+        assert!(Question::MultipleOf(3).is_correct(Number::int(21)));
+        assert!(!Question::MultipleOf(3).is_correct(Number::int(22)));
+        assert!(Question::Sum(10, 10).is_correct(Number::int(20)));
+        assert!(!Question::Odd.is_correct(Number::int(20)));
+        assert!(!Question::Even.is_correct(Number::fraction(1, 2)));
+        assert!(Question::Even.is_correct(Number::fraction(8, 2)));
+    }
+
+    #[test]
+    fn test_boards_beyond_whole_numbers() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let half = Question::EquivalentTo(Number::fraction(1, 2));
+        assert!(half.is_correct(Number::fraction(3, 6)));
+        assert!(!half.is_correct(Number::fraction(3, 5)));
 
+        let below_minus_three = Question::LessThan(Number::int(-3));
+        let max_value = below_minus_three.min_block_range();
+        let tiles: Vec<Number> = (0..100)
+            .map(|_| below_minus_three.random_tile(max_value, &mut rng))
+            .collect();
+        assert!(tiles.iter().any(|tile| tile.is_negative()));
+        assert!(
+            tiles
+                .iter()
+                .all(|&tile| below_minus_three.fits_board(tile, max_value))
+        );
 
+        let above = Question::GreaterThan(Number::decimal(25, 1));
+        let answer = above.random_answer(above.min_block_range(), &mut rng);
+        assert_eq!(answer.decimal_places(), Some(1));
+        assert!(answer > Number::decimal(25, 1));
+    }
+
+    #[test]
+    fn test_generated_equations_have_whole_answers() {
+        let mut rng = StdRng::seed_from_u64(11);
+        for lhs in [Expression::linear(3, -4), Expression::bracketed(2, 3)] {
+            for _ in 0..50 {
+                let Question::SolveFor { lhs, x } = random_equation(lhs, &mut rng) else {
+                    unreachable!();
+                };
+                let rhs = lhs.evaluate(x);
+                assert!(rhs > 0, "{lhs} = {rhs}");
+                assert_eq!(lhs.solve(rhs), Some(x));
+            }
+        }
+
+        for _ in 0..20 {
+            let expression = random_expression(4, 14, &mut rng);
+            assert_eq!(expression.evaluate(4), 14, "{expression}");
+        }
+    }
 
+    #[test]
+    fn test_measurement_questions() {
+        let later = Question::GreaterThan(Number::clock(2, 30));
+        assert_eq!(later.text(), "Eat the times later than 2:30");
+        assert!(later.is_correct(Number::clock(3, 45)));
+        assert!(!later.is_correct(Number::int(300)));
+
+        let metres = Question::EquivalentTo(Number::decimal(15, 1).in_unit(Unit::Metres));
+        assert_eq!(metres.text(), "Eat the lengths equal to 1.5 m");
+        assert!(metres.is_correct(Number::int(150).in_unit(Unit::Centimetres)));
+        assert!(!metres.is_correct(Number::int(15).in_unit(Unit::Centimetres)));
+        assert_eq!(metres.skill(), "length");
+    }
+}
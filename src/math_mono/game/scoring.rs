@@ -0,0 +1,206 @@
+//! Scoring: points for right answers, multiplied by streaks and topped up for speed, and
+//! the stars a level's score earns.
+
+use bevy::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    math_mono::{
+        components::{Combo, Health, NumberBlock, Player},
+        game::{
+            player::{AnswerEaten, CORRECT_ANSWER_POINTS},
+            power_up::ActivePowerUps,
+            question::CurrentQuestion,
+        },
+    },
+    screens::Screen,
+};
+
+/// Every this many right answers in a row adds one to the streak multiplier.
+const STREAK_STEP: u32 = 5;
+const MAX_STREAK_MULTIPLIER: i32 = 4;
+/// Right answers within this many seconds of the last answer or a new question score
+/// [`SPEED_BONUS_POINTS`] extra.
+const SPEED_BONUS_SECS: f32 = 3.0;
+const SPEED_BONUS_POINTS: i32 = 5;
+/// Added to the score for completing a level without being hurt.
+const NO_DAMAGE_BONUS_POINTS: i32 = 100;
+
+pub struct ScoringPlugin;
+
+impl Plugin for ScoringPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                tick_combo_timers.in_set(AppSystems::TickTimers),
+                (
+                    restart_speed_bonus_on_new_question.run_if(resource_changed::<CurrentQuestion>),
+                    score_answers,
+                )
+                    .chain()
+                    .in_set(AppSystems::Update),
+            )
+                .in_set(PausableSystems)
+                .run_if(in_state(Screen::Gameplay)),
+        );
+    }
+}
+
+impl Combo {
+    /// What the next right answer's points are multiplied by, from the streak so far.
+    pub fn multiplier(&self) -> i32 {
+        (1 + ((self.streak + 1) / STREAK_STEP) as i32).min(MAX_STREAK_MULTIPLIER)
+    }
+
+    /// Count a right answer worth `base` points before bonuses. Returns the points scored.
+    pub fn score_correct(&mut self, base: i32) -> i32 {
+        let mut points = base * self.multiplier();
+        if self.since_last_answer.elapsed_secs() <= SPEED_BONUS_SECS {
+            points += SPEED_BONUS_POINTS;
+        }
+        self.streak += 1;
+        self.best_streak = self.best_streak.max(self.streak);
+        self.since_last_answer.reset();
+        points
+    }
+
+    /// A wrong answer starts the streak again.
+    pub fn break_streak(&mut self) {
+        self.streak = 0;
+        self.since_last_answer.reset();
+    }
+}
+
+/// Score needed for each number of stars on a completed level. Completing it at all
+/// earns one star.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StarThresholds {
+    pub two_stars: i32,
+    pub three_stars: i32,
+}
+
+impl StarThresholds {
+    pub const fn new(two_stars: i32, three_stars: i32) -> Self {
+        Self {
+            two_stars,
+            three_stars,
+        }
+    }
+
+    /// The thresholds for `players` scoring together. They're set for one player, and
+    /// every extra player adds their own points to the score.
+    pub fn for_players(&self, players: usize) -> Self {
+        let players = players.max(1) as i32;
+        Self::new(self.two_stars * players, self.three_stars * players)
+    }
+
+    /// Stars earned for completing a level with `score`, from 1 to 3.
+    pub fn stars(&self, score: i32) -> u8 {
+        if score >= self.three_stars {
+            3
+        } else if score >= self.two_stars {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Score needed for one more star than `stars`, or `None` at three stars.
+    pub fn next(&self, stars: u8) -> Option<i32> {
+        match stars {
+            0 | 1 => Some(self.two_stars),
+            2 => Some(self.three_stars),
+            _ => None,
+        }
+    }
+}
+
+/// Points added for finishing a level with `health`, if it never took a hit.
+pub fn no_damage_bonus(health: &Health) -> i32 {
+    if health.hits_taken == 0 {
+        NO_DAMAGE_BONUS_POINTS
+    } else {
+        0
+    }
+}
+
+fn tick_combo_timers(time: Res<Time>, mut combo_query: Query<&mut Combo>) {
+    for mut combo in &mut combo_query {
+        combo.since_last_answer.tick(time.delta());
+    }
+}
+
+fn restart_speed_bonus_on_new_question(mut combo_query: Query<&mut Combo>) {
+    for mut combo in &mut combo_query {
+        combo.since_last_answer.reset();
+    }
+}
+
+fn score_answers(
+    mut eaten_events: EventReader<AnswerEaten>,
+    power_ups: Res<ActivePowerUps>,
+    mut player_query: Query<(&mut Player, &mut Combo)>,
+    mut block_query: Query<&mut NumberBlock>,
+) {
+    for event in eaten_events.read() {
+        // Each player scores and keeps a streak of their own.
+        let Ok((mut player, mut combo)) = player_query.get_mut(event.player) else {
+            continue;
+        };
+        if !event.correct {
+            combo.break_streak();
+            continue;
+        }
+        let base = CORRECT_ANSWER_POINTS * event.blocks as i32 * power_ups.score_multiplier();
+        let points = combo.score_correct(base);
+        player.score += points;
+        player.correct_answers += 1;
+        // In versus the tile remembers what it scored, for whoever steals it.
+        if let Some(claim) = block_query
+            .get_mut(event.block)
+            .ok()
+            .and_then(|block| block.into_inner().claim.as_mut())
+            .filter(|claim| claim.owner == event.player)
+        {
+            claim.points = points;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_streaks_multiply_and_speed_adds() {
+        let mut combo = Combo::default();
+        // Quick answers get the speed bonus.
+        assert_eq!(combo.score_correct(10), 15);
+        combo.since_last_answer.tick(Duration::from_secs(5));
+        assert_eq!(combo.score_correct(10), 10);
+        combo.streak = STREAK_STEP - 1;
+        assert_eq!(combo.multiplier(), 2);
+        assert_eq!(combo.score_correct(10), 25);
+        assert_eq!(combo.best_streak, STREAK_STEP);
+
+        combo.break_streak();
+        assert_eq!(combo.multiplier(), 1);
+        combo.streak = 100;
+        assert_eq!(combo.multiplier(), MAX_STREAK_MULTIPLIER);
+    }
+
+    #[test]
+    fn test_star_thresholds() {
+        let thresholds = StarThresholds::new(150, 300);
+        assert_eq!(thresholds.stars(0), 1);
+        assert_eq!(thresholds.stars(150), 2);
+        assert_eq!(thresholds.stars(450), 3);
+        assert_eq!(thresholds.for_players(2).stars(450), 2);
+        assert_eq!(thresholds.next(1), Some(150));
+        assert_eq!(thresholds.next(3), None);
+        assert_eq!(no_damage_bonus(&Health::default()), NO_DAMAGE_BONUS_POINTS);
+    }
+}
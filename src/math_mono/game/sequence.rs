@@ -0,0 +1,138 @@
+//! Sequence questions, where the right numbers have to be eaten in order.
+
+use bevy::prelude::*;
+
+use crate::{
+    math_mono::game::{math::is_prime, number::Number, question::Question},
+    screens::Screen,
+};
+
+pub struct SequencePlugin;
+
+impl Plugin for SequencePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SequenceProgress>();
+        app.add_systems(OnEnter(Screen::Gameplay), reset_sequence_progress);
+    }
+}
+
+/// Numbers to eat one after another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum Sequence {
+    /// `start`, `start + step`, `start + 2 × step`... for `length` numbers. A negative
+    /// `step` counts down.
+    Arithmetic { start: i32, step: i32, length: u32 },
+    /// The first `length` primes from `from` upwards.
+    Primes { from: i32, length: u32 },
+}
+
+impl Sequence {
+    /// Every number in the sequence, in the order to eat them.
+    pub fn terms(&self) -> Vec<i32> {
+        match *self {
+            Self::Arithmetic {
+                start,
+                step,
+                length,
+            } => (0..length as i32)
+                .map(|index| start + index * step)
+                .collect(),
+            Self::Primes { from, length } => (from..)
+                .filter(|&n| is_prime(n))
+                .take(length as usize)
+                .collect(),
+        }
+    }
+
+    pub fn text(&self) -> String {
+        match *self {
+            Self::Arithmetic { step, .. } if step > 0 => {
+                let terms: Vec<String> = self.terms().iter().map(i32::to_string).collect();
+                format!("Eat {} in order", terms.join(", "))
+            }
+            Self::Arithmetic {
+                start,
+                step,
+                length,
+            } => format!("Count down by {} from {start}: eat {length} numbers", -step),
+            Self::Primes { from, length } => {
+                format!("Eat the first {length} primes from {from} up, in order")
+            }
+        }
+    }
+
+    pub fn skill(&self) -> &'static str {
+        match *self {
+            Self::Arithmetic { step, .. } if step > 0 => "counting-up",
+            Self::Arithmetic { .. } => "counting-down",
+            Self::Primes { .. } => "ordered-primes",
+        }
+    }
+}
+
+/// The numbers of the current sequence eaten so far.
+///
+/// Emptied whenever a new question is asked.
+#[derive(Resource, Debug, Default)]
+pub struct SequenceProgress {
+    pub eaten: Vec<Number>,
+}
+
+impl SequenceProgress {
+    /// Whether eating `value` right now answers `question`. For sequences only the next
+    /// number in order does; for other questions any right answer does.
+    pub fn accepts(&self, question: &Question, value: Number) -> bool {
+        match question.ordered_answers() {
+            Some(answers) => answers.get(self.eaten.len()) == Some(&value),
+            None => question.is_correct(value),
+        }
+    }
+
+    /// The sequence built so far with gaps for the numbers still to eat, like
+    /// `2, 4, _, _`. `None` for questions that aren't sequences.
+    pub fn summary(&self, question: &Question) -> Option<String> {
+        let answers = question.ordered_answers()?;
+        let slots: Vec<String> = (0..answers.len())
+            .map(|index| match self.eaten.get(index) {
+                Some(value) => value.to_string(),
+                None => "_".to_string(),
+            })
+            .collect();
+        Some(slots.join(", "))
+    }
+}
+
+fn reset_sequence_progress(mut progress: ResMut<SequenceProgress>) {
+    *progress = SequenceProgress::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sequences_must_be_eaten_in_order() {
+        let question = Question::Sequence(Sequence::Arithmetic {
+            start: 30,
+            step: -3,
+            length: 4,
+        });
+        let mut progress = SequenceProgress::default();
+        assert!(!progress.accepts(&question, Number::int(27)));
+        assert!(progress.accepts(&question, Number::int(30)));
+
+        progress.eaten.push(Number::int(30));
+        assert!(progress.accepts(&question, Number::int(27)));
+        assert_eq!(progress.summary(&question).unwrap(), "30, _, _, _");
+        assert_eq!(progress.summary(&Question::Even), None);
+    }
+
+    #[test]
+    fn test_prime_sequence() {
+        let primes = Sequence::Primes {
+            from: 10,
+            length: 4,
+        };
+        assert_eq!(primes.terms(), [11, 13, 17, 19]);
+    }
+}
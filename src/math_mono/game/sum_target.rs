@@ -0,0 +1,118 @@
+//! Make-a-total questions: the player eats a chain of numbers that adds up to a target.
+
+use rand::Rng;
+
+use crate::math_mono::components::RunningTotal;
+
+/// What eating one more number did to the chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainStep {
+    /// Still below the target.
+    Building,
+    /// Hit the target exactly.
+    Made,
+    /// Went past the target.
+    Overshot,
+}
+
+impl RunningTotal {
+    /// Add a number to the chain and see where that leaves it. A chain that is made or
+    /// overshot starts again from zero; the caller decides what happens to its blocks.
+    pub fn add(&mut self, value: i32, target: i32) -> ChainStep {
+        self.total += value;
+        if self.total < target {
+            return ChainStep::Building;
+        }
+        let step = if self.total == target {
+            ChainStep::Made
+        } else {
+            ChainStep::Overshot
+        };
+        self.total = 0;
+        step
+    }
+}
+
+/// Whether some of `values`, each used at most once, add up to exactly `target`.
+pub fn can_make(values: impl IntoIterator<Item = i32>, target: i32) -> bool {
+    if target <= 0 {
+        return target == 0;
+    }
+    let target = target as usize;
+    let mut reachable = vec![false; target + 1];
+    reachable[0] = true;
+    for value in values {
+        if value <= 0 || value as usize > target {
+            continue;
+        }
+        for total in (value as usize..=target).rev() {
+            reachable[total] |= reachable[total - value as usize];
+        }
+    }
+    reachable[target]
+}
+
+/// Whether eating `value` next can still lead to exactly `needed`, using the rest of
+/// `remaining`. `remaining` includes `value` itself.
+pub fn can_finish_with(value: i32, remaining: &[i32], needed: i32) -> bool {
+    let Some(index) = remaining.iter().position(|&other| other == value) else {
+        return false;
+    };
+    let others = remaining
+        .iter()
+        .enumerate()
+        .filter(|&(other, _)| other != index)
+        .map(|(_, &other)| other);
+    value <= needed && can_make(others, needed - value)
+}
+
+/// Two or three positive whole numbers that add up to `target`.
+pub fn random_combination(target: i32, rng: &mut impl Rng) -> Vec<i32> {
+    let parts = if target >= 6 && rng.random_bool(0.5) {
+        3
+    } else {
+        2
+    };
+    let mut left = target;
+    let mut combination = Vec::new();
+    for remaining_parts in (2..=parts).rev() {
+        let part = rng.random_range(1..=left - (remaining_parts - 1));
+        combination.push(part);
+        left -= part;
+    }
+    combination.push(left);
+    combination
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+
+    #[test]
+    fn test_running_total() {
+        let mut running = RunningTotal::default();
+        assert_eq!(running.add(7, 10), ChainStep::Building);
+        assert_eq!(running.add(3, 10), ChainStep::Made);
+        assert_eq!(running.total, 0);
+        assert_eq!(running.add(8, 10), ChainStep::Building);
+        assert_eq!(running.add(5, 10), ChainStep::Overshot);
+        assert_eq!(running.total, 0);
+    }
+
+    #[test]
+    fn test_combinations_make_the_target() {
+        assert!(can_make([5, 9, 12], 21));
+        assert!(!can_make([5, 9, 12], 20));
+        assert!(can_finish_with(9, &[5, 9, 12], 21));
+        assert!(!can_finish_with(5, &[5, 9, 12], 21));
+
+        let mut rng = StdRng::seed_from_u64(9);
+        for target in [2, 10, 24, 50] {
+            let combination = random_combination(target, &mut rng);
+            assert_eq!(combination.iter().sum::<i32>(), target);
+            assert!(combination.iter().all(|&part| part > 0));
+        }
+    }
+}
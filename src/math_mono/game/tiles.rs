@@ -0,0 +1,340 @@
+//! What each cell of the grid is: a number block, or an obstacle or special floor laid
+//! out by the level. Levels can also leave cells out to make boards that aren't
+//! rectangles.
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::math_mono::components::GridPosition;
+
+/// One cell of the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum Tile {
+    /// A number block to eat.
+    #[default]
+    Number,
+    /// Blocks the player and enemies.
+    Wall,
+    /// Empty floor with no number on it.
+    Floor,
+    /// Anything stepping onto ice slides on in the same direction until stopped.
+    Ice,
+    /// Stepping onto a teleporter moves you to the other teleporter with the same id.
+    Teleporter(u8),
+    /// Can only be entered moving the way the arrow points.
+    Arrow(Direction),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub const ALL: [Self; 4] = [Self::Up, Self::Down, Self::Left, Self::Right];
+
+    /// Rows and columns moved by one step this way. Rows count up the screen.
+    pub fn offset(self) -> (i32, i32) {
+        match self {
+            Self::Up => (1, 0),
+            Self::Down => (-1, 0),
+            Self::Left => (0, -1),
+            Self::Right => (0, 1),
+        }
+    }
+
+    pub fn step(self, pos: GridPosition) -> GridPosition {
+        let (row, col) = self.offset();
+        GridPosition {
+            row: pos.row + row,
+            col: pos.col + col,
+        }
+    }
+}
+
+impl Tile {
+    fn from_char(c: char) -> Option<Self> {
+        Some(match c {
+            '.' => Self::Number,
+            '#' => Self::Wall,
+            '_' => Self::Floor,
+            '~' => Self::Ice,
+            '^' => Self::Arrow(Direction::Up),
+            'v' => Self::Arrow(Direction::Down),
+            '<' => Self::Arrow(Direction::Left),
+            '>' => Self::Arrow(Direction::Right),
+            '1'..='9' => Self::Teleporter(c as u8 - b'0'),
+            _ => return None,
+        })
+    }
+
+    /// Whether something moving `direction` may step onto this tile.
+    pub fn can_enter(self, direction: Direction) -> bool {
+        match self {
+            Self::Wall => false,
+            Self::Arrow(arrow) => arrow == direction,
+            _ => true,
+        }
+    }
+
+    /// Color of the sprite drawn for this tile. Number blocks have their own colors.
+    pub fn color(self) -> Color {
+        match self {
+            Self::Number => Color::WHITE,
+            Self::Wall => Color::srgb(0.3, 0.3, 0.35),
+            Self::Floor => Color::srgb(0.12, 0.12, 0.15),
+            Self::Ice => Color::srgb(0.7, 0.9, 1.0),
+            Self::Teleporter(_) => Color::srgb(0.6, 0.3, 0.9),
+            Self::Arrow(_) => Color::srgb(0.95, 0.6, 0.2),
+        }
+    }
+
+    /// Text drawn on this tile, if any.
+    pub fn label(self) -> Option<String> {
+        match self {
+            Self::Teleporter(id) => Some(id.to_string()),
+            Self::Arrow(Direction::Up) => Some("↑".to_string()),
+            Self::Arrow(Direction::Down) => Some("↓".to_string()),
+            Self::Arrow(Direction::Left) => Some("←".to_string()),
+            Self::Arrow(Direction::Right) => Some("→".to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// The tiles of a grid, row by row from the bottom.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    rows: i32,
+    cols: i32,
+    /// `None` for cells missing from the board. Empty for a grid of number blocks only.
+    tiles: Vec<Option<Tile>>,
+}
+
+impl Layout {
+    /// A grid of number blocks only.
+    pub fn open(rows: i32, cols: i32) -> Self {
+        Self {
+            rows,
+            cols,
+            tiles: Vec::new(),
+        }
+    }
+
+    /// Read a layout drawn as text, top row first. The drawing sets the size of the grid:
+    ///
+    /// `.` number, `#` wall, `_` floor, `~` ice, `^ v < >` arrows, a digit for each end
+    /// of a teleporter pair, and a space for a cell missing from the board.
+    pub fn parse(drawing: &[&str]) -> Result<Self, String> {
+        let rows = drawing.len() as i32;
+        let cols = drawing.first().map_or(0, |line| line.chars().count()) as i32;
+        let mut tiles = vec![None; (rows * cols) as usize];
+        for (index, line) in drawing.iter().enumerate() {
+            let row = rows - 1 - index as i32;
+            if line.chars().count() != cols as usize {
+                return Err(format!("row {row} should be {cols} tiles wide: `{line}`"));
+            }
+            for (col, c) in line.chars().enumerate() {
+                if c == ' ' {
+                    continue;
+                }
+                let tile =
+                    Tile::from_char(c).ok_or_else(|| format!("unknown tile `{c}` in row {row}"))?;
+                tiles[(row * cols) as usize + col] = Some(tile);
+            }
+        }
+        let layout = Self { rows, cols, tiles };
+        if layout.cells().next().is_none() {
+            return Err("the board has no cells".to_string());
+        }
+        for tile in layout.tiles.iter().flatten() {
+            if let Tile::Teleporter(id) = *tile {
+                let ends = layout.tiles.iter().flatten().filter(|&other| other == tile);
+                let ends = ends.count();
+                if ends != 2 {
+                    return Err(format!("teleporter {id} has {ends} ends, not 2"));
+                }
+            }
+        }
+        Ok(layout)
+    }
+
+    pub fn rows(&self) -> i32 {
+        self.rows
+    }
+
+    pub fn cols(&self) -> i32 {
+        self.cols
+    }
+
+    /// The tile at `pos`, or `None` off the grid or where a cell is missing.
+    pub fn tile(&self, pos: GridPosition) -> Option<Tile> {
+        let on_grid = (0..self.rows).contains(&pos.row) && (0..self.cols).contains(&pos.col);
+        if !on_grid {
+            return None;
+        }
+        if self.tiles.is_empty() {
+            return Some(Tile::Number);
+        }
+        self.tiles[(pos.row * self.cols + pos.col) as usize]
+    }
+
+    /// Every cell on the board, row by row from the bottom.
+    pub fn cells(&self) -> impl Iterator<Item = GridPosition> + '_ {
+        (0..self.rows)
+            .flat_map(|row| (0..self.cols).map(move |col| GridPosition { row, col }))
+            .filter(|&pos| self.tile(pos).is_some())
+    }
+
+    /// The bottom-left and top-right corners of the smallest rectangle holding every cell.
+    pub fn extent(&self) -> (GridPosition, GridPosition) {
+        let mut min = GridPosition {
+            row: self.rows,
+            col: self.cols,
+        };
+        let mut max = GridPosition { row: -1, col: -1 };
+        for pos in self.cells() {
+            min.row = min.row.min(pos.row);
+            min.col = min.col.min(pos.col);
+            max.row = max.row.max(pos.row);
+            max.col = max.col.max(pos.col);
+        }
+        (min, max)
+    }
+
+    /// Where the player starts: the cell nearest the middle of the board that isn't a wall.
+    pub fn start(&self) -> GridPosition {
+        let (min, max) = self.extent();
+        let middle = GridPosition {
+            row: (min.row + max.row) / 2,
+            col: (min.col + max.col) / 2,
+        };
+        self.cells()
+            .filter(|&pos| self.tile(pos) != Some(Tile::Wall))
+            .min_by_key(|pos| (pos.row - middle.row).abs() + (pos.col - middle.col).abs())
+            .unwrap_or(middle)
+    }
+
+    fn other_teleporter(&self, from: GridPosition, id: u8) -> GridPosition {
+        self.cells()
+            .find(|&pos| pos != from && self.tile(pos) == Some(Tile::Teleporter(id)))
+            .unwrap_or(from)
+    }
+
+    /// Where something at `from` ends up after trying to step `direction`: walls and
+    /// arrows stop it, ice slides it on, and teleporters send it to their other end.
+    pub fn step(&self, from: GridPosition, direction: Direction) -> GridPosition {
+        let mut pos = from;
+        loop {
+            let next = direction.step(pos);
+            if !self
+                .tile(next)
+                .is_some_and(|tile| tile.can_enter(direction))
+            {
+                return pos;
+            }
+            pos = next;
+            match self.tile(pos) {
+                Some(Tile::Ice) => continue,
+                Some(Tile::Teleporter(id)) => return self.other_teleporter(pos, id),
+                _ => return pos,
+            }
+        }
+    }
+
+    /// The shortest way from `from` to `to`, as every cell stopped on after each move and
+    /// ending with `to`. `None` if `to` can't be reached.
+    pub fn path(&self, from: GridPosition, to: GridPosition) -> Option<Vec<GridPosition>> {
+        let index = |pos: GridPosition| (pos.row * self.cols + pos.col) as usize;
+        let mut came_from = vec![None; (self.rows * self.cols) as usize];
+        let mut queue = VecDeque::from([from]);
+        while let Some(pos) = queue.pop_front() {
+            if pos == to {
+                let mut path = vec![to];
+                let mut current = to;
+                while current != from {
+                    current = came_from[index(current)]?;
+                    path.push(current);
+                }
+                path.pop();
+                path.reverse();
+                return Some(path);
+            }
+            for direction in Direction::ALL {
+                let next = self.step(pos, direction);
+                if next != from && came_from[index(next)].is_none() {
+                    came_from[index(next)] = Some(pos);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DRAWING: &[&str] = &[
+        "1.#", //
+        "~~.", //
+        ">.1",
+    ];
+
+    fn at(row: i32, col: i32) -> GridPosition {
+        GridPosition { row, col }
+    }
+
+    #[test]
+    fn test_tiles_change_movement() {
+        let layout = Layout::parse(DRAWING).unwrap();
+        assert_eq!(layout.tile(at(2, 2)), Some(Tile::Wall));
+        // Walls stop you.
+        assert_eq!(layout.step(at(1, 2), Direction::Up), at(1, 2));
+        // Ice slides you until something stops you.
+        assert_eq!(layout.step(at(1, 2), Direction::Left), at(1, 0));
+        // Arrows only let you in the way they point.
+        assert_eq!(layout.step(at(0, 1), Direction::Left), at(0, 1));
+        // Teleporters send you to the other end.
+        assert_eq!(layout.step(at(1, 2), Direction::Down), at(2, 0));
+    }
+
+    #[test]
+    fn test_bad_layouts_are_rejected() {
+        assert!(Layout::parse(&["1..", "...", "..."]).is_err());
+        assert!(Layout::parse(&["...", ".."]).is_err());
+        assert!(Layout::parse(&["..x", "...", "..."]).is_err());
+        assert!(Layout::parse(&["   ", "   "]).is_err());
+        assert!(Layout::parse(&[]).is_err());
+    }
+
+    #[test]
+    fn test_masked_boards() {
+        let ring = Layout::parse(&[
+            "  ...", //
+            "  . .", //
+            "  ...",
+        ])
+        .unwrap();
+        assert_eq!(ring.tile(at(1, 3)), None);
+        assert_eq!(ring.cells().count(), 8);
+        assert_eq!(ring.extent(), (at(0, 2), at(2, 4)));
+        assert_ne!(ring.start(), at(1, 3));
+        // Missing cells stop you like the edge of the board.
+        assert_eq!(ring.step(at(1, 2), Direction::Right), at(1, 2));
+        assert_eq!(ring.step(at(1, 2), Direction::Left), at(1, 2));
+        // The way round goes past the hole in the middle.
+        assert_eq!(
+            ring.path(at(1, 2), at(1, 4)).unwrap(),
+            [at(2, 2), at(2, 3), at(2, 4), at(1, 4)]
+        );
+        assert_eq!(ring.path(at(1, 2), at(1, 3)), None);
+        assert_eq!(Layout::open(3, 3).path(at(0, 0), at(0, 0)).unwrap(), []);
+    }
+}
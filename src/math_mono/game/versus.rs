@@ -0,0 +1,137 @@
+//! Versus: two players race on one board for the most right answers, stealing each
+//! other's tiles, with wrong answers stunning instead of hurting.
+
+use std::cmp::Ordering;
+
+use bevy::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    math_mono::{
+        components::{NumberBlock, Player},
+        game::{grid::animate_colors_to_music, player::PLAYER_TINTS},
+        run::{Party, RunSummary},
+    },
+    screens::Screen,
+};
+
+/// Seconds a wrong answer stops a player from moving or eating.
+pub const STUN_SECS: f32 = 1.5;
+/// How much darker tiles are drawn once stolen, as they can't be taken again.
+const STOLEN_DARKENING: f32 = 0.3;
+
+pub struct VersusPlugin;
+
+impl Plugin for VersusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TileStolen>();
+        app.add_systems(
+            Update,
+            (
+                // Battles stun too, so stuns always count down.
+                tick_stuns.in_set(AppSystems::TickTimers),
+                (
+                    score_steals,
+                    tint_claimed_blocks.after(animate_colors_to_music),
+                )
+                    .in_set(AppSystems::Update)
+                    .run_if(resource_equals(Party::Versus)),
+            )
+                .in_set(PausableSystems)
+                .run_if(in_state(Screen::Gameplay)),
+        );
+    }
+}
+
+/// Sent when a player eats a right answer the other player already ate.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileStolen {
+    pub thief: Entity,
+    pub victim: Entity,
+    /// What the tile scored for the victim.
+    pub points: i32,
+}
+
+impl RunSummary {
+    /// Index of the player with the most right answers, or `None` on a draw.
+    pub fn winner(&self) -> Option<usize> {
+        let [first, second] = self.player_correct;
+        match first.cmp(&second) {
+            Ordering::Greater => Some(0),
+            Ordering::Less => Some(1),
+            Ordering::Equal => None,
+        }
+    }
+}
+
+fn tick_stuns(time: Res<Time>, mut player_query: Query<&mut Player>) {
+    for mut player in &mut player_query {
+        if player.stunned_secs > 0.0 {
+            player.stunned_secs = (player.stunned_secs - time.delta_secs()).max(0.0);
+        }
+    }
+}
+
+/// A stolen tile moves its right answer, and exactly the points it scored, from the
+/// victim to the thief.
+fn score_steals(mut stolen_events: EventReader<TileStolen>, mut player_query: Query<&mut Player>) {
+    for event in stolen_events.read() {
+        if let Ok(mut victim) = player_query.get_mut(event.victim) {
+            victim.correct_answers = victim.correct_answers.saturating_sub(1);
+            victim.score -= event.points;
+        }
+        if let Ok(mut thief) = player_query.get_mut(event.thief) {
+            thief.correct_answers += 1;
+            thief.score += event.points;
+        }
+    }
+}
+
+/// Eaten right answers show who holds them.
+fn tint_claimed_blocks(
+    player_query: Query<&Player>,
+    mut block_query: Query<(&NumberBlock, &mut Sprite)>,
+) {
+    for (block, mut sprite) in &mut block_query {
+        let Some(claim) = block.claim.filter(|_| block.is_eaten) else {
+            continue;
+        };
+        let Ok(owner) = player_query.get(claim.owner) else {
+            continue;
+        };
+        let tint = PLAYER_TINTS[owner.index];
+        sprite.color = if claim.stolen {
+            tint.darker(STOLEN_DARKENING)
+        } else {
+            tint
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math_mono::run::{GameMode, MAX_PLAYERS};
+
+    #[test]
+    fn test_winner_has_most_right_answers() {
+        let mut summary = RunSummary {
+            mode: GameMode::Classic,
+            party: Party::Versus,
+            level: Some(0),
+            score: 0,
+            player_scores: [0; MAX_PLAYERS],
+            player_correct: [3, 5],
+            no_damage_bonus: 0,
+            best_streak: 0,
+            rounds_cleared: 0,
+            completed: true,
+            stars: 1,
+        };
+        assert_eq!(summary.winner(), Some(1));
+        summary.player_correct = [6, 5];
+        assert_eq!(summary.winner(), Some(0));
+        summary.player_correct = [5, 5];
+        assert_eq!(summary.winner(), None);
+    }
+}
@@ -0,0 +1,331 @@
+//! Word problems: short stories with numbers to fill in, written as templates in
+//! `word_problems.txt`.
+
+use std::sync::LazyLock;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::math_mono::game::number::Number;
+
+/// Names the stories are told about.
+const NAMES: [&str; 8] = ["Sam", "Ava", "Leo", "Mia", "Noah", "Zoe", "Omar", "Priya"];
+/// The numbers a template can fill in, in order.
+const SLOTS: [char; 3] = ['a', 'b', 'c'];
+
+static TEMPLATES: LazyLock<Vec<Template>> = LazyLock::new(|| {
+    parse_templates(include_str!("word_problems.txt"))
+        .unwrap_or_else(|error| panic!("word_problems.txt: {error}"))
+});
+
+/// A word problem from one template, with its numbers and name filled in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub struct WordProblem {
+    /// Id of the template in `word_problems.txt`.
+    pub template: &'static str,
+    values: [i32; 3],
+    name: u8,
+}
+
+impl WordProblem {
+    /// The template `id`, not filled in yet; see [`Self::with_random_slots`].
+    pub const fn new(template: &'static str) -> Self {
+        Self {
+            template,
+            values: [0; 3],
+            name: 0,
+        }
+    }
+
+    fn template(&self) -> &'static Template {
+        TEMPLATES
+            .iter()
+            .find(|template| template.id == self.template)
+            .unwrap_or_else(|| panic!("no word problem template `{}`", self.template))
+    }
+
+    /// The same story with new numbers and a new name.
+    pub fn with_random_slots(self, rng: &mut impl Rng) -> Self {
+        let template = self.template();
+        let mut values = [0; 3];
+        for (value, range) in values.iter_mut().zip(&template.ranges) {
+            if let Some((min, max)) = *range {
+                *value = rng.random_range(min..=max);
+            }
+        }
+        Self {
+            template: self.template,
+            values,
+            name: rng.random_range(0..NAMES.len()) as u8,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        let mut text = self
+            .template()
+            .text
+            .replace("{name}", NAMES[self.name as usize]);
+        for (slot, value) in SLOTS.iter().zip(self.values) {
+            text = text.replace(&format!("{{{slot}}}"), &value.to_string());
+        }
+        text
+    }
+
+    pub fn answer(&self) -> i32 {
+        self.template().answer.evaluate(&self.values)
+    }
+
+    /// The sum that answers the story, like `3 × 4`.
+    pub fn working(&self) -> String {
+        self.template().answer.render(&self.values)
+    }
+
+    /// Answers a player might get by slipping up: one or ten out, or doing the wrong
+    /// operation somewhere.
+    pub fn near_misses(&self) -> Vec<Number> {
+        let answer = self.answer();
+        let mut misses = vec![answer - 1, answer + 1, answer - 10, answer + 10];
+        misses.extend(self.template().answer.swapped_operators(&self.values));
+        misses.retain(|&miss| miss != answer);
+        misses.sort_unstable();
+        misses.dedup();
+        misses.into_iter().map(Number::int).collect()
+    }
+}
+
+struct Template {
+    id: &'static str,
+    text: &'static str,
+    /// Smallest and largest value of each slot, both included. `None` for unused slots.
+    ranges: [Option<(i32, i32)>; 3],
+    answer: Expression,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    Slot(usize),
+    Literal(i32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+}
+
+impl Operator {
+    const ALL: [Self; 3] = [Self::Add, Self::Subtract, Self::Multiply];
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Subtract => "-",
+            Self::Multiply => "×",
+        }
+    }
+}
+
+/// Operands with an operator between each pair, worked out with `×` before `+` and `-`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Expression {
+    operands: Vec<Operand>,
+    operators: Vec<Operator>,
+}
+
+impl Expression {
+    fn parse(source: &str) -> Result<Self, String> {
+        let mut operands = Vec::new();
+        let mut operators = Vec::new();
+        for (index, token) in source.split_whitespace().enumerate() {
+            if index % 2 == 1 {
+                operators.push(match token {
+                    "+" => Operator::Add,
+                    "-" => Operator::Subtract,
+                    "*" => Operator::Multiply,
+                    _ => return Err(format!("expected +, - or *, found `{token}`")),
+                });
+            } else if let Some(slot) = SLOTS.iter().position(|&slot| token == slot.to_string()) {
+                operands.push(Operand::Slot(slot));
+            } else {
+                let literal = token
+                    .parse()
+                    .map_err(|_| format!("expected a, b, c or a number, found `{token}`"))?;
+                operands.push(Operand::Literal(literal));
+            }
+        }
+        if operands.len() != operators.len() + 1 {
+            return Err(format!("`{source}` is not a complete sum"));
+        }
+        Ok(Self {
+            operands,
+            operators,
+        })
+    }
+
+    fn value(operand: Operand, values: &[i32; 3]) -> i32 {
+        match operand {
+            Operand::Slot(slot) => values[slot],
+            Operand::Literal(literal) => literal,
+        }
+    }
+
+    fn evaluate(&self, values: &[i32; 3]) -> i32 {
+        Self::evaluate_with(&self.operands, &self.operators, values)
+    }
+
+    fn evaluate_with(operands: &[Operand], operators: &[Operator], values: &[i32; 3]) -> i32 {
+        let mut total = 0;
+        let mut term = Self::value(operands[0], values);
+        let mut sign = 1;
+        for (&operator, &operand) in operators.iter().zip(&operands[1..]) {
+            let value = Self::value(operand, values);
+            match operator {
+                Operator::Multiply => term *= value,
+                Operator::Add | Operator::Subtract => {
+                    total += sign * term;
+                    sign = if operator == Operator::Add { 1 } else { -1 };
+                    term = value;
+                }
+            }
+        }
+        total + sign * term
+    }
+
+    /// The answers from changing any one operator to a different one.
+    fn swapped_operators(&self, values: &[i32; 3]) -> Vec<i32> {
+        let mut answers = Vec::new();
+        for index in 0..self.operators.len() {
+            for swapped in Operator::ALL {
+                if swapped == self.operators[index] {
+                    continue;
+                }
+                let mut operators = self.operators.clone();
+                operators[index] = swapped;
+                answers.push(Self::evaluate_with(&self.operands, &operators, values));
+            }
+        }
+        answers
+    }
+
+    fn render(&self, values: &[i32; 3]) -> String {
+        let mut text = Self::value(self.operands[0], values).to_string();
+        for (&operator, &operand) in self.operators.iter().zip(&self.operands[1..]) {
+            text += &format!(" {} {}", operator.symbol(), Self::value(operand, values));
+        }
+        text
+    }
+}
+
+/// Read templates in the format described at the top of `word_problems.txt`.
+fn parse_templates(source: &'static str) -> Result<Vec<Template>, String> {
+    let mut templates = Vec::new();
+    let mut current: Option<(&'static str, Vec<(&'static str, &'static str)>)> = None;
+    for (number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |message: String| format!("line {}: {message}", number + 1);
+        if let Some(id) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            if let Some((id, fields)) = current.replace((id, Vec::new())) {
+                templates.push(build_template(id, fields)?);
+            }
+            continue;
+        }
+        let Some((_, fields)) = current.as_mut() else {
+            return Err(error("expected a `[template-id]` first".to_string()));
+        };
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(error(format!("expected `key = value`, found `{line}`")));
+        };
+        fields.push((key.trim(), value.trim()));
+    }
+    if let Some((id, fields)) = current {
+        templates.push(build_template(id, fields)?);
+    }
+    Ok(templates)
+}
+
+fn build_template(
+    id: &'static str,
+    fields: Vec<(&'static str, &'static str)>,
+) -> Result<Template, String> {
+    let error = |message: String| format!("[{id}]: {message}");
+    let mut text = None;
+    let mut answer = None;
+    let mut ranges = [None; 3];
+    for (key, value) in fields {
+        match key {
+            "text" => text = Some(value),
+            "answer" => answer = Some(Expression::parse(value).map_err(error)?),
+            _ => {
+                let Some(slot) = SLOTS.iter().position(|&slot| key == slot.to_string()) else {
+                    return Err(error(format!("unknown key `{key}`")));
+                };
+                let range = value
+                    .split_once("..")
+                    .and_then(|(min, max)| {
+                        Some((min.trim().parse().ok()?, max.trim().parse().ok()?))
+                    })
+                    .filter(|(min, max)| min <= max)
+                    .ok_or_else(|| {
+                        error(format!("expected a range like `2..9`, found `{value}`"))
+                    })?;
+                ranges[slot] = Some(range);
+            }
+        }
+    }
+    let text = text.ok_or_else(|| error("missing `text`".to_string()))?;
+    let answer = answer.ok_or_else(|| error("missing `answer`".to_string()))?;
+    for (index, (slot, range)) in SLOTS.iter().zip(&ranges).enumerate() {
+        let used = text.contains(&format!("{{{slot}}}"))
+            || answer.operands.contains(&Operand::Slot(index));
+        if used && range.is_none() {
+            return Err(error(format!("`{slot}` is used but has no range")));
+        }
+    }
+    Ok(Template {
+        id,
+        text,
+        ranges,
+        answer,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+
+    #[test]
+    fn test_templates_parse_and_answer_within_board() {
+        let mut rng = StdRng::seed_from_u64(5);
+        assert!(!TEMPLATES.is_empty());
+        for template in TEMPLATES.iter() {
+            for _ in 0..50 {
+                let problem = WordProblem::new(template.id).with_random_slots(&mut rng);
+                assert!(
+                    (1..=100).contains(&problem.answer()),
+                    "{} = {}",
+                    problem.text(),
+                    problem.answer()
+                );
+                assert!(!problem.text().contains('{'), "{}", problem.text());
+            }
+        }
+    }
+
+    #[test]
+    fn test_expressions_multiply_first() {
+        let expression = Expression::parse("a * b - c").unwrap();
+        assert_eq!(expression.evaluate(&[3, 8, 5]), 19);
+        assert_eq!(expression.render(&[3, 8, 5]), "3 × 8 - 5");
+        assert!(expression.swapped_operators(&[3, 8, 5]).contains(&6));
+        assert!(Expression::parse("a * ").is_err());
+    }
+}
@@ -0,0 +1,101 @@
+//! Local high-score tables, one per level and game mode.
+
+use serde::{Deserialize, Serialize};
+
+use crate::math_mono::{
+    level::{DAILY_LEVEL, LEVELS, LevelDefinition},
+    run::{GameMode, RunSummary},
+};
+
+/// How many entries each table keeps.
+pub const MAX_HIGH_SCORES: usize = 10;
+/// The longest name that can be entered.
+pub const MAX_NAME_LEN: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub name: String,
+    pub score: i32,
+}
+
+/// Identifies one high-score table.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HighScoreTable {
+    pub mode: GameMode,
+    pub level: LevelDefinition,
+}
+
+impl HighScoreTable {
+    /// Every table, in the order the leaderboard shows them.
+    pub fn all() -> Vec<Self> {
+        LEVELS
+            .iter()
+            .map(|&level| Self {
+                mode: GameMode::Classic,
+                level,
+            })
+            .chain([Self {
+                mode: GameMode::Daily,
+                level: DAILY_LEVEL,
+            }])
+            .collect()
+    }
+
+    pub fn for_run(summary: &RunSummary) -> Self {
+        Self {
+            mode: summary.mode,
+            level: summary.level.map_or(DAILY_LEVEL, |index| LEVELS[index]),
+        }
+    }
+
+    /// Key for this table in the save file.
+    pub fn key(&self) -> String {
+        format!("{}/{}", self.mode.key(), self.level.id)
+    }
+}
+
+/// Whether `score` would make it onto a table that currently holds `scores`.
+pub fn qualifies(scores: &[HighScore], score: i32) -> bool {
+    score > 0
+        && (scores.len() < MAX_HIGH_SCORES || scores.last().is_some_and(|last| score > last.score))
+}
+
+/// Insert `entry` after any equal scores, keeping at most [`MAX_HIGH_SCORES`].
+pub fn insert(scores: &mut Vec<HighScore>, entry: HighScore) {
+    let index = scores.partition_point(|existing| existing.score >= entry.score);
+    scores.insert(index, entry);
+    scores.truncate(MAX_HIGH_SCORES);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_keeps_table_sorted_and_bounded() {
+        let mut scores = Vec::new();
+        for score in (1..=MAX_HIGH_SCORES as i32).map(|n| n * 10) {
+            insert(
+                &mut scores,
+                HighScore {
+                    name: format!("P{score}"),
+                    score,
+                },
+            );
+        }
+        assert!(!qualifies(&scores, 10));
+        assert!(qualifies(&scores, 15));
+
+        insert(
+            &mut scores,
+            HighScore {
+                name: "New".to_string(),
+                score: 50,
+            },
+        );
+        assert_eq!(scores.len(), MAX_HIGH_SCORES);
+        assert_eq!(scores[0].score, 100);
+        assert_eq!(scores[6].name, "New");
+        assert_eq!(scores.last().unwrap().score, 20);
+    }
+}
@@ -1,9 +1,29 @@
 //! Spawn the main level.
 
 use bevy::prelude::*;
+use rand::{Rng, seq::IndexedRandom};
 
 use crate::{
-    asset_tracking::LoadResource, audio::music, math_mono::game::spawn_grid,
+    AppSystems, PausableSystems,
+    asset_tracking::LoadResource,
+    audio::music,
+    math_mono::{
+        difficulty::{Difficulty, DifficultyRange},
+        game::{
+            boss::BossDefinition,
+            math::Expression,
+            number::{Number, Unit},
+            player::AnswerEaten,
+            question::{CurrentQuestion, Question},
+            scoring::StarThresholds,
+            sequence::Sequence,
+            spawn_grid,
+            word_problem::WordProblem,
+        },
+        run::{GameMode, Party, RunRng, RunSummary, online},
+    },
+    save::SaveData,
+    screens::Screen,
 };
 
 pub struct LevelManagerPlugin;
@@ -12,9 +32,492 @@ impl Plugin for LevelManagerPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<LevelAssets>();
         app.load_resource::<LevelAssets>();
+
+        app.init_resource::<SelectedLevel>();
+        app.add_systems(
+            Update,
+            (
+                track_level_goal
+                    .in_set(AppSystems::Update)
+                    .in_set(PausableSystems)
+                    // Battles end when the server says so.
+                    .run_if(in_state(Screen::Gameplay).and(not(online))),
+                record_level_result.run_if(resource_exists_and_changed::<RunSummary>),
+            ),
+        );
+    }
+}
+
+/// A hand-made level: which questions it asks and how dangerous its enemies are.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LevelDefinition {
+    /// Stable identifier used to key save data.
+    pub id: &'static str,
+    pub name: &'static str,
+    /// Each board asks one of these, picked at random. Ordered from easiest to hardest,
+    /// so lower difficulties only ask the first few.
+    pub questions: &'static [Question],
+    /// Correct answers needed to complete the level. `None` plays until out of health.
+    pub goal: Option<u32>,
+    /// Enemy timings at medium difficulty. The difficulty director scales them.
+    pub enemy_spawn_interval_secs: f32,
+    pub enemy_step_interval_secs: f32,
+    /// How easy and how hard the difficulty director may make this level.
+    pub difficulty: DifficultyRange,
+    /// Score needed for two and three stars when the level is completed.
+    pub stars: StarThresholds,
+    /// The boss fought on boss levels, who asks `questions` in order.
+    pub boss: Option<BossDefinition>,
+    /// The grid drawn as text, top row first; see [`Layout::parse`]. Spaces leave cells
+    /// out, so boards can be any shape. Empty for a 7×7 grid of number blocks only.
+    pub layout: &'static [&'static str],
+}
+
+impl LevelDefinition {
+    /// The questions that may be asked at `difficulty`.
+    pub fn available_questions(&self, difficulty: &Difficulty) -> &'static [Question] {
+        &self.questions[..difficulty.question_pool_len(self.questions.len())]
+    }
+
+    pub fn random_question(&self, difficulty: &Difficulty, rng: &mut impl Rng) -> Question {
+        *self.available_questions(difficulty).choose(rng).unwrap()
+    }
+}
+
+const MIXED_QUESTIONS: &[Question] = &[
+    Question::Even,
+    Question::Odd,
+    Question::LessThan(Number::int(30)),
+    Question::GreaterThan(Number::int(60)),
+    Question::MultipleOf(3),
+    Question::MultipleOf(4),
+    Question::Sum(14, 27),
+    Question::MultipleOf(6),
+    Question::MultipleOf(7),
+    Question::Prime,
+    Question::Sum(38, 45),
+];
+
+/// The levels in the order they unlock.
+pub const LEVELS: &[LevelDefinition] = &[
+    LevelDefinition {
+        id: "evens-and-odds",
+        name: "Evens and Odds",
+        questions: &[Question::Even, Question::Odd],
+        goal: Some(10),
+        enemy_spawn_interval_secs: 10.0,
+        enemy_step_interval_secs: 1.0,
+        difficulty: DifficultyRange::new(0.0, 0.6),
+        stars: StarThresholds::new(150, 250),
+        boss: None,
+        layout: &[],
+    },
+    LevelDefinition {
+        id: "times-tables",
+        name: "Times Tables",
+        questions: &[
+            Question::MultipleOf(10),
+            Question::MultipleOf(2),
+            Question::MultipleOf(5),
+            Question::MultipleOf(3),
+            Question::Product(6, 7),
+            Question::Product(8, 7),
+        ],
+        goal: Some(12),
+        enemy_spawn_interval_secs: 9.0,
+        enemy_step_interval_secs: 0.9,
+        difficulty: DifficultyRange::new(0.1, 0.7),
+        stars: StarThresholds::new(180, 300),
+        boss: None,
+        layout: &[],
+    },
+    LevelDefinition {
+        id: "bigger-or-smaller",
+        name: "Bigger or Smaller",
+        questions: &[
+            Question::GreaterThan(Number::int(50)),
+            Question::LessThan(Number::int(40)),
+            Question::GreaterThan(Number::int(75)),
+            Question::LessThan(Number::int(25)),
+        ],
+        goal: Some(14),
+        enemy_spawn_interval_secs: 8.0,
+        enemy_step_interval_secs: 0.8,
+        difficulty: DifficultyRange::new(0.2, 0.8),
+        stars: StarThresholds::new(210, 350),
+        boss: None,
+        layout: &[],
+    },
+    LevelDefinition {
+        id: "addition",
+        name: "Adding and Taking Away",
+        questions: &[
+            Question::Sum(10, 10),
+            Question::Sum(12, 9),
+            Question::Difference(45, 13),
+            Question::Sum(25, 17),
+            Question::Sum(33, 48),
+            Question::Difference(72, 36),
+        ],
+        goal: Some(10),
+        enemy_spawn_interval_secs: 7.0,
+        enemy_step_interval_secs: 0.7,
+        difficulty: DifficultyRange::new(0.3, 0.9),
+        stars: StarThresholds::new(150, 250),
+        boss: None,
+        layout: &[],
+    },
+    LevelDefinition {
+        id: "primes",
+        name: "Prime Numbers",
+        questions: &[Question::Prime],
+        goal: Some(15),
+        enemy_spawn_interval_secs: 6.5,
+        enemy_step_interval_secs: 0.7,
+        difficulty: DifficultyRange::new(0.4, 1.0),
+        stars: StarThresholds::new(225, 375),
+        boss: None,
+        layout: &[
+            "   .   ", //
+            "  ...  ", //
+            " ..... ", //
+            ".......", //
+            " ..... ", //
+            "  ...  ", //
+            "   .   ",
+        ],
+    },
+    LevelDefinition {
+        id: "mixed",
+        name: "Mixed Bag",
+        questions: MIXED_QUESTIONS,
+        goal: Some(20),
+        enemy_spawn_interval_secs: 6.0,
+        enemy_step_interval_secs: 0.6,
+        difficulty: DifficultyRange::new(0.5, 1.0),
+        stars: StarThresholds::new(300, 500),
+        boss: None,
+        layout: &[
+            "1.....#", //
+            ".#.....", //
+            "...#...", //
+            "..._...", //
+            "...#...", //
+            ".....#.", //
+            "#.....1",
+        ],
+    },
+    LevelDefinition {
+        id: "boss-the-count",
+        name: "Boss: The Count",
+        // Asked in order, one for each of the boss's phases.
+        questions: &[Question::Even, Question::MultipleOf(3), Question::Prime],
+        goal: Some(12),
+        enemy_spawn_interval_secs: 12.0,
+        enemy_step_interval_secs: 1.0,
+        difficulty: DifficultyRange::new(0.4, 0.8),
+        stars: StarThresholds::new(180, 300),
+        boss: Some(BossDefinition {
+            name: "The Count",
+            row: 5,
+            col: 2,
+            rows: 2,
+            cols: 3,
+            health: 12,
+            hits_per_phase: 4,
+            attack_interval_secs: 4.0,
+        }),
+        layout: &[
+            "..___..", //
+            "..___..", //
+            ".......", //
+            ".......", //
+            ".......", //
+            ".......", //
+            ".......",
+        ],
+    },
+    LevelDefinition {
+        id: "tricky-numbers",
+        name: "Tricky Numbers",
+        questions: &[
+            Question::LessThan(Number::int(0)),
+            Question::EquivalentTo(Number::fraction(1, 2)),
+            Question::GreaterThan(Number::decimal(25, 1)),
+            Question::LessThan(Number::int(-3)),
+            Question::EquivalentTo(Number::fraction(2, 3)),
+            Question::LessThan(Number::decimal(15, 1)),
+        ],
+        goal: Some(15),
+        enemy_spawn_interval_secs: 7.0,
+        enemy_step_interval_secs: 0.7,
+        difficulty: DifficultyRange::new(0.3, 0.9),
+        stars: StarThresholds::new(225, 375),
+        boss: None,
+        layout: &[],
+    },
+    LevelDefinition {
+        id: "measuring",
+        name: "Money, Time and Length",
+        questions: &[
+            Question::GreaterThan(Number::dollars(100)),
+            Question::LessThan(Number::clock(6, 0)),
+            Question::EquivalentTo(Number::int(1).in_unit(Unit::Metres)),
+            Question::GreaterThan(Number::clock(2, 30)),
+            Question::LessThan(Number::dollars(125)),
+            Question::EquivalentTo(Number::decimal(15, 1).in_unit(Unit::Metres)),
+            Question::LessThan(Number::int(120).in_unit(Unit::Centimetres)),
+        ],
+        goal: Some(15),
+        enemy_spawn_interval_secs: 7.0,
+        enemy_step_interval_secs: 0.7,
+        difficulty: DifficultyRange::new(0.3, 0.9),
+        stars: StarThresholds::new(225, 375),
+        boss: None,
+        layout: &[],
+    },
+    LevelDefinition {
+        id: "word-problems",
+        name: "Word Problems",
+        questions: &[
+            Question::WordProblem(WordProblem::new("stickers-bought")),
+            Question::WordProblem(WordProblem::new("marbles-given-away")),
+            Question::WordProblem(WordProblem::new("apples-in-bags")),
+            Question::WordProblem(WordProblem::new("legs-on-dogs")),
+            Question::WordProblem(WordProblem::new("bus-passengers")),
+            Question::WordProblem(WordProblem::new("pizza-slices")),
+        ],
+        goal: Some(10),
+        // Stories take longer to read, so enemies come slower.
+        enemy_spawn_interval_secs: 9.0,
+        enemy_step_interval_secs: 0.9,
+        difficulty: DifficultyRange::new(0.3, 0.8),
+        stars: StarThresholds::new(150, 250),
+        boss: None,
+        layout: &[],
+    },
+    // Each question is a shape: it gets new numbers every time it is asked.
+    LevelDefinition {
+        id: "algebra",
+        name: "Algebra",
+        questions: &[
+            Question::Evaluate { x: 3, value: 11 },
+            Question::SolveFor {
+                lhs: Expression::linear(1, 5),
+                x: 7,
+            },
+            Question::SolveFor {
+                lhs: Expression::linear(3, 0),
+                x: 6,
+            },
+            Question::SolveFor {
+                lhs: Expression::linear(3, 4),
+                x: 5,
+            },
+            Question::SolveFor {
+                lhs: Expression::linear(4, -7),
+                x: 5,
+            },
+            Question::SolveFor {
+                lhs: Expression::bracketed(2, 3),
+                x: 4,
+            },
+        ],
+        goal: Some(12),
+        enemy_spawn_interval_secs: 9.0,
+        enemy_step_interval_secs: 0.9,
+        difficulty: DifficultyRange::new(0.4, 0.9),
+        stars: StarThresholds::new(180, 300),
+        boss: None,
+        layout: &[
+            ".......", //
+            ".......", //
+            "..   ..", //
+            "..   ..", //
+            "..   ..", //
+            ".......", //
+            ".......",
+        ],
+    },
+    LevelDefinition {
+        id: "in-order",
+        name: "In Order",
+        questions: &[
+            Question::Sequence(Sequence::Arithmetic {
+                start: 2,
+                step: 2,
+                length: 4,
+            }),
+            Question::Sequence(Sequence::Arithmetic {
+                start: 5,
+                step: 5,
+                length: 5,
+            }),
+            Question::Sequence(Sequence::Arithmetic {
+                start: 30,
+                step: -3,
+                length: 5,
+            }),
+            Question::Sequence(Sequence::Primes { from: 2, length: 5 }),
+            Question::Sequence(Sequence::Arithmetic {
+                start: 50,
+                step: -7,
+                length: 5,
+            }),
+            Question::Sequence(Sequence::Primes {
+                from: 20,
+                length: 4,
+            }),
+        ],
+        // Counted in numbers eaten, so a few sequences.
+        goal: Some(20),
+        enemy_spawn_interval_secs: 8.0,
+        enemy_step_interval_secs: 0.8,
+        difficulty: DifficultyRange::new(0.3, 0.9),
+        stars: StarThresholds::new(300, 500),
+        boss: None,
+        layout: &[
+            "   ...   ", //
+            "   ...   ", //
+            "   ...   ", //
+            ".........", //
+            "....#....", //
+            ".........", //
+            "   ...   ", //
+            "   ...   ", //
+            "   ...   ",
+        ],
+    },
+    LevelDefinition {
+        id: "make-the-total",
+        name: "Make the Total",
+        questions: &[
+            Question::MakeTotal(10),
+            Question::MakeTotal(15),
+            Question::MakeTotal(20),
+            Question::MakeTotal(24),
+            Question::MakeTotal(30),
+            Question::MakeTotal(50),
+        ],
+        // Counted in totals made.
+        goal: Some(8),
+        enemy_spawn_interval_secs: 8.0,
+        enemy_step_interval_secs: 0.8,
+        difficulty: DifficultyRange::new(0.3, 0.9),
+        stars: StarThresholds::new(300, 550),
+        boss: None,
+        layout: &[
+            ".......", //
+            ".~~.~~.", //
+            ".>...<.", //
+            "..._...", //
+            ".>...<.", //
+            ".~~.~~.", //
+            ".......",
+        ],
+    },
+    LevelDefinition {
+        id: "boss-the-divider",
+        name: "Boss: The Divider",
+        // Asked in order, one for each of the boss's phases.
+        questions: &[
+            Question::MultipleOf(7),
+            Question::GreaterThan(Number::int(75)),
+            Question::Sum(27, 15),
+            Question::Prime,
+        ],
+        goal: Some(16),
+        enemy_spawn_interval_secs: 10.0,
+        enemy_step_interval_secs: 0.9,
+        difficulty: DifficultyRange::new(0.5, 1.0),
+        stars: StarThresholds::new(240, 400),
+        boss: Some(BossDefinition {
+            name: "The Divider",
+            row: 5,
+            col: 2,
+            rows: 2,
+            cols: 3,
+            health: 16,
+            hits_per_phase: 4,
+            attack_interval_secs: 3.0,
+        }),
+        layout: &[
+            "..___..", //
+            "..___..", //
+            ".......", //
+            ".......", //
+            ".......", //
+            ".......", //
+            ".......",
+        ],
+    },
+];
+
+/// The daily challenge plays until the player runs out of health.
+pub const DAILY_LEVEL: LevelDefinition = LevelDefinition {
+    id: "daily",
+    name: "Daily Challenge",
+    questions: MIXED_QUESTIONS,
+    goal: None,
+    enemy_spawn_interval_secs: 8.0,
+    enemy_step_interval_secs: 0.8,
+    // Fixed, so everyone plays the same boards.
+    difficulty: DifficultyRange::new(0.5, 0.5),
+    // Plays until out of health, so never completed.
+    stars: StarThresholds::new(0, 0),
+    boss: None,
+    layout: &[],
+};
+
+/// Index into [`LEVELS`] of the level picked from the level select menu.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SelectedLevel(pub usize);
+
+/// The level being played. `index` is `None` for levels outside [`LEVELS`], like the daily challenge.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct ActiveLevel {
+    pub index: Option<usize>,
+    pub definition: LevelDefinition,
+}
+
+/// How far the player is towards the active level's goal.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LevelProgress {
+    pub correct_answers: u32,
+}
+
+impl LevelProgress {
+    pub fn is_complete(&self, level: &LevelDefinition) -> bool {
+        level.goal.is_some_and(|goal| self.correct_answers >= goal)
     }
 }
 
+fn track_level_goal(
+    mut eaten_events: EventReader<AnswerEaten>,
+    level: Res<ActiveLevel>,
+    mut progress: ResMut<LevelProgress>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    for event in eaten_events.read() {
+        if event.correct {
+            progress.correct_answers += 1;
+        }
+    }
+    if progress.is_complete(&level.definition) {
+        next_screen.set(Screen::LevelReview);
+    }
+}
+
+fn record_level_result(summary: Res<RunSummary>, mut save: ResMut<SaveData>) {
+    // Versus races and online battles are won against rivals, so they don't set records.
+    let competitive =
+        summary.party == Party::Versus || matches!(summary.mode, GameMode::Online { .. });
+    let Some(index) = summary.level.filter(|_| summary.completed && !competitive) else {
+        return;
+    };
+    save.record_level(index, summary.score, summary.stars);
+}
+
 #[derive(Resource, Asset, Clone, Reflect)]
 #[reflect(Resource)]
 pub struct LevelAssets {
@@ -31,30 +534,45 @@ impl FromWorld for LevelAssets {
     }
 }
 
-
 pub fn spawn_level(
-        mut commands: Commands,
-        level_assets: Res<LevelAssets>,
-        config: Res<crate::math_mono::game::grid::GridConfig>,
-        meshes: ResMut<Assets<Mesh>>,
-        materials: ResMut<Assets<ColorMaterial>>,
-        window_size: Query<&Window>,
-        asset_server: Res<AssetServer>,
+    mut commands: Commands,
+    level_assets: Res<LevelAssets>,
+    config: Res<crate::math_mono::game::grid::GridConfig>,
+    meshes: ResMut<Assets<Mesh>>,
+    materials: ResMut<Assets<ColorMaterial>>,
+    window_size: Query<&Window>,
+    asset_server: Res<AssetServer>,
+    question: Res<CurrentQuestion>,
+    difficulty: Res<Difficulty>,
+    rng: ResMut<RunRng>,
 ) {
-    let level_entity = commands.spawn((
-        Name::new("Level"),
-        Transform::default(),
-        Visibility::default(),
-        StateScoped(crate::screens::Screen::Gameplay),
-    )).id();
+    let level_entity = commands
+        .spawn((
+            Name::new("Level"),
+            Transform::default(),
+            Visibility::default(),
+            StateScoped(Screen::Gameplay),
+        ))
+        .id();
 
     // Spawn music
-    commands.spawn((
-        Name::new("Gameplay Music"),
-        music(level_assets.music.clone()),
-    )).insert(ChildOf(level_entity));
+    commands
+        .spawn((
+            Name::new("Gameplay Music"),
+            music(level_assets.music.clone()),
+        ))
+        .insert(ChildOf(level_entity));
 
     // Spawn grid
-    spawn_grid( commands, meshes, materials, window_size, config, asset_server);
-
+    spawn_grid(
+        commands,
+        meshes,
+        materials,
+        window_size,
+        config,
+        asset_server,
+        question,
+        difficulty,
+        rng,
+    );
 }
@@ -0,0 +1,204 @@
+//! Learning statistics: how well the player knows each kind of question.
+
+use std::collections::BTreeMap;
+
+use bevy::{prelude::*, time::Stopwatch};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppSystems, PausableSystems,
+    math_mono::game::{number::Number, player::AnswerEaten, question::CurrentQuestion},
+    save::SaveData,
+    screens::Screen,
+};
+
+/// Answers needed before a mastery score is fully trusted.
+const CONFIDENT_ANSWER_COUNT: u32 = 20;
+
+pub struct MasteryPlugin;
+
+impl Plugin for MasteryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(Screen::Gameplay), begin_run_stats);
+        app.add_systems(OnExit(Screen::Gameplay), save_run_stats);
+        app.add_systems(
+            Update,
+            (
+                tick_question_clock.in_set(AppSystems::TickTimers),
+                (
+                    record_answers,
+                    start_question_clock.run_if(resource_exists_and_changed::<CurrentQuestion>),
+                )
+                    .chain()
+                    .in_set(AppSystems::Update),
+            )
+                .in_set(PausableSystems)
+                .run_if(in_state(Screen::Gameplay)),
+        );
+    }
+}
+
+/// Everything recorded about one skill, see [`Question::skill`](crate::math_mono::game::question::Question::skill).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SkillStats {
+    /// How many times a question practicing this skill was asked.
+    pub attempts: u32,
+    pub correct: u32,
+    pub incorrect: u32,
+    /// Total seconds from a question being shown to its first eat.
+    pub total_response_secs: f32,
+    /// How many response times went into [`Self::total_response_secs`].
+    pub responses: u32,
+    /// How often each wrong number was eaten.
+    pub distractors: BTreeMap<Number, u32>,
+    /// From 0 to 1: accuracy, scaled down until there are enough answers to trust it.
+    pub mastery: f32,
+}
+
+impl SkillStats {
+    fn accuracy(&self) -> Option<f32> {
+        let answers = self.correct + self.incorrect;
+        (answers > 0).then(|| self.correct as f32 / answers as f32)
+    }
+
+    fn record_answer(&mut self, value: Number, correct: bool) {
+        if correct {
+            self.correct += 1;
+        } else {
+            self.incorrect += 1;
+            *self.distractors.entry(value).or_default() += 1;
+        }
+        self.update_mastery();
+    }
+
+    /// Add statistics gathered separately, e.g. during one run.
+    fn merge(&mut self, other: &SkillStats) {
+        self.attempts += other.attempts;
+        self.correct += other.correct;
+        self.incorrect += other.incorrect;
+        self.total_response_secs += other.total_response_secs;
+        self.responses += other.responses;
+        for (value, count) in &other.distractors {
+            *self.distractors.entry(*value).or_default() += count;
+        }
+        self.update_mastery();
+    }
+
+    fn update_mastery(&mut self) {
+        let answers = self.correct + self.incorrect;
+        let confidence = (answers as f32 / CONFIDENT_ANSWER_COUNT as f32).min(1.0);
+        self.mastery = self.accuracy().unwrap_or(0.0) * confidence;
+    }
+}
+
+/// Statistics from the current run, keyed like [`SaveData::mastery`]. They're saved
+/// once the run ends rather than rewriting the save file on every answer.
+#[derive(Resource, Debug, Default)]
+struct RunStats(BTreeMap<String, SkillStats>);
+
+fn begin_run_stats(mut commands: Commands) {
+    commands.insert_resource(RunStats::default());
+}
+
+fn save_run_stats(
+    mut commands: Commands,
+    run_stats: Option<Res<RunStats>>,
+    mut save: ResMut<SaveData>,
+) {
+    let Some(run_stats) = run_stats else {
+        return;
+    };
+    commands.remove_resource::<RunStats>();
+    if run_stats.0.is_empty() {
+        return;
+    }
+    for (skill, stats) in &run_stats.0 {
+        save.mastery.entry(skill.clone()).or_default().merge(stats);
+    }
+}
+
+/// Time since the current question was shown, excluding time spent paused.
+#[derive(Resource, Debug, Default)]
+struct QuestionClock {
+    stopwatch: Stopwatch,
+    answered: bool,
+}
+
+fn tick_question_clock(time: Res<Time>, clock: Option<ResMut<QuestionClock>>) {
+    if let Some(mut clock) = clock {
+        clock.stopwatch.tick(time.delta());
+    }
+}
+
+/// Runs after [`record_answers`] so answers to the previous question are timed against it.
+fn start_question_clock(
+    mut commands: Commands,
+    question: Res<CurrentQuestion>,
+    mut run_stats: ResMut<RunStats>,
+) {
+    commands.insert_resource(QuestionClock::default());
+    run_stats
+        .0
+        .entry(question.question.skill())
+        .or_default()
+        .attempts += 1;
+}
+
+fn record_answers(
+    mut eaten_events: EventReader<AnswerEaten>,
+    mut clock: Option<ResMut<QuestionClock>>,
+    mut run_stats: ResMut<RunStats>,
+) {
+    // Statistics belong to the profile being played, so a second player's answers are
+    // left out.
+    for event in eaten_events.read().filter(|event| event.player_index == 0) {
+        let stats = run_stats.0.entry(event.question.skill()).or_default();
+        stats.record_answer(event.value, event.correct);
+
+        if let Some(clock) = clock.as_mut().filter(|clock| !clock.answered) {
+            clock.answered = true;
+            stats.total_response_secs += clock.stopwatch.elapsed_secs();
+            stats.responses += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mastery_needs_enough_answers() {
+        let mut stats = SkillStats::default();
+        for _ in 0..5 {
+            stats.record_answer(Number::int(4), true);
+        }
+        stats.record_answer(Number::int(9), false);
+        stats.record_answer(Number::int(9), false);
+        stats.record_answer(Number::int(15), false);
+
+        assert_eq!(stats.accuracy(), Some(5.0 / 8.0));
+        assert!(stats.mastery < 0.5 * 5.0 / 8.0);
+        assert_eq!(stats.distractors[&Number::int(9)], 2);
+    }
+
+    #[test]
+    fn test_merging_a_run_matches_recording_it_directly() {
+        let answers = [(4, true), (9, false), (6, true), (9, false)];
+        let mut direct = SkillStats::default();
+        let mut run = SkillStats::default();
+        let mut saved = SkillStats::default();
+        for (value, correct) in answers {
+            direct.record_answer(Number::int(value), correct);
+            if value == 4 {
+                saved.record_answer(Number::int(value), correct);
+            } else {
+                run.record_answer(Number::int(value), correct);
+            }
+        }
+
+        saved.merge(&run);
+        assert_eq!(saved, direct);
+    }
+}
@@ -0,0 +1,101 @@
+//! The mistakes made during a run, kept for the review shown after a level.
+
+use bevy::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    math_mono::{
+        components::NumberBlock,
+        game::{
+            number::Number,
+            player::{AnswerEaten, eat_number_on_spacebar},
+            question::{CurrentQuestion, Question},
+        },
+    },
+    screens::Screen,
+};
+
+pub struct MistakesPlugin;
+
+impl Plugin for MistakesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MistakeLog>();
+        app.add_systems(OnEnter(Screen::Gameplay), clear_mistake_log);
+        app.add_systems(
+            Update,
+            log_wrong_answers
+                .after(eat_number_on_spacebar)
+                .in_set(AppSystems::RecordInput)
+                .in_set(PausableSystems)
+                .run_if(in_state(Screen::Gameplay)),
+        );
+        app.add_systems(OnExit(Screen::Gameplay), log_missed_answers);
+    }
+}
+
+/// Every round of the last run that needs reviewing, in the order they were played.
+///
+/// Kept after the run ends so the review screen can show it.
+#[derive(Resource, Debug, Default)]
+pub struct MistakeLog {
+    pub rounds: Vec<RoundMistakes>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundMistakes {
+    pub question: Question,
+    /// [`CurrentQuestion::round`] of this round.
+    pub round: u32,
+    /// Wrong numbers eaten, in order.
+    pub wrong: Vec<Number>,
+    /// Right numbers left on the board when the run ended.
+    pub missed: Vec<Number>,
+}
+
+impl MistakeLog {
+    fn round_mut(&mut self, question: Question, round: u32) -> &mut RoundMistakes {
+        if self.rounds.last().is_none_or(|last| last.round != round) {
+            self.rounds.push(RoundMistakes {
+                question,
+                round,
+                wrong: Vec::new(),
+                missed: Vec::new(),
+            });
+        }
+        self.rounds.last_mut().unwrap()
+    }
+}
+
+fn clear_mistake_log(mut log: ResMut<MistakeLog>) {
+    *log = MistakeLog::default();
+}
+
+fn log_wrong_answers(mut eaten_events: EventReader<AnswerEaten>, mut log: ResMut<MistakeLog>) {
+    for event in eaten_events.read().filter(|event| !event.correct) {
+        log.round_mut(event.question, event.round)
+            .wrong
+            .push(event.value);
+    }
+}
+
+fn log_missed_answers(
+    question: Option<Res<CurrentQuestion>>,
+    block_query: Query<&NumberBlock>,
+    mut log: ResMut<MistakeLog>,
+) {
+    // A make-a-total board has no single numbers to miss.
+    let Some(question) = question.filter(|question| question.question.target_total().is_none())
+    else {
+        return;
+    };
+    let mut missed: Vec<Number> = block_query
+        .iter()
+        .filter(|block| !block.is_eaten && question.question.is_correct(block.value))
+        .map(|block| block.value)
+        .collect();
+    if missed.is_empty() {
+        return;
+    }
+    missed.sort_unstable();
+    log.round_mut(question.question, question.round).missed = missed;
+}
@@ -3,12 +3,31 @@ use bevy::prelude::*;
 mod animation;
 pub mod common;
 pub mod components;
+pub mod daily;
+pub mod difficulty;
 pub mod game;
+pub mod high_scores;
 pub mod level;
+pub mod mastery;
+pub mod mistakes;
+pub mod review;
+pub mod run;
+pub mod session;
 pub struct MathMonoPlugin;
 
 impl Plugin for MathMonoPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((level::LevelManagerPlugin, animation::AnimationPlugin, game::GamePlugin));
+        app.add_plugins((
+            level::LevelManagerPlugin,
+            animation::AnimationPlugin,
+            game::GamePlugin,
+            run::RunPlugin,
+            daily::DailyPlugin,
+            mastery::MasteryPlugin,
+            mistakes::MistakesPlugin,
+            difficulty::DifficultyPlugin,
+            review::ReviewPlugin,
+            session::SessionPlugin,
+        ));
     }
 }
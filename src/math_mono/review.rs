@@ -0,0 +1,201 @@
+//! Spaced repetition: skills the player keeps missing are asked again sooner.
+//!
+//! Each skill lives in a Leitner box. A round answered without mistakes moves its skill
+//! up a box, a mistake sends it back to the first box. Skills in higher boxes wait
+//! longer before they are due again.
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use rand::{Rng, seq::IndexedRandom};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppSystems, PausableSystems,
+    math_mono::{
+        difficulty::Difficulty,
+        game::{
+            player::{AnswerEaten, eat_number_on_spacebar},
+            question::{CurrentQuestion, Question},
+        },
+        level::LevelDefinition,
+        run::{GameMode, Party},
+    },
+    save::SaveData,
+    screens::Screen,
+};
+
+/// The highest Leitner box.
+const MAX_BOX: u8 = 4;
+
+pub struct ReviewPlugin;
+
+impl Plugin for ReviewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            note_mistakes
+                .after(eat_number_on_spacebar)
+                .in_set(AppSystems::RecordInput)
+                .in_set(PausableSystems)
+                .run_if(in_state(Screen::Gameplay)),
+        );
+        app.add_systems(OnExit(Screen::Gameplay), review_unfinished_round);
+    }
+}
+
+/// Every skill's Leitner box, for one profile.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReviewSchedule {
+    /// How many rounds have been reviewed so far.
+    pub rounds: u64,
+    /// Keyed by [`Question::skill`].
+    pub cards: BTreeMap<String, ReviewCard>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewCard {
+    /// Leitner box, from 0 to [`MAX_BOX`].
+    pub leitner_box: u8,
+    /// [`ReviewSchedule::rounds`] when this skill was last reviewed.
+    pub last_round: u64,
+}
+
+impl ReviewCard {
+    /// Rounds to wait after a review before the skill is due again.
+    fn interval(&self) -> u64 {
+        1 << self.leitner_box
+    }
+}
+
+impl ReviewSchedule {
+    /// Record a finished round of `skill`, with or without mistakes.
+    pub fn record(&mut self, skill: String, remembered: bool) {
+        self.rounds += 1;
+        let card = self.cards.entry(skill).or_default();
+        card.leitner_box = if remembered {
+            (card.leitner_box + 1).min(MAX_BOX)
+        } else {
+            0
+        };
+        card.last_round = self.rounds;
+    }
+
+    fn is_due(&self, skill: &str) -> bool {
+        self.cards
+            .get(skill)
+            .is_none_or(|card| self.rounds - card.last_round >= card.interval())
+    }
+
+    fn leitner_box(&self, skill: &str) -> u8 {
+        self.cards.get(skill).map_or(0, |card| card.leitner_box)
+    }
+
+    /// Pick a question from `pool`, preferring due skills in the lowest box.
+    pub fn choose(&self, pool: &[Question], rng: &mut impl Rng) -> Question {
+        let due: Vec<Question> = pool
+            .iter()
+            .copied()
+            .filter(|question| self.is_due(&question.skill()))
+            .collect();
+        let Some(lowest_box) = due
+            .iter()
+            .map(|question| self.leitner_box(&question.skill()))
+            .min()
+        else {
+            return *pool.choose(rng).unwrap();
+        };
+        let candidates: Vec<Question> = due
+            .into_iter()
+            .filter(|question| self.leitner_box(&question.skill()) == lowest_box)
+            .collect();
+        *candidates.choose(rng).unwrap()
+    }
+}
+
+/// Pick the next question for a run.
+///
+/// The daily challenge ignores the schedule, so everyone gets the same questions.
+pub fn next_question(
+    mode: GameMode,
+    level: &LevelDefinition,
+    difficulty: &Difficulty,
+    save: &SaveData,
+    rng: &mut impl Rng,
+) -> Question {
+    // Boss levels start with their first question. The boss picks the rest.
+    if level.boss.is_some() {
+        return level.questions[0].with_random_slots(rng);
+    }
+    let question = match mode {
+        GameMode::Classic => save
+            .review
+            .choose(level.available_questions(difficulty), rng),
+        GameMode::Daily | GameMode::Online { .. } => level.random_question(difficulty, rng),
+    };
+    question.with_random_slots(rng)
+}
+
+/// How many wrong numbers the player has eaten since the current question was shown.
+#[derive(Resource, Debug, Default)]
+pub struct RoundReview {
+    pub mistakes: u32,
+}
+
+fn note_mistakes(mut eaten_events: EventReader<AnswerEaten>, mut round: ResMut<RoundReview>) {
+    let mistakes = eaten_events.read().filter(|event| !event.correct).count() as u32;
+    if mistakes > 0 {
+        round.mistakes += mistakes;
+    }
+}
+
+/// A run can end halfway through a round. Mistakes made in it still count.
+fn review_unfinished_round(
+    mode: Res<GameMode>,
+    party: Res<Party>,
+    round: Option<Res<RoundReview>>,
+    question: Option<Res<CurrentQuestion>>,
+    mut save: ResMut<SaveData>,
+) {
+    let (GameMode::Classic, Party::Solo, Some(round), Some(question)) =
+        (*mode, *party, round, question)
+    else {
+        return;
+    };
+    if round.mistakes > 0 {
+        save.review.record(question.question.skill(), false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+
+    #[test]
+    fn test_missed_skills_come_back_first() {
+        let pool = [Question::Even, Question::Odd, Question::Prime];
+        let mut schedule = ReviewSchedule::default();
+        schedule.record(Question::Even.skill(), true);
+        schedule.record(Question::Odd.skill(), false);
+        schedule.record(Question::Prime.skill(), true);
+
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..10 {
+            assert_eq!(schedule.choose(&pool, &mut rng), Question::Odd);
+        }
+    }
+
+    #[test]
+    fn test_remembered_skills_wait_longer() {
+        let mut schedule = ReviewSchedule::default();
+        schedule.record("even".to_string(), true);
+        schedule.record("even".to_string(), true);
+        assert!(!schedule.is_due("even"));
+
+        schedule.rounds += 4;
+        assert!(schedule.is_due("even"));
+    }
+}
@@ -0,0 +1,258 @@
+//! A single run of the game: how it was started, its seeded randomness and how it ended.
+
+use bevy::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    math_mono::{
+        components::Combo,
+        components::{Health, Player},
+        daily,
+        difficulty::{Difficulty, DifficultyDirector},
+        game::{grid::GridConfig, question::CurrentQuestion, scoring::no_damage_bonus},
+        level::{ActiveLevel, DAILY_LEVEL, LEVELS, LevelProgress, SelectedLevel},
+        review::{RoundReview, next_question},
+    },
+    save::SaveData,
+    screens::Screen,
+};
+
+pub struct RunPlugin;
+
+impl Plugin for RunPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<GameMode>();
+        app.init_resource::<GameMode>();
+        app.register_type::<Party>();
+        app.init_resource::<Party>();
+        app.add_systems(OnExit(Screen::Gameplay), finish_run);
+    }
+}
+
+/// Which menu entry started the current run.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+#[reflect(Resource)]
+pub enum GameMode {
+    /// Play the level picked in the level select menu.
+    #[default]
+    Classic,
+    /// Everyone gets the same board on the same day.
+    Daily,
+    /// A math battle hosted by a server, which picked the seed so every player gets
+    /// the same boards.
+    Online { seed: u64 },
+}
+
+impl GameMode {
+    /// Stable name used to key save data.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::Classic => "classic",
+            Self::Daily => "daily",
+            Self::Online { .. } => "online",
+        }
+    }
+}
+
+/// Run condition for systems the battle server does instead of the game.
+pub fn online(mode: Res<GameMode>) -> bool {
+    matches!(*mode, GameMode::Online { .. })
+}
+
+/// Most players that can share a board.
+pub const MAX_PLAYERS: usize = 2;
+
+/// Who is playing: one player, or two on the same board working together or racing.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+#[reflect(Resource)]
+pub enum Party {
+    #[default]
+    Solo,
+    /// Two players share the level goal, each with their own score and health.
+    Coop,
+    /// Two players race for the most right answers.
+    Versus,
+}
+
+impl Party {
+    /// In the order the level select menu steps through them.
+    pub const ALL: [Self; 3] = [Self::Solo, Self::Coop, Self::Versus];
+
+    pub fn player_count(&self) -> usize {
+        match self {
+            Self::Solo => 1,
+            Self::Coop | Self::Versus => 2,
+        }
+    }
+}
+
+/// Random number streams for the current run.
+///
+/// Each part of the game draws from its own stream, so e.g. how quickly the player
+/// clears boards doesn't change where enemies spawn.
+///
+/// The streams use a fixed algorithm rather than `StdRng`, whose algorithm may change
+/// between `rand` versions: daily boards and battles must roll the same for everyone.
+#[derive(Resource)]
+pub struct RunRng {
+    pub board: ChaCha8Rng,
+    pub questions: ChaCha8Rng,
+    pub enemies: ChaCha8Rng,
+    pub power_ups: ChaCha8Rng,
+}
+
+impl RunRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            board: ChaCha8Rng::seed_from_u64(seed),
+            questions: ChaCha8Rng::seed_from_u64(seed.wrapping_add(1)),
+            enemies: ChaCha8Rng::seed_from_u64(seed.wrapping_add(2)),
+            power_ups: ChaCha8Rng::seed_from_u64(seed.wrapping_add(3)),
+        }
+    }
+}
+
+/// The outcome of the last finished run.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct RunSummary {
+    pub mode: GameMode,
+    pub party: Party,
+    /// Index into [`LEVELS`] of the level played, if any.
+    pub level: Option<usize>,
+    /// Final score of all players together, including [`Self::no_damage_bonus`].
+    pub score: i32,
+    /// Each player's own score, without the bonus. Unused entries are 0.
+    pub player_scores: [i32; MAX_PLAYERS],
+    /// Each player's right answers, after steals. Unused entries are 0.
+    pub player_correct: [u32; MAX_PLAYERS],
+    /// Points added for completing the level without any player being hurt.
+    pub no_damage_bonus: i32,
+    /// Longest streak of any player.
+    pub best_streak: u32,
+    pub rounds_cleared: u32,
+    /// Whether the level's goal was reached.
+    pub completed: bool,
+    /// Stars earned, or 0 if the level wasn't completed.
+    pub stars: u8,
+}
+
+/// Seed the run, pick its level and first question. Must run before the level is spawned.
+pub fn start_run(
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    selected_level: Res<SelectedLevel>,
+    save: Res<SaveData>,
+) {
+    let (seed, level) = match *mode {
+        GameMode::Classic => (
+            rand::random(),
+            ActiveLevel {
+                index: Some(selected_level.0),
+                definition: LEVELS[selected_level.0],
+            },
+        ),
+        GameMode::Daily => (
+            daily::daily_seed(&daily::today_key()),
+            ActiveLevel {
+                index: None,
+                definition: DAILY_LEVEL,
+            },
+        ),
+        GameMode::Online { seed } => (
+            seed,
+            // Battles don't count towards the level's record.
+            ActiveLevel {
+                index: None,
+                definition: LEVELS[selected_level.0],
+            },
+        ),
+    };
+    let mut rng = RunRng::from_seed(seed);
+    let difficulty = Difficulty::for_level(&level.definition);
+
+    commands.insert_resource(CurrentQuestion {
+        question: next_question(
+            *mode,
+            &level.definition,
+            &difficulty,
+            &save,
+            &mut rng.questions,
+        ),
+        round: 0,
+    });
+    commands.insert_resource(GridConfig::for_level(&level.definition));
+    commands.insert_resource(difficulty);
+    commands.insert_resource(DifficultyDirector::default());
+    commands.insert_resource(RoundReview::default());
+    commands.insert_resource(rng);
+    commands.insert_resource(level);
+    commands.insert_resource(LevelProgress::default());
+}
+
+fn finish_run(
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    party: Res<Party>,
+    question: Option<Res<CurrentQuestion>>,
+    level: Option<Res<ActiveLevel>>,
+    progress: Option<Res<LevelProgress>>,
+    player_query: Query<(&Player, &Health, &Combo)>,
+) {
+    // The next run starts from a fresh seed, level and question.
+    commands.remove_resource::<RunRng>();
+    commands.remove_resource::<CurrentQuestion>();
+    commands.remove_resource::<ActiveLevel>();
+    commands.remove_resource::<LevelProgress>();
+    commands.remove_resource::<Difficulty>();
+    commands.remove_resource::<DifficultyDirector>();
+    commands.remove_resource::<RoundReview>();
+
+    let (false, Some(level), Some(progress)) = (player_query.is_empty(), level, progress) else {
+        return;
+    };
+    let completed = progress.is_complete(&level.definition);
+    let bonus = if completed {
+        // Only if nobody was hurt.
+        player_query
+            .iter()
+            .map(|(_, health, _)| no_damage_bonus(health))
+            .min()
+            .unwrap_or_default()
+    } else {
+        0
+    };
+    let mut player_scores = [0; MAX_PLAYERS];
+    let mut player_correct = [0; MAX_PLAYERS];
+    for (player, _, _) in &player_query {
+        player_scores[player.index] = player.score;
+        player_correct[player.index] = player.correct_answers;
+    }
+    let score = player_scores.iter().sum::<i32>() + bonus;
+    let best_streak = player_query
+        .iter()
+        .map(|(_, _, combo)| combo.best_streak)
+        .max()
+        .unwrap_or_default();
+    commands.insert_resource(RunSummary {
+        mode: *mode,
+        party: *party,
+        level: level.index,
+        score,
+        player_scores,
+        player_correct,
+        no_damage_bonus: bonus,
+        best_streak,
+        rounds_cleared: question.map_or(0, |question| question.round),
+        completed,
+        stars: if completed {
+            level
+                .definition
+                .stars
+                .for_players(party.player_count())
+                .stars(score)
+        } else {
+            0
+        },
+    });
+}
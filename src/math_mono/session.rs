@@ -0,0 +1,151 @@
+//! A log of every run, kept for teacher reports.
+
+use bevy::{prelude::*, time::Stopwatch};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppSystems, PausableSystems,
+    math_mono::{
+        components::Player,
+        game::{hint::HintUsed, number::Number, player::AnswerEaten, question::CurrentQuestion},
+        level::ActiveLevel,
+        run::{GameMode, start_run},
+    },
+    save::{Profiles, record_session},
+    screens::Screen,
+};
+
+pub struct SessionPlugin;
+
+impl Plugin for SessionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(Screen::Gameplay), begin_session.after(start_run));
+        app.add_systems(
+            Update,
+            (
+                tick_session_clocks.in_set(AppSystems::TickTimers),
+                (
+                    log_answers,
+                    log_hints,
+                    restart_question_clock.run_if(resource_exists_and_changed::<CurrentQuestion>),
+                )
+                    .chain()
+                    .in_set(AppSystems::Update),
+            )
+                .in_set(PausableSystems)
+                .run_if(in_state(Screen::Gameplay)),
+        );
+        app.add_systems(OnExit(Screen::Gameplay), end_session);
+    }
+}
+
+/// One run, as it appears in teacher reports.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionLog {
+    /// Local start time, formatted as `YYYY-MM-DD HH:MM`.
+    pub date: String,
+    /// [`GameMode::key`] of the run.
+    pub mode: String,
+    /// [`LevelDefinition::id`](crate::math_mono::level::LevelDefinition::id) of the level played.
+    pub level: String,
+    pub score: i32,
+    /// Seconds played, excluding time spent paused.
+    pub duration_secs: f32,
+    /// How many hints were used.
+    pub hints_used: u32,
+    /// Empty for online battles, where the server checks answers instead of the game.
+    pub answers: Vec<AnswerLog>,
+}
+
+/// One number eaten during a run. The numbers of a made total are logged together once
+/// the total is made.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnswerLog {
+    /// Who ate it: 0 for the profile's player, 1 for a second player in co-op or versus.
+    pub player: usize,
+    /// Which question of the run this answers, counting from 0.
+    pub round: u32,
+    /// The question as shown to the player.
+    pub question: String,
+    pub value: Number,
+    pub correct: bool,
+    /// Seconds from the question being shown to this number being eaten.
+    pub seconds: f32,
+}
+
+#[derive(Resource, Debug)]
+struct SessionRecorder {
+    log: SessionLog,
+    session_clock: Stopwatch,
+    question_clock: Stopwatch,
+}
+
+fn begin_session(mut commands: Commands, mode: Res<GameMode>, level: Res<ActiveLevel>) {
+    commands.insert_resource(SessionRecorder {
+        log: SessionLog {
+            date: chrono::Local::now().format("%Y-%m-%d %H:%M").to_string(),
+            mode: mode.key().to_string(),
+            level: level.definition.id.to_string(),
+            ..default()
+        },
+        session_clock: Stopwatch::new(),
+        question_clock: Stopwatch::new(),
+    });
+}
+
+fn tick_session_clocks(time: Res<Time>, recorder: Option<ResMut<SessionRecorder>>) {
+    if let Some(mut recorder) = recorder {
+        recorder.session_clock.tick(time.delta());
+        recorder.question_clock.tick(time.delta());
+    }
+}
+
+fn restart_question_clock(mut recorder: ResMut<SessionRecorder>) {
+    recorder.question_clock.reset();
+}
+
+/// Runs before [`restart_question_clock`] so the last answer of a round is timed against it.
+fn log_answers(mut eaten_events: EventReader<AnswerEaten>, mut recorder: ResMut<SessionRecorder>) {
+    for event in eaten_events.read() {
+        let seconds = recorder.question_clock.elapsed_secs();
+        let values = if event.chain.is_empty() {
+            std::slice::from_ref(&event.value)
+        } else {
+            &event.chain
+        };
+        for &value in values {
+            recorder.log.answers.push(AnswerLog {
+                player: event.player_index,
+                round: event.round,
+                question: event.question.text(),
+                value,
+                correct: event.correct,
+                seconds,
+            });
+        }
+    }
+}
+
+fn log_hints(mut hint_events: EventReader<HintUsed>, mut recorder: ResMut<SessionRecorder>) {
+    recorder.log.hints_used += hint_events.read().count() as u32;
+}
+
+fn end_session(
+    mut commands: Commands,
+    recorder: Option<Res<SessionRecorder>>,
+    player_query: Query<&Player>,
+    profiles: Res<Profiles>,
+) {
+    let Some(recorder) = recorder else {
+        return;
+    };
+    let mut log = recorder.log.clone();
+    log.score = player_query.iter().map(|player| player.score).sum();
+    log.duration_secs = recorder.session_clock.elapsed_secs();
+    commands.remove_resource::<SessionRecorder>();
+
+    // Written once here rather than with the save file, which changes all through a run.
+    record_session(profiles.current(), log);
+}
@@ -44,6 +44,7 @@ fn assets() -> impl Bundle {
     grid(vec![
         ["Ducky sprite", "CC0 by Caz Creates Games"],
         ["Button SFX", "CC0 by Jaszunio15"],
+        ["Power-up SFX", "Synthesized for Math Mono"],
         ["Music", "CC BY 3.0 by Kevin MacLeod"],
         [
             "Bevy logo",
@@ -0,0 +1,117 @@
+//! The daily challenge menu: start today's attempt and look back at past results.
+
+use bevy::{
+    ecs::spawn::SpawnIter, input::common_conditions::input_just_pressed, prelude::*, ui::Val::*,
+};
+
+use crate::{
+    math_mono::{
+        daily::{DailyRecords, today_key},
+        run::{GameMode, Party},
+    },
+    menus::Menu,
+    save::SaveData,
+    screens::Screen,
+    theme::prelude::*,
+};
+
+/// How many past days to list.
+const HISTORY_LENGTH: usize = 7;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Menu::Daily), spawn_daily_menu);
+    app.add_systems(
+        Update,
+        go_back.run_if(in_state(Menu::Daily).and(input_just_pressed(KeyCode::Escape))),
+    );
+}
+
+fn spawn_daily_menu(mut commands: Commands, save: Res<SaveData>) {
+    let records = &save.daily;
+    let today = today_key();
+    let today_result = records.get(&today);
+    let status = match today_result {
+        Some(result) => format!("Today's score: {}", result.score),
+        None => "You haven't played today's challenge yet".to_string(),
+    };
+    let best = match records.best() {
+        Some(result) => format!("Best: {} on {}", result.score, result.date),
+        None => "No results yet".to_string(),
+    };
+
+    let root = commands
+        .spawn((
+            widget::ui_root("Daily Menu"),
+            GlobalZIndex(2),
+            StateScoped(Menu::Daily),
+            children![
+                widget::header(format!("Daily Challenge {today}")),
+                widget::label(status),
+                widget::label(best),
+                history_grid(records),
+            ],
+        ))
+        .id();
+
+    let played_today = today_result.is_some();
+    commands.entity(root).with_children(|parent| {
+        if !played_today {
+            parent.spawn(widget::button("Play", start_daily_challenge));
+        }
+        parent.spawn(widget::button("Back", go_back_on_click));
+    });
+}
+
+fn history_grid(records: &DailyRecords) -> impl Bundle {
+    let rows: Vec<[String; 2]> = records
+        .recent(HISTORY_LENGTH)
+        .into_iter()
+        .map(|result| [result.date.clone(), result.score.to_string()])
+        .collect();
+
+    (
+        Name::new("History Grid"),
+        Node {
+            display: Display::Grid,
+            row_gap: Px(10.0),
+            column_gap: Px(30.0),
+            grid_template_columns: RepeatedGridTrack::px(2, 200.0),
+            ..default()
+        },
+        Children::spawn(SpawnIter(rows.into_iter().flatten().enumerate().map(
+            |(i, text)| {
+                (
+                    widget::label(text),
+                    Node {
+                        justify_self: if i.is_multiple_of(2) {
+                            JustifySelf::End
+                        } else {
+                            JustifySelf::Start
+                        },
+                        ..default()
+                    },
+                )
+            },
+        ))),
+    )
+}
+
+fn start_daily_challenge(
+    _: Trigger<Pointer<Click>>,
+    mut game_mode: ResMut<GameMode>,
+    mut party: ResMut<Party>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    *game_mode = GameMode::Daily;
+    // Everyone plays the daily challenge alone.
+    *party = Party::Solo;
+    next_screen.set(Screen::Gameplay);
+}
+
+fn go_back_on_click(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Main);
+}
+
+fn go_back(mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Main);
+}
@@ -0,0 +1,139 @@
+//! The leaderboard menu: browse the high-score table of every level and mode.
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*, ui::Val::*};
+
+use crate::{
+    math_mono::{high_scores::HighScoreTable, run::GameMode},
+    menus::Menu,
+    save::SaveData,
+    theme::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<LeaderboardPage>();
+    app.add_systems(OnEnter(Menu::Leaderboard), spawn_leaderboard_menu);
+    app.add_systems(
+        Update,
+        (
+            go_back.run_if(input_just_pressed(KeyCode::Escape)),
+            previous_page.run_if(input_just_pressed(KeyCode::ArrowLeft)),
+            next_page.run_if(input_just_pressed(KeyCode::ArrowRight)),
+            update_leaderboard_table.run_if(resource_changed::<LeaderboardPage>),
+        )
+            .chain()
+            .run_if(in_state(Menu::Leaderboard)),
+    );
+}
+
+/// Index into [`HighScoreTable::all`] of the table being shown.
+#[derive(Resource, Debug, Default)]
+struct LeaderboardPage(usize);
+
+impl LeaderboardPage {
+    fn step(&mut self, forwards: bool) {
+        let count = HighScoreTable::all().len();
+        self.0 = if forwards {
+            (self.0 + 1) % count
+        } else {
+            (self.0 + count - 1) % count
+        };
+    }
+}
+
+#[derive(Component)]
+struct LeaderboardTitle;
+
+#[derive(Component)]
+struct LeaderboardTable;
+
+fn spawn_leaderboard_menu(mut commands: Commands, mut page: ResMut<LeaderboardPage>) {
+    commands.spawn((
+        widget::ui_root("Leaderboard Menu"),
+        GlobalZIndex(2),
+        StateScoped(Menu::Leaderboard),
+        children![
+            widget::header("High Scores"),
+            (
+                Name::new("Table Picker"),
+                Node {
+                    align_items: AlignItems::Center,
+                    column_gap: Px(20.0),
+                    ..default()
+                },
+                children![
+                    widget::button_small("<", previous_page_on_click),
+                    (widget::label(""), LeaderboardTitle),
+                    widget::button_small(">", next_page_on_click),
+                ],
+            ),
+            (
+                Name::new("Leaderboard Table"),
+                LeaderboardTable,
+                Node {
+                    display: Display::Grid,
+                    row_gap: Px(6.0),
+                    column_gap: Px(30.0),
+                    grid_template_columns: RepeatedGridTrack::px(3, 200.0),
+                    ..default()
+                },
+            ),
+            widget::button("Back", go_back_on_click),
+        ],
+    ));
+    // Fill in the table for whichever page was shown last.
+    page.set_changed();
+}
+
+fn update_leaderboard_table(
+    mut commands: Commands,
+    page: Res<LeaderboardPage>,
+    save: Res<SaveData>,
+    mut title: Single<&mut Text, With<LeaderboardTitle>>,
+    table_entity: Single<Entity, With<LeaderboardTable>>,
+) {
+    let tables = HighScoreTable::all();
+    let table = &tables[page.0];
+    title.0 = match table.mode {
+        GameMode::Classic | GameMode::Online { .. } => table.level.name.to_string(),
+        GameMode::Daily => format!("{} (all days)", table.level.name),
+    };
+
+    let scores = save.high_scores(table);
+    let mut table_commands = commands.entity(*table_entity);
+    table_commands.despawn_related::<Children>();
+    if scores.is_empty() {
+        table_commands.with_child(widget::label("No scores yet"));
+        return;
+    }
+    table_commands.with_children(|parent| {
+        for (rank, entry) in scores.iter().enumerate() {
+            parent.spawn(widget::label(format!("{}.", rank + 1)));
+            parent.spawn(widget::label(entry.name.clone()));
+            parent.spawn(widget::label(entry.score.to_string()));
+        }
+    });
+}
+
+fn previous_page_on_click(_: Trigger<Pointer<Click>>, mut page: ResMut<LeaderboardPage>) {
+    page.step(false);
+}
+
+fn next_page_on_click(_: Trigger<Pointer<Click>>, mut page: ResMut<LeaderboardPage>) {
+    page.step(true);
+}
+
+fn previous_page(mut page: ResMut<LeaderboardPage>) {
+    page.step(false);
+}
+
+fn next_page(mut page: ResMut<LeaderboardPage>) {
+    page.step(true);
+}
+
+fn go_back_on_click(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Main);
+}
+
+fn go_back(mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Main);
+}
@@ -0,0 +1,156 @@
+//! The level select menu: pick any unlocked level and see its best result.
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*, ui::Val::*};
+
+use crate::{
+    math_mono::{
+        level::{LEVELS, SelectedLevel},
+        run::{GameMode, Party},
+    },
+    menus::Menu,
+    save::SaveData,
+    screens::Screen,
+    theme::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Menu::Levels), spawn_levels_menu);
+    app.add_systems(
+        Update,
+        go_back.run_if(in_state(Menu::Levels).and(input_just_pressed(KeyCode::Escape))),
+    );
+
+    app.register_type::<PartyLabel>();
+    app.add_systems(Update, update_party_label.run_if(in_state(Menu::Levels)));
+}
+
+fn spawn_levels_menu(mut commands: Commands, save: Res<SaveData>) {
+    let root = commands
+        .spawn((
+            widget::ui_root("Levels Menu"),
+            GlobalZIndex(2),
+            StateScoped(Menu::Levels),
+            children![widget::header("Levels"), party_widget()],
+        ))
+        .id();
+
+    commands.entity(root).with_children(|parent| {
+        parent
+            .spawn((
+                Name::new("Levels Grid"),
+                Node {
+                    display: Display::Grid,
+                    row_gap: Px(10.0),
+                    column_gap: Px(30.0),
+                    // Two levels per row, each followed by its best result.
+                    grid_template_columns: RepeatedGridTrack::auto(4),
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+            ))
+            .with_children(|grid| {
+                for (index, level) in LEVELS.iter().enumerate() {
+                    let mut entry = grid.spawn((
+                        Name::new("Level Entry"),
+                        Node {
+                            justify_self: JustifySelf::End,
+                            ..default()
+                        },
+                    ));
+                    if save.is_unlocked(index) {
+                        entry.with_child(widget::button(level.name, start_level(index)));
+                    } else {
+                        entry.with_child(widget::label(format!("{} (Locked)", level.name)));
+                    }
+                    grid.spawn((
+                        Name::new("Level Best"),
+                        Node {
+                            justify_self: JustifySelf::Start,
+                            ..default()
+                        },
+                        children![widget::label(best_result(&save, index))],
+                    ));
+                }
+            });
+        parent.spawn(widget::button("Back", go_back_on_click));
+    });
+}
+
+/// Pick between playing alone and two players sharing the board, together or racing.
+fn party_widget() -> impl Bundle {
+    (
+        Name::new("Party Widget"),
+        Node::default(),
+        children![
+            widget::button_small("-", previous_party),
+            (
+                Name::new("Current Party"),
+                Node {
+                    padding: UiRect::horizontal(Px(10.0)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![(widget::label(""), PartyLabel)],
+            ),
+            widget::button_small("+", next_party),
+        ],
+    )
+}
+
+fn previous_party(_: Trigger<Pointer<Click>>, mut party: ResMut<Party>) {
+    let index = Party::ALL
+        .iter()
+        .position(|p| p == &*party)
+        .unwrap_or_default();
+    *party = Party::ALL[index.saturating_sub(1)];
+}
+
+fn next_party(_: Trigger<Pointer<Click>>, mut party: ResMut<Party>) {
+    let index = Party::ALL
+        .iter()
+        .position(|p| p == &*party)
+        .unwrap_or_default();
+    *party = Party::ALL[(index + 1).min(Party::ALL.len() - 1)];
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct PartyLabel;
+
+fn update_party_label(party: Res<Party>, mut label: Single<&mut Text, With<PartyLabel>>) {
+    label.0 = match *party {
+        Party::Solo => "1 player".to_string(),
+        Party::Coop => "2 players (co-op)".to_string(),
+        Party::Versus => "2 players (versus)".to_string(),
+    };
+}
+
+fn best_result(save: &SaveData, index: usize) -> String {
+    match save.level_record(index) {
+        Some(record) => format!(
+            "Best: {} {}",
+            record.best_score,
+            "*".repeat(record.stars.into())
+        ),
+        None => String::new(),
+    }
+}
+
+fn start_level(
+    index: usize,
+) -> impl Fn(Trigger<Pointer<Click>>, ResMut<SelectedLevel>, ResMut<GameMode>, ResMut<NextState<Screen>>)
+{
+    move |_, mut selected_level, mut game_mode, mut next_screen| {
+        selected_level.0 = index;
+        *game_mode = GameMode::Classic;
+        next_screen.set(Screen::Gameplay);
+    }
+}
+
+fn go_back_on_click(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Main);
+}
+
+fn go_back(mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Main);
+}
@@ -0,0 +1,254 @@
+//! The Math Battle menu: join a battle server by address, pick a level and start a
+//! battle with everyone in the room.
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*, ui::Val::*};
+
+use crate::{
+    math_mono::level::LEVELS,
+    menus::Menu,
+    net::{
+        ClientMessage, DEFAULT_ADDRESS, battle_levels,
+        client::{Lobby, NetClient},
+    },
+    save::Profiles,
+    theme::{
+        prelude::*,
+        text_entry::{TextEntry, TextEntrySubmitted},
+    },
+};
+
+/// Longest server address that can be typed.
+const MAX_ADDRESS_LEN: usize = 40;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Menu::Lobby), spawn_lobby_menu);
+    // Back in the menus after a battle, or still in a room: return to the room.
+    app.add_systems(
+        OnEnter(Menu::Main),
+        return_to_lobby.run_if(resource_exists::<NetClient>),
+    );
+    app.add_systems(
+        Update,
+        go_back.run_if(in_state(Menu::Lobby).and(input_just_pressed(KeyCode::Escape))),
+    );
+
+    app.init_resource::<BattleLevel>();
+    app.register_type::<(LobbyStatus, BattleLevelLabel)>();
+    app.add_systems(
+        Update,
+        (update_lobby_status, update_battle_level_label).run_if(in_state(Menu::Lobby)),
+    );
+}
+
+/// Index into [`LEVELS`] of the level battles are started on.
+#[derive(Resource, Debug)]
+struct BattleLevel(usize);
+
+impl Default for BattleLevel {
+    fn default() -> Self {
+        Self(battle_levels().next().map_or(0, |(index, _)| index))
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct LobbyStatus;
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct BattleLevelLabel;
+
+fn spawn_lobby_menu(mut commands: Commands) {
+    commands.spawn((
+        widget::ui_root("Lobby Menu"),
+        GlobalZIndex(2),
+        StateScoped(Menu::Lobby),
+        children![
+            widget::header("Math Battle"),
+            (widget::label(""), LobbyStatus),
+            widget::label("Server address:"),
+            widget::text_entry_with(
+                TextEntry {
+                    value: DEFAULT_ADDRESS.to_string(),
+                    max_len: MAX_ADDRESS_LEN,
+                    symbols: ".:",
+                },
+                join_on_submit,
+            ),
+            widget::button("Join", join_on_click),
+            level_widget(),
+            widget::button("Start battle", start_battle),
+            widget::button("Back", go_back_on_click),
+        ],
+    ));
+}
+
+/// Pick which level the next battle is played on.
+fn level_widget() -> impl Bundle {
+    (
+        Name::new("Battle Level Widget"),
+        Node::default(),
+        children![
+            widget::button_small("-", previous_level),
+            (
+                Name::new("Current Battle Level"),
+                Node {
+                    padding: UiRect::horizontal(Px(10.0)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![(widget::label(""), BattleLevelLabel)],
+            ),
+            widget::button_small("+", next_level),
+        ],
+    )
+}
+
+fn previous_level(_: Trigger<Pointer<Click>>, mut level: ResMut<BattleLevel>) {
+    if let Some((index, _)) = battle_levels().filter(|(index, _)| *index < level.0).last() {
+        level.0 = index;
+    }
+}
+
+fn next_level(_: Trigger<Pointer<Click>>, mut level: ResMut<BattleLevel>) {
+    if let Some((index, _)) = battle_levels().find(|(index, _)| *index > level.0) {
+        level.0 = index;
+    }
+}
+
+fn update_battle_level_label(
+    level: Res<BattleLevel>,
+    mut label: Single<&mut Text, With<BattleLevelLabel>>,
+) {
+    label.0 = format!("Level: {}", LEVELS[level.0].name);
+}
+
+fn update_lobby_status(
+    lobby: Res<Lobby>,
+    client: Option<Res<NetClient>>,
+    mut label: Single<&mut Text, With<LobbyStatus>>,
+) {
+    let mut lines = Vec::new();
+    if !lobby.status.is_empty() {
+        lines.push(lobby.status.clone());
+    }
+    match client.as_deref() {
+        Some(NetClient { id: None, .. }) => lines.push("Joining...".to_string()),
+        Some(client) => {
+            let names: Vec<&str> = client
+                .members
+                .iter()
+                .map(|member| member.name.as_str())
+                .collect();
+            lines.push(format!("In the room: {}", names.join(", ")));
+        }
+        None => lines.push("Not in a room.".to_string()),
+    }
+    if !lobby.standings.is_empty() {
+        lines.push("Last battle:".to_string());
+        for (place, standing) in lobby.standings.iter().enumerate() {
+            lines.push(format!(
+                "{}. {} - {} right, {} points",
+                place + 1,
+                standing.name,
+                standing.correct_answers,
+                standing.score
+            ));
+        }
+    }
+    let status = lines.join("\n");
+    if label.0 != status {
+        label.0 = status;
+    }
+}
+
+fn join_on_submit(
+    trigger: Trigger<TextEntrySubmitted>,
+    commands: Commands,
+    profiles: Res<Profiles>,
+    lobby: ResMut<Lobby>,
+    client: Option<Res<NetClient>>,
+) {
+    join(&trigger.value, commands, &profiles, lobby, client.is_some());
+}
+
+fn join_on_click(
+    _: Trigger<Pointer<Click>>,
+    commands: Commands,
+    profiles: Res<Profiles>,
+    lobby: ResMut<Lobby>,
+    client: Option<Res<NetClient>>,
+    entry: Single<&TextEntry>,
+) {
+    join(&entry.value, commands, &profiles, lobby, client.is_some());
+}
+
+fn join(
+    address: &str,
+    mut commands: Commands,
+    profiles: &Profiles,
+    mut lobby: ResMut<Lobby>,
+    already_joined: bool,
+) {
+    if already_joined {
+        lobby.status = "Already in a room. Go back to leave it.".to_string();
+        return;
+    }
+    match NetClient::join(address.trim(), &profiles.current().name) {
+        Ok(client) => {
+            lobby.status.clear();
+            commands.insert_resource(client);
+        }
+        Err(error) => lobby.status = format!("Couldn't join {address}: {error}"),
+    }
+}
+
+fn start_battle(
+    _: Trigger<Pointer<Click>>,
+    client: Option<ResMut<NetClient>>,
+    level: Res<BattleLevel>,
+    mut lobby: ResMut<Lobby>,
+) {
+    let Some(mut client) = client else {
+        lobby.status = "Join a room first.".to_string();
+        return;
+    };
+    if let Err(error) = client.send(&ClientMessage::Start { level: level.0 }) {
+        lobby.status = format!("Couldn't start: {error}");
+    }
+}
+
+fn return_to_lobby(mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Lobby);
+}
+
+/// Leave the room, if in one, and go back to the main menu.
+fn leave(commands: &mut Commands, client: Option<ResMut<NetClient>>, lobby: &mut Lobby) {
+    if let Some(mut client) = client {
+        // The server notices a closed connection too, so there's nothing to do on error.
+        let _ = client.send(&ClientMessage::Leave);
+        commands.remove_resource::<NetClient>();
+        lobby.status.clear();
+    }
+}
+
+fn go_back_on_click(
+    _: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    client: Option<ResMut<NetClient>>,
+    mut lobby: ResMut<Lobby>,
+    mut next_menu: ResMut<NextState<Menu>>,
+) {
+    leave(&mut commands, client, &mut lobby);
+    next_menu.set(Menu::Main);
+}
+
+fn go_back(
+    mut commands: Commands,
+    client: Option<ResMut<NetClient>>,
+    mut lobby: ResMut<Lobby>,
+    mut next_menu: ResMut<NextState<Menu>>,
+) {
+    leave(&mut commands, client, &mut lobby);
+    next_menu.set(Menu::Main);
+}
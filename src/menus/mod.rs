@@ -1,8 +1,14 @@
 //! The game's menus and transitions between them.
 
 mod credits;
+mod daily;
+mod leaderboard;
+mod levels;
+#[cfg(not(target_family = "wasm"))]
+mod lobby;
 mod main;
 mod pause;
+mod profiles;
 mod settings;
 
 use bevy::prelude::*;
@@ -12,9 +18,15 @@ pub(super) fn plugin(app: &mut App) {
 
     app.add_plugins((
         credits::plugin,
+        daily::plugin,
+        leaderboard::plugin,
+        levels::plugin,
+        #[cfg(not(target_family = "wasm"))]
+        lobby::plugin,
         main::plugin,
         settings::plugin,
         pause::plugin,
+        profiles::plugin,
     ));
 }
 
@@ -25,6 +37,11 @@ pub enum Menu {
     None,
     Main,
     Credits,
+    Daily,
+    Levels,
+    Leaderboard,
+    Lobby,
+    Profiles,
     Settings,
     Pause,
 }
@@ -0,0 +1,138 @@
+//! The profiles menu: pick who is playing, add new players and remove old ones.
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*, ui::Val::*};
+
+use crate::{
+    export::export_report,
+    menus::Menu,
+    save::Profiles,
+    theme::{
+        prelude::*,
+        text_entry::{TextEntry, TextEntrySubmitted},
+    },
+};
+
+/// The longest profile name that can be entered.
+const MAX_PROFILE_NAME_LEN: usize = 16;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Menu::Profiles), spawn_profiles_menu);
+    app.add_systems(
+        Update,
+        (
+            go_back.run_if(input_just_pressed(KeyCode::Escape)),
+            update_profile_list.run_if(resource_changed::<Profiles>),
+        )
+            .run_if(in_state(Menu::Profiles)),
+    );
+}
+
+#[derive(Component)]
+struct ProfileList;
+
+#[derive(Component)]
+struct ExportStatus;
+
+fn spawn_profiles_menu(mut commands: Commands, mut profiles: ResMut<Profiles>) {
+    commands.spawn((
+        widget::ui_root("Profiles Menu"),
+        GlobalZIndex(2),
+        StateScoped(Menu::Profiles),
+        children![
+            widget::header("Profiles"),
+            (
+                Name::new("Profile List"),
+                ProfileList,
+                Node {
+                    display: Display::Grid,
+                    row_gap: Px(10.0),
+                    column_gap: Px(20.0),
+                    grid_template_columns: vec![GridTrack::auto(), GridTrack::auto()],
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+            ),
+            widget::label("New profile:"),
+            widget::text_entry(MAX_PROFILE_NAME_LEN, create_profile),
+            widget::button("Export report", export_teacher_report),
+            (widget::label(""), ExportStatus),
+            widget::button("Back", go_back_on_click),
+        ],
+    ));
+    // Fill in the list once it has been spawned.
+    profiles.set_changed();
+}
+
+fn update_profile_list(
+    mut commands: Commands,
+    profiles: Res<Profiles>,
+    list: Single<Entity, With<ProfileList>>,
+) {
+    let can_delete = profiles.profiles.len() > 1;
+    commands
+        .entity(*list)
+        .despawn_related::<Children>()
+        .with_children(|parent| {
+            for profile in &profiles.profiles {
+                let name = if profile.id == profiles.current {
+                    format!("> {}", profile.name)
+                } else {
+                    profile.name.clone()
+                };
+                parent.spawn(widget::button(name, select_profile(profile.id)));
+                if can_delete {
+                    parent.spawn(widget::button_small("x", delete_profile(profile.id)));
+                } else {
+                    // Keep the grid aligned.
+                    parent.spawn(Node::default());
+                }
+            }
+        });
+}
+
+fn select_profile(id: u32) -> impl Fn(Trigger<Pointer<Click>>, ResMut<Profiles>) {
+    move |_, mut profiles| {
+        profiles.current = id;
+    }
+}
+
+fn delete_profile(id: u32) -> impl Fn(Trigger<Pointer<Click>>, ResMut<Profiles>) {
+    move |_, mut profiles| {
+        profiles.delete(id);
+    }
+}
+
+fn create_profile(
+    trigger: Trigger<TextEntrySubmitted>,
+    mut profiles: ResMut<Profiles>,
+    mut entry_query: Query<&mut TextEntry>,
+) {
+    let name = trigger.value.trim();
+    if name.is_empty() {
+        return;
+    }
+    let id = profiles.create(name);
+    profiles.current = id;
+    if let Ok(mut entry) = entry_query.get_mut(trigger.target()) {
+        entry.value.clear();
+    }
+}
+
+fn export_teacher_report(
+    _: Trigger<Pointer<Click>>,
+    profiles: Res<Profiles>,
+    mut status: Single<&mut Text, With<ExportStatus>>,
+) {
+    status.0 = match export_report(&profiles) {
+        Ok(location) => location,
+        Err(error) => format!("Export failed: {error}"),
+    };
+}
+
+fn go_back_on_click(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Main);
+}
+
+fn go_back(mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::Main);
+}
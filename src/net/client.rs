@@ -0,0 +1,429 @@
+//! The game's side of a math battle: joining a room, predicting our own moves, and
+//! following the server for everything else.
+
+use std::{collections::VecDeque, io};
+
+use bevy::prelude::*;
+
+use crate::{
+    AppSystems, PausableSystems,
+    math_mono::{
+        components::{
+            Combo, GridPosition, NumberBlock, Player, PlayerInput, RivalScoreDisplay, RunningTotal,
+        },
+        difficulty::Difficulty,
+        game::{
+            grid::{GridConfig, grid_position_to_world},
+            player::{PLAYER_TINTS, PlayerAssets, duck, read_player_input, spawn_player},
+            question::CurrentQuestion,
+            sequence::SequenceProgress,
+            tiles::Direction,
+            versus::STUN_SECS,
+        },
+        level::{ActiveLevel, LevelProgress, SelectedLevel},
+        review::{RoundReview, next_question},
+        run::{GameMode, Party, RunRng, online},
+    },
+    net::{
+        ClientMessage, Connection, MAX_ROOM_PLAYERS, PlayerId, RoomPlayer, ServerMessage, Standing,
+    },
+    save::SaveData,
+    screens::Screen,
+};
+
+/// How quickly rivals glide to where the server last put them. Higher is snappier.
+const RIVAL_SMOOTHING: f32 = 15.0;
+/// Rivals further than this many blocks from where they're drawn jump straight there,
+/// e.g. after a teleporter.
+const RIVAL_SNAP_BLOCKS: f32 = 2.0;
+/// Sprite tint of each rival, in the order they joined.
+const RIVAL_TINTS: [Color; MAX_ROOM_PLAYERS - 1] = [
+    PLAYER_TINTS[1],
+    Color::srgb(1.0, 0.6, 0.75),
+    Color::srgb(0.6, 1.0, 0.6),
+];
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Lobby>();
+    app.add_event::<FromServer>();
+    app.add_systems(
+        Update,
+        receive_from_server.run_if(resource_exists::<NetClient>),
+    );
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        place_battle_players.after(spawn_player).run_if(in_battle),
+    );
+    app.add_systems(
+        OnExit(Screen::Gameplay),
+        leave_unfinished_battle.run_if(resource_exists::<NetClient>),
+    );
+    // Input is only read while unpaused, so it's only sent then too. Otherwise the last
+    // input read before pausing would be sent every frame.
+    app.add_systems(
+        Update,
+        send_player_input
+            .after(read_player_input)
+            .in_set(AppSystems::RecordInput)
+            .in_set(PausableSystems)
+            .run_if(in_state(Screen::Gameplay).and(in_battle)),
+    );
+    // Not pausable: the battle carries on while a player looks at the pause menu.
+    app.add_systems(
+        Update,
+        (
+            follow_server
+                .after(receive_from_server)
+                .in_set(AppSystems::Update),
+            interpolate_rivals,
+            update_rival_display,
+        )
+            .run_if(in_state(Screen::Gameplay).and(in_battle)),
+    );
+}
+
+/// A connection to a battle server.
+#[derive(Resource)]
+pub struct NetClient {
+    connection: Connection,
+    /// Ours, once the server has let us in.
+    pub id: Option<PlayerId>,
+    /// Everyone in the room, us included.
+    pub members: Vec<RoomPlayer>,
+    /// Where everyone started the battle being played, if any.
+    battle: Option<Vec<(PlayerId, GridPosition)>>,
+    next_seq: u32,
+    /// Moves sent but not yet confirmed. They're replayed on top of each position the
+    /// server confirms, so our own player never waits for the server.
+    pending_moves: VecDeque<(u32, Direction)>,
+}
+
+impl NetClient {
+    pub fn join(address: &str, name: &str) -> io::Result<Self> {
+        let mut connection = Connection::connect(address)?;
+        connection.send(&ClientMessage::Join {
+            name: name.to_string(),
+        })?;
+        Ok(Self {
+            connection,
+            id: None,
+            members: Vec::new(),
+            battle: None,
+            next_seq: 0,
+            pending_moves: VecDeque::new(),
+        })
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) -> io::Result<()> {
+        self.connection.send(message)
+    }
+}
+
+/// What the Math Battle menu shows about the room, kept between connections.
+#[derive(Resource, Debug, Default)]
+pub struct Lobby {
+    pub status: String,
+    /// Results of the last battle played.
+    pub standings: Vec<Standing>,
+}
+
+/// A message from the server for the battle being played.
+#[derive(Event, Debug, Clone)]
+struct FromServer(ServerMessage);
+
+/// Another player in the battle, moved by the server.
+#[derive(Component, Debug)]
+struct Rival {
+    id: PlayerId,
+    name: String,
+    score: i32,
+    correct_answers: u32,
+}
+
+fn in_battle(mode: Res<GameMode>, client: Option<Res<NetClient>>) -> bool {
+    online(mode) && client.is_some()
+}
+
+fn receive_from_server(
+    mut commands: Commands,
+    mut client: ResMut<NetClient>,
+    mut lobby: ResMut<Lobby>,
+    mut selected_level: ResMut<SelectedLevel>,
+    mut mode: ResMut<GameMode>,
+    mut party: ResMut<Party>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut from_server: EventWriter<FromServer>,
+) {
+    // Sends whatever the socket didn't take last frame, too.
+    let connection = &mut client.connection;
+    let messages = match connection
+        .flush()
+        .and_then(|()| connection.receive::<ServerMessage>())
+    {
+        Ok(messages) => messages,
+        Err(error) => {
+            lobby.status = format!("Lost the connection: {error}");
+            if client.battle.is_some() {
+                next_screen.set(Screen::Title);
+            }
+            commands.remove_resource::<NetClient>();
+            return;
+        }
+    };
+    for message in messages {
+        match message {
+            ServerMessage::Welcome { id } => {
+                client.id = Some(id);
+                lobby.status = "Joined the room.".to_string();
+            }
+            ServerMessage::Lobby { players } => client.members = players,
+            ServerMessage::Rejected { reason } => {
+                lobby.status = reason;
+                if client.id.is_none() {
+                    commands.remove_resource::<NetClient>();
+                }
+            }
+            ServerMessage::Started {
+                seed,
+                level,
+                starts,
+            } => {
+                client.battle = Some(starts);
+                client.pending_moves.clear();
+                selected_level.0 = level;
+                *mode = GameMode::Online { seed };
+                *party = Party::Solo;
+                next_screen.set(Screen::Gameplay);
+            }
+            ServerMessage::Finished { standings } => {
+                lobby.standings = standings;
+                lobby.status = "Battle over!".to_string();
+                client.battle = None;
+                next_screen.set(Screen::Title);
+            }
+            message => {
+                from_server.write(FromServer(message));
+            }
+        }
+    }
+}
+
+/// Put our player where the server started it, and spawn everyone else.
+fn place_battle_players(
+    mut commands: Commands,
+    client: Res<NetClient>,
+    player_assets: Res<PlayerAssets>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    config: Res<GridConfig>,
+    mut player_query: Query<&mut GridPosition, With<Player>>,
+) {
+    let Some(starts) = &client.battle else {
+        return;
+    };
+    let rivals = starts.iter().filter(|(id, _)| Some(*id) != client.id);
+    for ((id, start), tint) in rivals.zip(RIVAL_TINTS) {
+        let name = client
+            .members
+            .iter()
+            .find(|member| member.id == *id)
+            .map_or_else(
+                || format!("Player {}", id + 1),
+                |member| member.name.clone(),
+            );
+        commands.spawn((
+            Name::new(format!("Rival {name}")),
+            Rival {
+                id: *id,
+                name,
+                score: 0,
+                correct_answers: 0,
+            },
+            *start,
+            duck(&player_assets, &mut texture_atlas_layouts, &config, tint),
+            StateScoped(Screen::Gameplay),
+        ));
+    }
+    let Some(&(_, start)) = starts.iter().find(|(id, _)| Some(*id) == client.id) else {
+        return;
+    };
+    for mut grid_pos in &mut player_query {
+        *grid_pos = start;
+    }
+}
+
+fn send_player_input(
+    mut client: ResMut<NetClient>,
+    player_query: Query<&PlayerInput, With<Player>>,
+) {
+    // A broken connection shows up when receiving, so send errors are left to that.
+    for input in &player_query {
+        for &direction in &input.moves {
+            client.next_seq += 1;
+            let seq = client.next_seq;
+            client.pending_moves.push_back((seq, direction));
+            let _ = client.send(&ClientMessage::Move { seq, direction });
+        }
+        if input.eat {
+            let _ = client.send(&ClientMessage::Eat);
+        }
+    }
+}
+
+/// Apply what the server says happened: where everyone moved, what they ate, and when
+/// the next question starts.
+fn follow_server(
+    mut from_server: EventReader<FromServer>,
+    mut client: ResMut<NetClient>,
+    config: Res<GridConfig>,
+    mode: Res<GameMode>,
+    level: Res<ActiveLevel>,
+    difficulty: Res<Difficulty>,
+    save: Res<SaveData>,
+    mut rng: ResMut<RunRng>,
+    mut question: ResMut<CurrentQuestion>,
+    mut sequence: ResMut<SequenceProgress>,
+    mut round_review: ResMut<RoundReview>,
+    mut progress: ResMut<LevelProgress>,
+    mut player_query: Query<(
+        &mut Player,
+        &mut GridPosition,
+        &mut RunningTotal,
+        &mut Combo,
+    )>,
+    mut rival_query: Query<(&mut Rival, &mut GridPosition), Without<Player>>,
+    mut block_query: Query<(&mut NumberBlock, &GridPosition), (Without<Player>, Without<Rival>)>,
+) {
+    for FromServer(message) in from_server.read() {
+        match message {
+            ServerMessage::Moved { id, position, seq } if Some(*id) == client.id => {
+                // Replay the moves the server hasn't seen yet on top of where it says we
+                // are. Usually that's where we already are.
+                client.pending_moves.retain(|(pending, _)| pending > seq);
+                let predicted = client
+                    .pending_moves
+                    .iter()
+                    .fold(*position, |pos, (_, direction)| {
+                        config.layout.step(pos, *direction)
+                    });
+                for (_, mut grid_pos, _, _) in &mut player_query {
+                    grid_pos.set_if_neq(predicted);
+                }
+            }
+            ServerMessage::Moved { id, position, .. } => {
+                for (_, mut grid_pos) in rival_query.iter_mut().filter(|(rival, _)| rival.id == *id)
+                {
+                    grid_pos.set_if_neq(*position);
+                }
+            }
+            ServerMessage::Ate {
+                id,
+                eaten,
+                restored,
+                correct,
+                total,
+                score,
+                correct_answers,
+                streak,
+            } => {
+                let ordered = question.question.ordered_answers().is_some();
+                for (mut block, pos) in &mut block_query {
+                    if eaten.contains(pos) {
+                        block.is_eaten = true;
+                        if ordered && *correct == Some(true) {
+                            sequence.eaten.push(block.value);
+                        }
+                    } else if restored.contains(pos) {
+                        block.is_eaten = false;
+                    }
+                }
+                if *correct == Some(true) {
+                    progress.correct_answers += 1;
+                }
+                if Some(*id) == client.id {
+                    for (mut player, _, mut running, mut combo) in &mut player_query {
+                        player.score = *score;
+                        player.correct_answers = *correct_answers;
+                        combo.streak = *streak;
+                        combo.best_streak = combo.best_streak.max(*streak);
+                        running.total = *total;
+                        if *correct == Some(false) {
+                            player.stunned_secs = STUN_SECS;
+                        }
+                    }
+                }
+                for (mut rival, _) in rival_query.iter_mut().filter(|(rival, _)| rival.id == *id) {
+                    rival.score = *score;
+                    rival.correct_answers = *correct_answers;
+                }
+            }
+            ServerMessage::NextQuestion { round } => {
+                // Rolled from the same seed as the server's, so it's the same question.
+                *sequence = SequenceProgress::default();
+                *round_review = RoundReview::default();
+                question.question = next_question(
+                    *mode,
+                    &level.definition,
+                    &difficulty,
+                    &save,
+                    &mut rng.questions,
+                );
+                question.round = *round;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Rivals glide to where the server last put them, hiding the gaps between updates.
+fn interpolate_rivals(
+    time: Res<Time>,
+    config: Res<GridConfig>,
+    mut rival_query: Query<(&GridPosition, &mut Transform), With<Rival>>,
+) {
+    let blend = 1.0 - (-RIVAL_SMOOTHING * time.delta_secs()).exp();
+    for (grid_pos, mut transform) in &mut rival_query {
+        // Just under our own player.
+        let target = grid_position_to_world(&config, grid_pos).extend(0.9);
+        let far = transform.translation.distance(target) > RIVAL_SNAP_BLOCKS * config.block_size.x;
+        transform.translation = if far {
+            target
+        } else {
+            transform.translation.lerp(target, blend)
+        };
+    }
+}
+
+fn update_rival_display(
+    rival_query: Query<&Rival>,
+    mut display_query: Query<&mut Text2d, With<RivalScoreDisplay>>,
+) {
+    let mut rivals: Vec<&Rival> = rival_query.iter().collect();
+    rivals.sort_by_key(|rival| rival.id);
+    let lines: Vec<String> = rivals
+        .iter()
+        .map(|rival| {
+            format!(
+                "{}\n{} right, {} points",
+                rival.name, rival.correct_answers, rival.score
+            )
+        })
+        .collect();
+    let display = lines.join("\n\n");
+    for mut text in &mut display_query {
+        text.0.clone_from(&display);
+    }
+}
+
+/// Quitting a battle part way leaves the room.
+fn leave_unfinished_battle(
+    mut commands: Commands,
+    mut client: ResMut<NetClient>,
+    mut lobby: ResMut<Lobby>,
+) {
+    if client.battle.is_none() {
+        return;
+    }
+    let _ = client.send(&ClientMessage::Leave);
+    commands.remove_resource::<NetClient>();
+    lobby.status = "You left the battle.".to_string();
+}
@@ -0,0 +1,277 @@
+//! Math battles over the network: a headless server hosts a room, and players join it
+//! by address from the Math Battle menu.
+//!
+//! Messages are JSON, one per line, over TCP. The server rolls every board from a seed
+//! it picks and checks every eat; the game only predicts its own player's moves.
+
+pub mod client;
+mod room;
+pub mod server;
+
+use std::{
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::math_mono::{
+    components::GridPosition,
+    game::tiles::Direction,
+    level::{LEVELS, LevelDefinition},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_plugins(client::plugin);
+}
+
+/// Where the server hosts and the game looks for it unless told otherwise.
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:7878";
+/// Most players in one room.
+pub const MAX_ROOM_PLAYERS: usize = 4;
+/// How long joining waits for the server to answer.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+/// Longest message accepted, so a bad peer can't fill memory.
+const MAX_LINE_LEN: usize = 64 * 1024;
+/// Most bytes waiting to go out to one peer. A peer that falls this far behind is
+/// treated as gone rather than stalling everyone else.
+const MAX_OUTGOING_LEN: usize = 256 * 1024;
+
+pub type PlayerId = u32;
+
+/// Sent by the game to the server.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ClientMessage {
+    Join {
+        name: String,
+    },
+    /// Start a battle on [`LEVELS`]`[level]` with everyone in the room.
+    Start {
+        level: usize,
+    },
+    /// `seq` counts up with every move, so the server can say which it has applied.
+    Move {
+        seq: u32,
+        direction: Direction,
+    },
+    Eat,
+    Leave,
+}
+
+/// Sent by the server to the game.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ServerMessage {
+    /// You joined the room as `id`.
+    Welcome {
+        id: PlayerId,
+    },
+    /// Everyone in the room, in the order they joined.
+    Lobby {
+        players: Vec<RoomPlayer>,
+    },
+    Rejected {
+        reason: String,
+    },
+    /// A battle began. Everyone rolls the same boards from `seed`.
+    Started {
+        seed: u64,
+        level: usize,
+        starts: Vec<(PlayerId, GridPosition)>,
+    },
+    /// Where a player is after every move up to `seq`.
+    Moved {
+        id: PlayerId,
+        position: GridPosition,
+        seq: u32,
+    },
+    /// A player ate. `correct` is `None` while a total is still being built, and
+    /// `Some(false)` stuns the player.
+    Ate {
+        id: PlayerId,
+        eaten: Vec<GridPosition>,
+        /// Blocks put back on the board, from a chain that overshot its total.
+        restored: Vec<GridPosition>,
+        correct: Option<bool>,
+        total: i32,
+        score: i32,
+        correct_answers: u32,
+        /// Right answers in a row.
+        streak: u32,
+    },
+    /// The board was cleared. Everyone rolls the next question and board.
+    NextQuestion {
+        round: u32,
+    },
+    /// The level goal was reached, by everyone together.
+    Finished {
+        standings: Vec<Standing>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RoomPlayer {
+    pub id: PlayerId,
+    pub name: String,
+}
+
+/// One player's result from a finished battle.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Standing {
+    pub name: String,
+    pub correct_answers: u32,
+    pub score: i32,
+}
+
+/// Levels a battle can be played on: ones with a goal to race to, and no boss.
+pub fn battle_levels() -> impl Iterator<Item = (usize, &'static LevelDefinition)> {
+    LEVELS
+        .iter()
+        .enumerate()
+        .filter(|(_, level)| level.goal.is_some() && level.boss.is_none())
+}
+
+/// One end of a TCP connection, sending and receiving newline-separated JSON without
+/// blocking.
+pub struct Connection {
+    stream: TcpStream,
+    received: Vec<u8>,
+    /// Sent messages the socket hasn't taken yet.
+    outgoing: Vec<u8>,
+    closed: bool,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            stream,
+            received: Vec::new(),
+            outgoing: Vec::new(),
+            closed: false,
+        })
+    }
+
+    pub fn connect(address: &str) -> io::Result<Self> {
+        let address = address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "address not found"))?;
+        Self::new(TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)?)
+    }
+
+    /// Queue a message and send as much as the socket takes. Whatever it doesn't goes
+    /// out on a later [`Connection::flush`].
+    pub fn send(&mut self, message: &impl Serialize) -> io::Result<()> {
+        serde_json::to_writer(&mut self.outgoing, message)?;
+        self.outgoing.push(b'\n');
+        self.flush()
+    }
+
+    /// Send what's queued without waiting on a full socket. Errors if the other end is
+    /// gone or isn't keeping up.
+    pub fn flush(&mut self) -> io::Result<()> {
+        let mut written = 0;
+        while written < self.outgoing.len() {
+            match self.stream.write(&self.outgoing[written..]) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(count) => written += count,
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+        self.outgoing.drain(..written);
+        if self.outgoing.len() > MAX_OUTGOING_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "peer isn't keeping up",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Every whole message that has arrived. Errors once the other end has closed and
+    /// everything it sent has been read.
+    pub fn receive<T: DeserializeOwned>(&mut self) -> io::Result<Vec<T>> {
+        let mut buffer = [0; 4096];
+        while !self.closed {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => self.closed = true,
+                Ok(count) => self.received.extend_from_slice(&buffer[..count]),
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+
+        let mut messages = Vec::new();
+        while let Some(end) = self.received.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = self.received.drain(..=end).collect();
+            messages.push(serde_json::from_slice(&line)?);
+        }
+        if self.received.len() > MAX_LINE_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "message too long",
+            ));
+        }
+        if messages.is_empty() && self.closed {
+            return Err(io::ErrorKind::ConnectionAborted.into());
+        }
+        Ok(messages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn test_connection_sends_whole_messages() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = Connection::connect(&listener.local_addr().unwrap().to_string()).unwrap();
+        let mut server = Connection::new(listener.accept().unwrap().0).unwrap();
+
+        let sent = [
+            ClientMessage::Join {
+                name: "Ada".to_string(),
+            },
+            ClientMessage::Move {
+                seq: 1,
+                direction: Direction::Left,
+            },
+        ];
+        for message in &sent {
+            client.send(message).unwrap();
+        }
+        let mut received: Vec<ClientMessage> = Vec::new();
+        while received.len() < sent.len() {
+            received.extend(server.receive::<ClientMessage>().unwrap());
+        }
+        assert_eq!(received, sent);
+
+        drop(client);
+        while server.receive::<ClientMessage>().is_ok() {}
+    }
+
+    #[test]
+    fn test_sending_to_a_stalled_peer_gives_up_without_blocking() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = Connection::connect(&listener.local_addr().unwrap().to_string()).unwrap();
+        // Never reads.
+        let _server = listener.accept().unwrap();
+
+        let message = ClientMessage::Join {
+            name: "x".repeat(MAX_LINE_LEN / 2),
+        };
+        let sent = (0..1000)
+            .take_while(|_| client.send(&message).is_ok())
+            .count();
+        assert!(sent < 1000);
+    }
+}
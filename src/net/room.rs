@@ -0,0 +1,557 @@
+//! The server's side of a room: who is in it and, during a battle, the one true board.
+//!
+//! Nothing here touches the network, so battles can be played out in tests.
+
+use std::time::Duration;
+
+use crate::{
+    math_mono::{
+        components::{Combo, GridPosition, RunningTotal},
+        difficulty::Difficulty,
+        game::{
+            grid::{GridConfig, roll_board},
+            number::Number,
+            player::{CORRECT_ANSWER_POINTS, start_positions},
+            question::Question,
+            sequence::SequenceProgress,
+            sum_target::ChainStep,
+            tiles::Direction,
+            versus::STUN_SECS,
+        },
+        level::{LEVELS, LevelDefinition},
+        review::next_question,
+        run::{GameMode, RunRng},
+    },
+    net::{ClientMessage, MAX_ROOM_PLAYERS, PlayerId, RoomPlayer, ServerMessage, Standing},
+    save::SaveData,
+};
+
+/// Longest name a player can join with. Longer names are cut short.
+const MAX_NAME_LEN: usize = 16;
+
+/// Who a message from the room is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recipient {
+    Everyone,
+    Only(PlayerId),
+}
+
+pub type Outbox = Vec<(Recipient, ServerMessage)>;
+
+#[derive(Default)]
+pub struct Room {
+    members: Vec<RoomPlayer>,
+    next_id: PlayerId,
+    battle: Option<Battle>,
+}
+
+impl Room {
+    /// Let a player in, or say why not.
+    pub fn join(&mut self, name: &str) -> Result<(PlayerId, Outbox), String> {
+        if self.battle.is_some() {
+            return Err("A battle is already going. Try again when it's over.".to_string());
+        }
+        if self.members.len() >= MAX_ROOM_PLAYERS {
+            return Err("The room is full.".to_string());
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let name = match name.trim() {
+            "" => format!("Player {}", id + 1),
+            name => name.chars().take(MAX_NAME_LEN).collect(),
+        };
+        self.members.push(RoomPlayer { id, name });
+        let mut outbox = vec![(Recipient::Only(id), ServerMessage::Welcome { id })];
+        outbox.push(self.lobby());
+        Ok((id, outbox))
+    }
+
+    pub fn leave(&mut self, id: PlayerId) -> Outbox {
+        self.members.retain(|member| member.id != id);
+        if let Some(battle) = &mut self.battle {
+            battle.racers.retain(|racer| racer.id != id);
+            if battle.racers.is_empty() {
+                self.battle = None;
+            }
+        }
+        vec![self.lobby()]
+    }
+
+    pub fn handle(&mut self, id: PlayerId, message: ClientMessage) -> Outbox {
+        match message {
+            // Joining is handled before a player is in the room.
+            ClientMessage::Join { .. } => Vec::new(),
+            ClientMessage::Start { level } => self.start(id, level, rand::random()),
+            ClientMessage::Move { seq, direction } => match &mut self.battle {
+                Some(battle) => battle.move_racer(id, seq, direction),
+                None => Vec::new(),
+            },
+            ClientMessage::Eat => self.eat(id),
+            ClientMessage::Leave => self.leave(id),
+        }
+    }
+
+    /// Count down stuns and time answers for the speed bonus.
+    pub fn tick(&mut self, delta: Duration) {
+        let Some(battle) = &mut self.battle else {
+            return;
+        };
+        for racer in &mut battle.racers {
+            racer.stunned_secs = (racer.stunned_secs - delta.as_secs_f32()).max(0.0);
+            racer.combo.since_last_answer.tick(delta);
+        }
+    }
+
+    /// Start a battle on [`LEVELS`]`[level]`, with boards rolled from `seed`.
+    pub fn start(&mut self, id: PlayerId, level: usize, seed: u64) -> Outbox {
+        let reject = |reason: &str| {
+            vec![(
+                Recipient::Only(id),
+                ServerMessage::Rejected {
+                    reason: reason.to_string(),
+                },
+            )]
+        };
+        if self.battle.is_some() {
+            return reject("A battle is already going.");
+        }
+        let Some(definition) = LEVELS
+            .get(level)
+            .filter(|level| level.goal.is_some() && level.boss.is_none())
+        else {
+            return reject("That level can't be played as a battle.");
+        };
+        let battle = Battle::new(definition, seed, &self.members);
+        let starts = battle
+            .racers
+            .iter()
+            .map(|racer| (racer.id, racer.position))
+            .collect();
+        self.battle = Some(battle);
+        vec![(
+            Recipient::Everyone,
+            ServerMessage::Started {
+                seed,
+                level,
+                starts,
+            },
+        )]
+    }
+
+    fn eat(&mut self, id: PlayerId) -> Outbox {
+        let Some(battle) = &mut self.battle else {
+            return Vec::new();
+        };
+        let mut outbox = battle.eat(id);
+        if battle.is_won() {
+            let standings = battle.standings();
+            self.battle = None;
+            outbox.push((Recipient::Everyone, ServerMessage::Finished { standings }));
+        } else if let Some(round) = battle.advance_if_cleared() {
+            outbox.push((Recipient::Everyone, ServerMessage::NextQuestion { round }));
+        }
+        outbox
+    }
+
+    fn lobby(&self) -> (Recipient, ServerMessage) {
+        (
+            Recipient::Everyone,
+            ServerMessage::Lobby {
+                players: self.members.clone(),
+            },
+        )
+    }
+}
+
+/// A battle in progress. Rolls its questions and boards exactly like a run started with
+/// [`GameMode::Online`] and the same seed, so every game shows the same board.
+struct Battle {
+    seed: u64,
+    definition: LevelDefinition,
+    config: GridConfig,
+    difficulty: Difficulty,
+    rng: RunRng,
+    question: Question,
+    round: u32,
+    sequence: SequenceProgress,
+    blocks: Vec<Block>,
+    racers: Vec<Racer>,
+    /// Right answers by everyone, towards the level goal.
+    correct_answers: u32,
+}
+
+struct Block {
+    position: GridPosition,
+    value: Number,
+    is_eaten: bool,
+}
+
+struct Racer {
+    id: PlayerId,
+    name: String,
+    position: GridPosition,
+    score: i32,
+    correct_answers: u32,
+    /// Scores right answers the same way the game does.
+    combo: Combo,
+    running: RunningTotal,
+    /// Indices into [`Battle::blocks`] of the chain being built.
+    chain: Vec<usize>,
+    stunned_secs: f32,
+}
+
+impl Battle {
+    fn new(definition: &LevelDefinition, seed: u64, members: &[RoomPlayer]) -> Self {
+        let mut rng = RunRng::from_seed(seed);
+        let difficulty = Difficulty::for_level(definition);
+        let question = next_question(
+            GameMode::Online { seed },
+            definition,
+            &difficulty,
+            &SaveData::default(),
+            &mut rng.questions,
+        );
+        let config = GridConfig::for_level(definition);
+        let racers = members
+            .iter()
+            .zip(start_positions(&config, members.len()))
+            .map(|(member, position)| Racer {
+                id: member.id,
+                name: member.name.clone(),
+                position,
+                score: 0,
+                correct_answers: 0,
+                combo: Combo::default(),
+                running: RunningTotal::default(),
+                chain: Vec::new(),
+                stunned_secs: 0.0,
+            })
+            .collect();
+        let mut battle = Self {
+            seed,
+            definition: *definition,
+            config,
+            difficulty,
+            rng,
+            question,
+            round: 0,
+            sequence: SequenceProgress::default(),
+            blocks: Vec::new(),
+            racers,
+            correct_answers: 0,
+        };
+        battle.roll_blocks();
+        battle
+    }
+
+    fn roll_blocks(&mut self) {
+        let params = self.difficulty.board_params(&self.question);
+        self.blocks = roll_board(&self.config, &self.question, &params, &mut self.rng.board)
+            .into_iter()
+            .map(|(position, value, _)| Block {
+                position,
+                value,
+                is_eaten: false,
+            })
+            .collect();
+    }
+
+    fn move_racer(&mut self, id: PlayerId, seq: u32, direction: Direction) -> Outbox {
+        let Some(racer) = self.racers.iter_mut().find(|racer| racer.id == id) else {
+            return Vec::new();
+        };
+        // Stunned players stay put. Saying so puts them back if they moved anyway.
+        if racer.stunned_secs <= 0.0 {
+            racer.position = self.config.layout.step(racer.position, direction);
+        }
+        vec![(
+            Recipient::Everyone,
+            ServerMessage::Moved {
+                id,
+                position: racer.position,
+                seq,
+            },
+        )]
+    }
+
+    fn eat(&mut self, id: PlayerId) -> Outbox {
+        let Some(racer) = self
+            .racers
+            .iter_mut()
+            .find(|racer| racer.id == id && racer.stunned_secs <= 0.0)
+        else {
+            return Vec::new();
+        };
+        let Some(index) = self
+            .blocks
+            .iter()
+            .position(|block| block.position == racer.position && !block.is_eaten)
+        else {
+            return Vec::new();
+        };
+        let value = self.blocks[index].value;
+        let mut eaten = vec![self.blocks[index].position];
+        let mut restored = Vec::new();
+
+        let correct = match self.question.target_total() {
+            Some(target) => match racer
+                .running
+                .add(value.as_integer().unwrap_or_default(), target)
+            {
+                ChainStep::Building => {
+                    racer.chain.push(index);
+                    None
+                }
+                ChainStep::Made => {
+                    racer.chain.push(index);
+                    racer.score += racer
+                        .combo
+                        .score_correct(CORRECT_ANSWER_POINTS * racer.chain.len() as i32);
+                    racer.chain.clear();
+                    Some(true)
+                }
+                ChainStep::Overshot => {
+                    // The whole chain goes back on the board.
+                    eaten.clear();
+                    for eaten_index in racer.chain.drain(..) {
+                        self.blocks[eaten_index].is_eaten = false;
+                        restored.push(self.blocks[eaten_index].position);
+                    }
+                    Some(false)
+                }
+            },
+            None => {
+                let correct = self.sequence.accepts(&self.question, value);
+                if correct {
+                    if self.question.ordered_answers().is_some() {
+                        self.sequence.eaten.push(value);
+                    }
+                    racer.score += racer.combo.score_correct(CORRECT_ANSWER_POINTS);
+                } else {
+                    // Wrong answers stay on the board.
+                    eaten.clear();
+                }
+                Some(correct)
+            }
+        };
+        if !eaten.is_empty() {
+            self.blocks[index].is_eaten = true;
+        }
+        match correct {
+            Some(true) => {
+                racer.correct_answers += 1;
+                self.correct_answers += 1;
+            }
+            Some(false) => {
+                racer.stunned_secs = STUN_SECS;
+                racer.combo.break_streak();
+            }
+            None => {}
+        }
+
+        vec![(
+            Recipient::Everyone,
+            ServerMessage::Ate {
+                id,
+                eaten,
+                restored,
+                correct,
+                total: racer.running.total,
+                score: racer.score,
+                correct_answers: racer.correct_answers,
+                streak: racer.combo.streak,
+            },
+        )]
+    }
+
+    fn is_won(&self) -> bool {
+        self.definition
+            .goal
+            .is_some_and(|goal| self.correct_answers >= goal)
+    }
+
+    /// Move on to the next question once the board is cleared, like the game does on
+    /// its own. Returns the new round.
+    fn advance_if_cleared(&mut self) -> Option<u32> {
+        // Wait for chains in progress to finish.
+        if self.racers.iter().any(|racer| racer.running.total != 0) {
+            return None;
+        }
+        let remaining: Vec<Number> = self
+            .blocks
+            .iter()
+            .filter(|block| !block.is_eaten)
+            .map(|block| block.value)
+            .collect();
+        if !self.question.is_cleared(&remaining) {
+            return None;
+        }
+        self.sequence = SequenceProgress::default();
+        self.question = next_question(
+            GameMode::Online { seed: self.seed },
+            &self.definition,
+            &self.difficulty,
+            &SaveData::default(),
+            &mut self.rng.questions,
+        );
+        self.round += 1;
+        self.roll_blocks();
+        // A new question restarts the clock on the speed bonus.
+        for racer in &mut self.racers {
+            racer.combo.since_last_answer.reset();
+        }
+        Some(self.round)
+    }
+
+    /// Everyone's result, best first.
+    fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = self
+            .racers
+            .iter()
+            .map(|racer| Standing {
+                name: racer.name.clone(),
+                correct_answers: racer.correct_answers,
+                score: racer.score,
+            })
+            .collect();
+        standings
+            .sort_by_key(|standing| std::cmp::Reverse((standing.correct_answers, standing.score)));
+        standings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::battle_levels;
+
+    fn racer_at(room: &mut Room, id: PlayerId, position: GridPosition) {
+        let battle = room.battle.as_mut().unwrap();
+        battle
+            .racers
+            .iter_mut()
+            .find(|racer| racer.id == id)
+            .unwrap()
+            .position = position;
+    }
+
+    #[test]
+    fn test_room_fills_up_and_locks_during_battles() {
+        let mut room = Room::default();
+        for name in ["Ada", "Bo", "Cy", "Di"] {
+            room.join(name).unwrap();
+        }
+        assert!(room.join("Ed").is_err());
+        room.leave(3);
+        let (id, outbox) = room.join("").unwrap();
+        assert_eq!(
+            outbox[0],
+            (Recipient::Only(id), ServerMessage::Welcome { id })
+        );
+        assert_eq!(room.members.last().unwrap().name, "Player 5");
+
+        let (level, _) = battle_levels().next().unwrap();
+        let outbox = room.start(0, level, 1);
+        let ServerMessage::Started { starts, .. } = &outbox[0].1 else {
+            panic!("battle didn't start: {outbox:?}");
+        };
+        assert_eq!(starts.len(), 4);
+        assert!(room.join("Ed").is_err());
+    }
+
+    #[test]
+    fn test_server_boards_are_seeded() {
+        let (level, definition) = battle_levels().next().unwrap();
+        let members = [RoomPlayer {
+            id: 0,
+            name: "Ada".to_string(),
+        }];
+        let values = |seed| -> Vec<Number> {
+            Battle::new(definition, seed, &members)
+                .blocks
+                .iter()
+                .map(|block| block.value)
+                .collect()
+        };
+        assert_eq!(values(9), values(9));
+        assert_ne!(values(9), values(10));
+
+        let mut room = Room::default();
+        room.join("Ada").unwrap();
+        assert!(matches!(
+            room.start(0, LEVELS.len(), 9)[0].1,
+            ServerMessage::Rejected { .. }
+        ));
+        assert!(matches!(
+            room.start(0, level, 9)[0].1,
+            ServerMessage::Started { seed: 9, .. }
+        ));
+    }
+
+    #[test]
+    fn test_server_checks_eats_and_stuns_wrong_answers() {
+        let mut room = Room::default();
+        room.join("Ada").unwrap();
+        room.join("Bo").unwrap();
+        // Pick a level whose questions don't build totals, so one eat is one answer.
+        let (level, _) = battle_levels()
+            .find(|(_, level)| {
+                level
+                    .questions
+                    .iter()
+                    .all(|question| question.target_total().is_none())
+            })
+            .unwrap();
+        room.start(0, level, 3);
+
+        let battle = room.battle.as_ref().unwrap();
+        let accepts = |block: &Block| battle.sequence.accepts(&battle.question, block.value);
+        let right = battle.blocks.iter().find(|block| accepts(block)).unwrap();
+        let wrong = battle.blocks.iter().find(|block| !accepts(block)).unwrap();
+        let (right, wrong) = (right.position, wrong.position);
+
+        racer_at(&mut room, 1, wrong);
+        let outbox = room.handle(1, ClientMessage::Eat);
+        assert!(matches!(
+            outbox[0].1,
+            ServerMessage::Ate {
+                correct: Some(false),
+                ..
+            }
+        ));
+        // Stunned players can't move or eat.
+        let moved = room.handle(
+            1,
+            ClientMessage::Move {
+                seq: 1,
+                direction: Direction::Up,
+            },
+        );
+        assert!(matches!(
+            moved[0].1,
+            ServerMessage::Moved { position, seq: 1, .. } if position == wrong
+        ));
+        racer_at(&mut room, 1, right);
+        assert!(room.handle(1, ClientMessage::Eat).is_empty());
+
+        room.tick(Duration::from_secs_f32(STUN_SECS));
+        racer_at(&mut room, 0, right);
+        let outbox = room.handle(0, ClientMessage::Eat);
+        // Scored like any other mode: answered quickly, so with the speed bonus.
+        let mut combo = Combo::default();
+        let expected = combo.score_correct(CORRECT_ANSWER_POINTS);
+        assert!(matches!(
+            outbox[0].1,
+            ServerMessage::Ate {
+                id: 0,
+                correct: Some(true),
+                correct_answers: 1,
+                score,
+                streak: 1,
+                ..
+            } if score == expected
+        ));
+        // It's gone for everyone.
+        assert!(room.handle(1, ClientMessage::Eat).is_empty());
+    }
+}
@@ -0,0 +1,250 @@
+//! The headless battle server: accepts players, passes their messages to the [`Room`]
+//! and sends back what it says.
+
+use std::{
+    io,
+    net::TcpListener,
+    time::{Duration, Instant},
+};
+
+use bevy::{app::ScheduleRunnerPlugin, log::LogPlugin, prelude::*};
+
+use crate::net::{
+    ClientMessage, Connection, PlayerId, ServerMessage,
+    room::{Outbox, Recipient, Room},
+};
+
+/// How long the server sleeps between checking for messages.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+/// How long a new connection has to ask to join before it's dropped.
+const JOIN_TIMEOUT: Duration = Duration::from_secs(5);
+/// Most connections waiting to join at once. Any more are turned away.
+const MAX_NEWCOMERS: usize = 16;
+
+/// Host a room on `address` until the process is stopped.
+pub fn run(address: &str) -> io::Result<()> {
+    let server = Server::bind(address)?;
+    let local_address = server.listener.local_addr()?;
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(POLL_INTERVAL)),
+        LogPlugin::default(),
+    ))
+    .insert_resource(server)
+    .add_systems(Update, poll);
+    info!("Hosting math battles on {local_address}");
+    app.run();
+    Ok(())
+}
+
+fn poll(mut server: ResMut<Server>) {
+    server.poll();
+}
+
+#[derive(Resource)]
+struct Server {
+    listener: TcpListener,
+    room: Room,
+    /// Connected, but not yet in the room, with when they connected.
+    newcomers: Vec<(Instant, Connection)>,
+    members: Vec<(PlayerId, Connection)>,
+    last_poll: Instant,
+}
+
+impl Server {
+    fn bind(address: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            room: Room::default(),
+            newcomers: Vec::new(),
+            members: Vec::new(),
+            last_poll: Instant::now(),
+        })
+    }
+
+    /// Accept new connections and handle every message that has arrived.
+    fn poll(&mut self) {
+        let now = Instant::now();
+        self.room.tick(now - self.last_poll);
+        self.last_poll = now;
+
+        loop {
+            match self
+                .listener
+                .accept()
+                .and_then(|(stream, _)| Connection::new(stream))
+            {
+                Ok(_) if self.newcomers.len() >= MAX_NEWCOMERS => {
+                    warn!("Turned a player away: too many waiting to join");
+                }
+                Ok(connection) => self.newcomers.push((now, connection)),
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) => {
+                    warn!("Couldn't accept a player: {error}");
+                    break;
+                }
+            }
+        }
+
+        for (connected_at, mut connection) in std::mem::take(&mut self.newcomers) {
+            let Ok(messages) = connection.receive::<ClientMessage>() else {
+                continue;
+            };
+            let Some(name) = messages.into_iter().find_map(|message| match message {
+                ClientMessage::Join { name } => Some(name),
+                _ => None,
+            }) else {
+                if now - connected_at < JOIN_TIMEOUT {
+                    self.newcomers.push((connected_at, connection));
+                } else {
+                    warn!("Dropped a connection that never asked to join");
+                }
+                continue;
+            };
+            match self.room.join(&name) {
+                Ok((id, outbox)) => {
+                    info!("{name} joined");
+                    self.members.push((id, connection));
+                    self.send(outbox);
+                }
+                Err(reason) => {
+                    let _ = connection.send(&ServerMessage::Rejected { reason });
+                }
+            }
+        }
+
+        let mut left = Vec::new();
+        let mut outbox = Vec::new();
+        for (id, connection) in &mut self.members {
+            // Also sends whatever a slow player's socket didn't take last time.
+            match connection
+                .flush()
+                .and_then(|()| connection.receive::<ClientMessage>())
+            {
+                Ok(messages) => {
+                    for message in messages {
+                        if message == ClientMessage::Leave {
+                            left.push(*id);
+                        }
+                        outbox.extend(self.room.handle(*id, message));
+                    }
+                }
+                Err(_) => left.push(*id),
+            }
+        }
+        for id in left {
+            self.members.retain(|(member, _)| *member != id);
+            outbox.extend(self.room.leave(id));
+        }
+        self.send(outbox);
+    }
+
+    fn send(&mut self, outbox: Outbox) {
+        for (recipient, message) in outbox {
+            for (id, connection) in &mut self.members {
+                if recipient == Recipient::Everyone || recipient == Recipient::Only(*id) {
+                    // Players whose connection broke or fell behind are dropped on the next poll.
+                    let _ = connection.send(&message);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::net::battle_levels;
+
+    /// Poll the server until `connection` has `count` more messages.
+    fn receive(
+        server: &mut Server,
+        connection: &mut Connection,
+        count: usize,
+    ) -> Vec<ServerMessage> {
+        let mut messages = Vec::new();
+        for _ in 0..1000 {
+            server.poll();
+            messages.extend(connection.receive::<ServerMessage>().unwrap());
+            if messages.len() >= count {
+                return messages;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        panic!("only got {messages:?}");
+    }
+
+    #[test]
+    fn test_battle_on_localhost() {
+        let mut server = Server::bind("127.0.0.1:0").unwrap();
+        let address = server.listener.local_addr().unwrap().to_string();
+
+        let mut ada = Connection::connect(&address).unwrap();
+        ada.send(&ClientMessage::Join {
+            name: "Ada".to_string(),
+        })
+        .unwrap();
+        assert_eq!(
+            receive(&mut server, &mut ada, 2)[0],
+            ServerMessage::Welcome { id: 0 }
+        );
+
+        let mut bo = Connection::connect(&address).unwrap();
+        bo.send(&ClientMessage::Join {
+            name: "Bo".to_string(),
+        })
+        .unwrap();
+        let ServerMessage::Lobby { players } = &receive(&mut server, &mut bo, 2)[1] else {
+            panic!("no lobby");
+        };
+        assert_eq!(players.len(), 2);
+        receive(&mut server, &mut ada, 1);
+
+        let (level, _) = battle_levels().next().unwrap();
+        ada.send(&ClientMessage::Start { level }).unwrap();
+        let started = receive(&mut server, &mut bo, 1);
+        assert!(matches!(&started[0], ServerMessage::Started { starts, .. } if starts.len() == 2));
+        receive(&mut server, &mut ada, 1);
+
+        bo.send(&ClientMessage::Move {
+            seq: 1,
+            direction: crate::math_mono::game::tiles::Direction::Left,
+        })
+        .unwrap();
+        assert!(matches!(
+            receive(&mut server, &mut ada, 1)[0],
+            ServerMessage::Moved { id: 1, seq: 1, .. }
+        ));
+
+        // Leaving hands the room back to whoever is left.
+        drop(bo);
+        let ServerMessage::Lobby { players } = &receive(&mut server, &mut ada, 1)[0] else {
+            panic!("no lobby");
+        };
+        assert_eq!(players.len(), 1);
+    }
+
+    #[test]
+    fn test_newcomers_that_never_join_are_dropped() {
+        let mut server = Server::bind("127.0.0.1:0").unwrap();
+        let address = server.listener.local_addr().unwrap().to_string();
+
+        let _silent = Connection::connect(&address).unwrap();
+        for _ in 0..1000 {
+            server.poll();
+            if !server.newcomers.is_empty() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(server.newcomers.len(), 1);
+
+        server.newcomers[0].0 -= JOIN_TIMEOUT;
+        server.poll();
+        assert!(server.newcomers.is_empty());
+    }
+}
@@ -0,0 +1,433 @@
+//! Player profiles and their save files: settings, level progress, high scores,
+//! daily challenge results and learning statistics.
+//!
+//! Every profile has its own save file. The list of [`Profiles`] is loaded when entering
+//! [`Screen::Loading`], followed by the current profile's [`SaveData`]. Both are written
+//! back every time they change. Older save files are migrated to [`SAVE_VERSION`] on load.
+//!
+//! Session logs grow with every run, so each profile keeps them apart from its save file
+//! and only writes them when a run ends.
+
+use std::collections::BTreeMap;
+
+use bevy::{audio::Volume, prelude::*};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{
+    math_mono::{
+        daily::DailyRecords,
+        high_scores::{self, HighScore, HighScoreTable},
+        level::LEVELS,
+        mastery::SkillStats,
+        review::ReviewSchedule,
+        session::SessionLog,
+    },
+    screens::Screen,
+    storage,
+};
+
+/// Bump this and add a step to [`migrate`] when the save format changes in a way old
+/// files can't be read as. New fields don't need a bump: they're `#[serde(default)]`, so
+/// files written before them load with their default.
+pub const SAVE_VERSION: u32 = 1;
+const PROFILES_KEY: &str = "profiles";
+/// Before profiles existed, the only save file was stored under this key.
+const LEGACY_SAVE_KEY: &str = "save";
+/// Before save files were versioned, only daily challenge results were stored, under this key.
+const LEGACY_DAILY_KEY: &str = "daily";
+/// Older sessions are dropped once a profile has this many.
+const MAX_SESSIONS: usize = 100;
+/// The name of the profile created on first launch.
+const DEFAULT_PROFILE_NAME: &str = "Player 1";
+/// Stands in for the current profile if there are none. Loading and [`Profiles::delete`]
+/// always leave at least one, so this is never actually played.
+static NO_PROFILE: Profile = Profile {
+    id: 0,
+    name: String::new(),
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::Loading), load_profiles);
+    app.add_systems(
+        Update,
+        (
+            (
+                write_profiles,
+                delete_removed_profiles,
+                load_current_profile,
+            )
+                .run_if(resource_exists_and_changed::<Profiles>),
+            sync_settings_from_global_volume
+                .run_if(resource_exists::<SaveData>.and(resource_changed::<GlobalVolume>)),
+            write_save_data.run_if(resource_exists_and_changed::<SaveData>),
+        )
+            .chain(),
+    );
+}
+
+/// Everyone who plays on this machine.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profiles {
+    pub profiles: Vec<Profile>,
+    /// [`Profile::id`] of the profile being played.
+    pub current: u32,
+    next_id: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    pub id: u32,
+    pub name: String,
+}
+
+impl Profile {
+    fn save_key(&self) -> String {
+        profile_save_key(self.id)
+    }
+
+    fn sessions_key(&self) -> String {
+        profile_sessions_key(self.id)
+    }
+}
+
+fn profile_save_key(id: u32) -> String {
+    format!("profile-{id}")
+}
+
+fn profile_sessions_key(id: u32) -> String {
+    format!("profile-{id}-sessions")
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        let mut profiles = Self {
+            profiles: Vec::new(),
+            current: 0,
+            next_id: 0,
+        };
+        let id = profiles.create(DEFAULT_PROFILE_NAME);
+        profiles.current = id;
+        profiles
+    }
+}
+
+impl Profiles {
+    pub fn current(&self) -> &Profile {
+        self.profiles
+            .iter()
+            .find(|profile| profile.id == self.current)
+            .or(self.profiles.first())
+            .unwrap_or(&NO_PROFILE)
+    }
+
+    /// Add a new profile and return its id.
+    pub fn create(&mut self, name: impl Into<String>) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.profiles.push(Profile {
+            id,
+            name: name.into(),
+        });
+        id
+    }
+
+    /// Remove a profile. The last remaining profile can't be deleted.
+    pub fn delete(&mut self, id: u32) {
+        if self.profiles.len() <= 1 {
+            return;
+        }
+        self.profiles.retain(|profile| profile.id != id);
+        if self.current == id {
+            self.current = self.profiles[0].id;
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SaveData {
+    pub version: u32,
+    pub settings: Settings,
+    /// How many levels, counted from the start of [`LEVELS`], can be played.
+    pub unlocked_levels: usize,
+    /// Best results keyed by [`LevelDefinition::id`](crate::math_mono::level::LevelDefinition::id).
+    pub levels: BTreeMap<String, LevelRecord>,
+    /// High-score tables keyed by [`HighScoreTable::key`].
+    pub high_scores: BTreeMap<String, Vec<HighScore>>,
+    pub daily: DailyRecords,
+    /// Learning statistics keyed by [`Question::skill`](crate::math_mono::game::question::Question::skill).
+    pub mastery: BTreeMap<String, SkillStats>,
+    /// Spaced-repetition state for picking questions.
+    pub review: ReviewSchedule,
+}
+
+impl Default for SaveData {
+    fn default() -> Self {
+        Self {
+            version: SAVE_VERSION,
+            settings: Settings::default(),
+            unlocked_levels: 1,
+            levels: BTreeMap::new(),
+            high_scores: BTreeMap::new(),
+            daily: DailyRecords::default(),
+            mastery: BTreeMap::new(),
+            review: ReviewSchedule::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Linear master volume.
+    pub volume: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self { volume: 1.0 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LevelRecord {
+    pub best_score: i32,
+    pub stars: u8,
+}
+
+impl SaveData {
+    pub fn is_unlocked(&self, index: usize) -> bool {
+        index < self.unlocked_levels
+    }
+
+    pub fn level_record(&self, index: usize) -> Option<&LevelRecord> {
+        self.levels.get(LEVELS.get(index)?.id)
+    }
+
+    /// Keep the best score and stars for a completed level and unlock the one after it.
+    pub fn record_level(&mut self, index: usize, score: i32, stars: u8) {
+        let Some(level) = LEVELS.get(index) else {
+            return;
+        };
+        let record = self.levels.entry(level.id.to_string()).or_default();
+        record.best_score = record.best_score.max(score);
+        record.stars = record.stars.max(stars);
+        self.unlocked_levels = self.unlocked_levels.max(index + 2).min(LEVELS.len());
+    }
+
+    pub fn high_scores(&self, table: &HighScoreTable) -> &[HighScore] {
+        self.high_scores
+            .get(&table.key())
+            .map_or(&[], Vec::as_slice)
+    }
+
+    pub fn qualifies_for_high_score(&self, table: &HighScoreTable, score: i32) -> bool {
+        high_scores::qualifies(self.high_scores(table), score)
+    }
+
+    pub fn add_high_score(&mut self, table: &HighScoreTable, entry: HighScore) {
+        high_scores::insert(self.high_scores.entry(table.key()).or_default(), entry);
+    }
+}
+
+/// Bring a save file of any older version up to [`SAVE_VERSION`].
+fn migrate(mut save: Value) -> Result<SaveData, serde_json::Error> {
+    let version = save.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if version < 1 {
+        // Version 0 was the bare daily records. Everything else starts from its default.
+        save = json!({ "version": 1, "daily": save });
+    }
+    serde_json::from_value(save)
+}
+
+fn load_profiles(mut commands: Commands) {
+    let profiles = match storage::load::<Profiles>(PROFILES_KEY) {
+        Some(profiles) => checked_profiles(profiles),
+        None => adopt_legacy_save(),
+    };
+    commands.insert_resource(profiles);
+}
+
+/// The game never writes a list without profiles, but one must not stop it starting.
+fn checked_profiles(profiles: Profiles) -> Profiles {
+    if profiles.profiles.is_empty() {
+        warn!("Ignoring a profile list without any profiles");
+        return Profiles::default();
+    }
+    profiles
+}
+
+fn adopt_legacy_save() -> Profiles {
+    // The first launch with profiles adopts any save made before they existed.
+    let profiles = Profiles::default();
+    let legacy =
+        storage::load::<Value>(LEGACY_SAVE_KEY).or_else(|| storage::load(LEGACY_DAILY_KEY));
+    if let Some(legacy) = legacy {
+        storage::save(&profiles.current().save_key(), &legacy);
+    }
+    profiles
+}
+
+/// Load the save file of any profile, not just the current one.
+pub fn load_profile_save(profile: &Profile) -> SaveData {
+    let Some(mut save) = storage::load::<Value>(&profile.save_key()) else {
+        return SaveData::default();
+    };
+    adopt_saved_sessions(profile, &mut save);
+    migrate(save).unwrap_or_else(|error| {
+        warn!("Ignoring save data that couldn't be migrated: {error}");
+        SaveData::default()
+    })
+}
+
+/// Sessions used to be logged in the save file. Move them to their own, and write the
+/// save file back without them so they're only moved once.
+fn adopt_saved_sessions(profile: &Profile, save: &mut Value) {
+    let Some(sessions) = save
+        .as_object_mut()
+        .and_then(|save| save.remove("sessions"))
+    else {
+        return;
+    };
+    match serde_json::from_value::<Vec<SessionLog>>(sessions) {
+        Ok(mut sessions) => {
+            sessions.extend(load_profile_sessions(profile));
+            write_sessions(profile, sessions);
+        }
+        Err(error) => warn!("Dropping session logs that couldn't be read: {error}"),
+    }
+    storage::save(&profile.save_key(), save);
+}
+
+/// Every run a profile has played, oldest first.
+pub fn load_profile_sessions(profile: &Profile) -> Vec<SessionLog> {
+    storage::load(&profile.sessions_key()).unwrap_or_default()
+}
+
+/// Add a finished run to a profile's session log.
+pub fn record_session(profile: &Profile, log: SessionLog) {
+    let mut sessions = load_profile_sessions(profile);
+    sessions.push(log);
+    write_sessions(profile, sessions);
+}
+
+fn write_sessions(profile: &Profile, mut sessions: Vec<SessionLog>) {
+    let excess = sessions.len().saturating_sub(MAX_SESSIONS);
+    sessions.drain(..excess);
+    storage::save_compact(&profile.sessions_key(), &sessions);
+}
+
+/// The profile whose [`SaveData`] is loaded.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+struct LoadedProfile(u32);
+
+fn load_current_profile(
+    mut commands: Commands,
+    profiles: Res<Profiles>,
+    loaded: Option<Res<LoadedProfile>>,
+    mut global_volume: ResMut<GlobalVolume>,
+) {
+    let profile = profiles.current();
+    if loaded.is_some_and(|loaded| loaded.0 == profile.id) {
+        return;
+    }
+    let save = load_profile_save(profile);
+    global_volume.volume = Volume::Linear(save.settings.volume);
+    commands.insert_resource(save);
+    commands.insert_resource(LoadedProfile(profile.id));
+}
+
+fn write_profiles(profiles: Res<Profiles>) {
+    storage::save(PROFILES_KEY, &*profiles);
+}
+
+/// Remove the save files of profiles that were deleted.
+fn delete_removed_profiles(profiles: Res<Profiles>, mut known: Local<Vec<u32>>) {
+    for &id in known.iter() {
+        if !profiles.profiles.iter().any(|profile| profile.id == id) {
+            storage::remove(&profile_save_key(id));
+            storage::remove(&profile_sessions_key(id));
+        }
+    }
+    *known = profiles.profiles.iter().map(|profile| profile.id).collect();
+}
+
+fn sync_settings_from_global_volume(global_volume: Res<GlobalVolume>, mut save: ResMut<SaveData>) {
+    let volume = global_volume.volume.to_linear();
+    // Avoid rewriting the save file when nothing actually changed.
+    if save.settings.volume != volume {
+        save.settings.volume = volume;
+    }
+}
+
+fn write_save_data(save: Res<SaveData>, loaded: Res<LoadedProfile>) {
+    storage::save(&profile_save_key(loaded.0), &*save);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_legacy_daily_records() {
+        let legacy = json!({
+            "results": [{ "date": "2025-09-10", "score": 40, "rounds_cleared": 2 }],
+        });
+
+        let save = migrate(legacy).unwrap();
+        assert_eq!(save.version, SAVE_VERSION);
+        assert_eq!(save.unlocked_levels, 1);
+        assert_eq!(save.daily.get("2025-09-10").unwrap().score, 40);
+    }
+
+    #[test]
+    fn test_version_1_save_without_newer_fields_loads() {
+        let save = migrate(json!({ "version": 1, "unlocked_levels": 3 })).unwrap();
+        assert_eq!(save.unlocked_levels, 3);
+        assert!(save.mastery.is_empty());
+        assert!(save.high_scores.is_empty());
+    }
+
+    #[test]
+    fn test_empty_profile_list_falls_back_to_default() {
+        let empty: Profiles =
+            serde_json::from_value(json!({ "profiles": [], "current": 3, "next_id": 4 })).unwrap();
+        assert_eq!(empty.current().name, "");
+
+        let profiles = checked_profiles(empty);
+        assert_eq!(profiles.current().name, DEFAULT_PROFILE_NAME);
+    }
+
+    #[test]
+    fn test_deleting_current_profile_selects_another() {
+        let mut profiles = Profiles::default();
+        let first = profiles.current;
+        let second = profiles.create("Ada");
+        profiles.current = second;
+
+        profiles.delete(second);
+        assert_eq!(profiles.current, first);
+
+        // The last profile stays.
+        profiles.delete(first);
+        assert_eq!(profiles.profiles.len(), 1);
+    }
+
+    #[test]
+    fn test_record_level_unlocks_next() {
+        let mut save = SaveData::default();
+        save.record_level(0, 120, 2);
+        save.record_level(0, 80, 3);
+
+        assert!(save.is_unlocked(1));
+        assert!(!save.is_unlocked(2));
+        assert_eq!(
+            save.level_record(0),
+            Some(&LevelRecord {
+                best_score: 120,
+                stars: 3
+            })
+        );
+    }
+}
@@ -0,0 +1,79 @@
+//! The screen shown when the player runs out of health.
+
+use bevy::prelude::*;
+
+use crate::{
+    math_mono::run::{GameMode, Party, RunSummary},
+    save::SaveData,
+    screens::{Screen, high_score_entry},
+    theme::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::GameOver), spawn_game_over_screen);
+}
+
+fn spawn_game_over_screen(mut commands: Commands, summary: Res<RunSummary>, save: Res<SaveData>) {
+    let root = commands
+        .spawn((
+            widget::ui_root("Game Over Screen"),
+            StateScoped(Screen::GameOver),
+            children![
+                widget::header(header(&summary, "Game Over")),
+                widget::label(format!("Score: {}", summary.score)),
+                widget::label(format!("Questions cleared: {}", summary.rounds_cleared)),
+            ],
+        ))
+        .id();
+    for label in player_score_labels(&summary) {
+        commands.entity(root).with_child(widget::label(label));
+    }
+
+    let new_high_score = high_score_entry::qualifies(&summary, &save);
+    commands.entity(root).with_children(|parent| {
+        if new_high_score {
+            parent.spawn(high_score_entry::high_score_entry());
+        }
+        // The daily challenge only allows one attempt.
+        if summary.mode != GameMode::Daily {
+            parent.spawn(widget::button("Try again", enter_gameplay_screen));
+        }
+        parent.spawn(widget::button("Quit to title", enter_title_screen));
+    });
+}
+
+/// Who won a versus run, or `default` for any other run.
+pub(super) fn header(summary: &RunSummary, default: &str) -> String {
+    if summary.party != Party::Versus {
+        return default.to_string();
+    }
+    match summary.winner() {
+        Some(index) => format!("Player {} Wins!", index + 1),
+        None => "It's a Draw!".to_string(),
+    }
+}
+
+/// Each player's own score, when more than one played.
+pub(super) fn player_score_labels(summary: &RunSummary) -> Vec<String> {
+    let scores = summary.player_scores.iter().enumerate();
+    match summary.party {
+        Party::Solo => Vec::new(),
+        Party::Coop => scores
+            .map(|(index, score)| format!("Player {}: {score}", index + 1))
+            .collect(),
+        Party::Versus => scores
+            .zip(summary.player_correct)
+            .map(|((index, score), correct)| {
+                format!("Player {}: {correct} right, {score} points", index + 1)
+            })
+            .collect(),
+    }
+}
+
+fn enter_gameplay_screen(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Gameplay);
+}
+
+fn enter_title_screen(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Title);
+}
@@ -2,10 +2,15 @@
 
 use bevy::{input::common_conditions::input_just_pressed, prelude::*, ui::Val::*};
 
-use crate::{Pause, math_mono::level::spawn_level, menus::Menu, screens::Screen};
+use crate::{
+    Pause,
+    math_mono::{level::spawn_level, run::start_run},
+    menus::Menu,
+    screens::Screen,
+};
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_systems(OnEnter(Screen::Gameplay), spawn_level);
+    app.add_systems(OnEnter(Screen::Gameplay), (start_run, spawn_level).chain());
 
     // Toggle pause on key press.
     app.add_systems(
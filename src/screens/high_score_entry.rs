@@ -0,0 +1,66 @@
+//! Name entry for a run that made it onto its high-score table.
+
+use bevy::{prelude::*, ui::Val::*};
+
+use crate::{
+    math_mono::{
+        high_scores::{HighScore, HighScoreTable, MAX_NAME_LEN},
+        run::{Party, RunSummary},
+    },
+    save::SaveData,
+    theme::{prelude::*, text_entry::TextEntrySubmitted},
+};
+
+/// Name used when the player submits an empty name.
+const DEFAULT_NAME: &str = "Anonymous";
+
+#[derive(Component)]
+struct HighScoreEntry;
+
+/// Whether the last run should be offered a spot on its high-score table. Only runs
+/// played alone are.
+pub(super) fn qualifies(summary: &RunSummary, save: &SaveData) -> bool {
+    summary.party == Party::Solo
+        && save.qualifies_for_high_score(&HighScoreTable::for_run(summary), summary.score)
+}
+
+pub(super) fn high_score_entry() -> impl Bundle {
+    (
+        Name::new("High Score Entry"),
+        HighScoreEntry,
+        Node {
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: Px(10.0),
+            ..default()
+        },
+        children![
+            widget::label("New high score! Enter your name:"),
+            widget::text_entry(MAX_NAME_LEN, save_high_score),
+        ],
+    )
+}
+
+fn save_high_score(
+    trigger: Trigger<TextEntrySubmitted>,
+    mut commands: Commands,
+    summary: Res<RunSummary>,
+    mut save: ResMut<SaveData>,
+    entry: Single<Entity, With<HighScoreEntry>>,
+) {
+    let name = match trigger.value.trim() {
+        "" => DEFAULT_NAME.to_string(),
+        name => name.to_string(),
+    };
+    save.add_high_score(
+        &HighScoreTable::for_run(&summary),
+        HighScore {
+            name: name.clone(),
+            score: summary.score,
+        },
+    );
+    commands
+        .entity(*entry)
+        .despawn_related::<Children>()
+        .with_child(widget::label(format!("Saved as {name}")));
+}
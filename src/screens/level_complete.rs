@@ -0,0 +1,82 @@
+//! The screen shown when the player reaches a level's goal.
+
+use bevy::prelude::*;
+
+use crate::{
+    math_mono::{
+        level::{LEVELS, SelectedLevel},
+        run::RunSummary,
+    },
+    save::SaveData,
+    screens::{Screen, game_over, high_score_entry},
+    theme::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::LevelComplete), spawn_level_complete_screen);
+}
+
+fn spawn_level_complete_screen(
+    mut commands: Commands,
+    summary: Res<RunSummary>,
+    save: Res<SaveData>,
+) {
+    let root = commands
+        .spawn((
+            widget::ui_root("Level Complete Screen"),
+            StateScoped(Screen::LevelComplete),
+            children![
+                widget::header(game_over::header(&summary, "Level Complete")),
+                widget::label(format!("Score: {}", summary.score)),
+                widget::label(format!("Best streak: {}", summary.best_streak)),
+                widget::label(format!("Stars: {}/3", summary.stars)),
+            ],
+        ))
+        .id();
+    for label in game_over::player_score_labels(&summary) {
+        commands.entity(root).with_child(widget::label(label));
+    }
+
+    if summary.no_damage_bonus > 0 {
+        commands.entity(root).with_child(widget::label(format!(
+            "No damage bonus: +{}",
+            summary.no_damage_bonus
+        )));
+    }
+    if let Some(next) = summary.level.and_then(|index| {
+        LEVELS[index]
+            .stars
+            .for_players(summary.party.player_count())
+            .next(summary.stars)
+    }) {
+        commands.entity(root).with_child(widget::label(format!(
+            "{} stars at {next} points",
+            summary.stars + 1
+        )));
+    }
+
+    let has_next_level = summary.level.is_some_and(|index| index + 1 < LEVELS.len());
+    let new_high_score = high_score_entry::qualifies(&summary, &save);
+    commands.entity(root).with_children(|parent| {
+        if new_high_score {
+            parent.spawn(high_score_entry::high_score_entry());
+        }
+        if has_next_level {
+            parent.spawn(widget::button("Next level", enter_next_level));
+        }
+        parent.spawn(widget::button("Quit to title", enter_title_screen));
+    });
+}
+
+fn enter_next_level(
+    _: Trigger<Pointer<Click>>,
+    mut selected_level: ResMut<SelectedLevel>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    selected_level.0 += 1;
+    next_screen.set(Screen::Gameplay);
+}
+
+fn enter_title_screen(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Title);
+}
@@ -0,0 +1,68 @@
+//! The screen shown after a level, going over the mistakes the player made.
+
+use bevy::{prelude::*, ui::Val::*};
+
+use crate::{
+    math_mono::{game::explanation::explain, mistakes::MistakeLog},
+    screens::Screen,
+    theme::prelude::*,
+};
+
+/// Explanations shown before the rest are summed up in one line.
+const MAX_LINES: usize = 10;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::LevelReview), spawn_level_review_screen);
+}
+
+fn spawn_level_review_screen(mut commands: Commands, log: Res<MistakeLog>) {
+    let mut lines = Vec::new();
+    for round in &log.rounds {
+        lines.push(format!("{}:", round.question.text()));
+        for &value in &round.wrong {
+            lines.push(format!("  You ate {}", explain(&round.question, value)));
+        }
+        for &value in &round.missed {
+            lines.push(format!("  You missed {}", explain(&round.question, value)));
+        }
+    }
+    let hidden = lines.len().saturating_sub(MAX_LINES);
+    lines.truncate(MAX_LINES);
+    if hidden > 0 {
+        lines.push(format!("...and {hidden} more"));
+    }
+    if lines.is_empty() {
+        lines.push("No mistakes. Well done!".to_string());
+    }
+
+    commands
+        .spawn((
+            widget::ui_root("Level Review Screen"),
+            StateScoped(Screen::LevelReview),
+        ))
+        .with_children(|parent| {
+            parent.spawn(widget::header("Let's review"));
+            parent
+                .spawn((
+                    Name::new("Explanations"),
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Px(6.0),
+                        ..default()
+                    },
+                ))
+                .with_children(|parent| {
+                    for line in lines {
+                        parent.spawn(widget::label(line));
+                    }
+                });
+            parent.spawn(widget::button("Continue", enter_level_complete_screen));
+        });
+}
+
+fn enter_level_complete_screen(
+    _: Trigger<Pointer<Click>>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    next_screen.set(Screen::LevelComplete);
+}
@@ -1,6 +1,10 @@
 //! The game's main screen states and transitions between them.
 
+mod game_over;
 mod gameplay;
+mod high_score_entry;
+mod level_complete;
+mod level_review;
 mod loading;
 mod splash;
 mod title;
@@ -11,7 +15,10 @@ pub(super) fn plugin(app: &mut App) {
     app.init_state::<Screen>();
 
     app.add_plugins((
+        game_over::plugin,
         gameplay::plugin,
+        level_complete::plugin,
+        level_review::plugin,
         loading::plugin,
         splash::plugin,
         title::plugin,
@@ -27,4 +34,7 @@ pub enum Screen {
     Title,
     Loading,
     Gameplay,
+    GameOver,
+    LevelReview,
+    LevelComplete,
 }
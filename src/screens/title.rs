@@ -1,4 +1,4 @@
-//! The title screen that appears after the splash screen.
+//! The title screen that appears once loading has finished.
 
 use bevy::prelude::*;
 
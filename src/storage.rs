@@ -0,0 +1,110 @@
+//! Persist small pieces of game data between sessions.
+//!
+//! Native builds store each key as a JSON file in the platform config directory.
+//! Web builds store it in the browser's `localStorage` instead.
+
+use bevy::prelude::*;
+use serde::{Serialize, de::DeserializeOwned};
+
+/// Load the value stored under `key`. Returns `None` if nothing was stored yet
+/// or if the stored data can't be read.
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let text = backend::read(key)?;
+    match serde_json::from_str(&text) {
+        Ok(value) => Some(value),
+        Err(error) => {
+            warn!("Ignoring unreadable save data `{key}`: {error}");
+            None
+        }
+    }
+}
+
+/// Store `value` under `key`, replacing whatever was stored there before.
+pub fn save<T: Serialize>(key: &str, value: &T) {
+    write(key, serde_json::to_string_pretty(value));
+}
+
+/// Like [`save`], but without whitespace, for values too big to be worth reading by hand.
+pub fn save_compact<T: Serialize>(key: &str, value: &T) {
+    write(key, serde_json::to_string(value));
+}
+
+fn write(key: &str, text: serde_json::Result<String>) {
+    let text = match text {
+        Ok(text) => text,
+        Err(error) => {
+            warn!("Failed to serialize save data `{key}`: {error}");
+            return;
+        }
+    };
+    if let Err(error) = backend::write(key, &text) {
+        warn!("Failed to write save data `{key}`: {error}");
+    }
+}
+
+/// Forget whatever is stored under `key`.
+pub fn remove(key: &str) {
+    if let Err(error) = backend::remove(key) {
+        warn!("Failed to remove save data `{key}`: {error}");
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+mod backend {
+    use std::{fs, path::PathBuf};
+
+    fn path(key: &str) -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("math-mono").join(format!("{key}.json")))
+    }
+
+    pub fn read(key: &str) -> Option<String> {
+        fs::read_to_string(path(key)?).ok()
+    }
+
+    pub fn write(key: &str, text: &str) -> Result<(), String> {
+        let path = path(key).ok_or("no config directory on this platform")?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+        }
+        fs::write(path, text).map_err(|error| error.to_string())
+    }
+
+    pub fn remove(key: &str) -> Result<(), String> {
+        let path = path(key).ok_or("no config directory on this platform")?;
+        match fs::remove_file(path) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(target_family = "wasm")]
+mod backend {
+    use web_sys::Storage;
+
+    fn storage() -> Option<Storage> {
+        web_sys::window()?.local_storage().ok()?
+    }
+
+    fn item_key(key: &str) -> String {
+        format!("math-mono/{key}")
+    }
+
+    pub fn read(key: &str) -> Option<String> {
+        storage()?.get_item(&item_key(key)).ok()?
+    }
+
+    pub fn write(key: &str, text: &str) -> Result<(), String> {
+        let storage = storage().ok_or("`localStorage` is unavailable")?;
+        storage
+            .set_item(&item_key(key), text)
+            .map_err(|error| format!("{error:?}"))
+    }
+
+    pub fn remove(key: &str) -> Result<(), String> {
+        let storage = storage().ok_or("`localStorage` is unavailable")?;
+        storage
+            .remove_item(&item_key(key))
+            .map_err(|error| format!("{error:?}"))
+    }
+}
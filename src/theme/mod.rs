@@ -5,6 +5,7 @@
 
 pub mod interaction;
 pub mod palette;
+pub mod text_entry;
 pub mod widget;
 
 #[allow(unused_imports)]
@@ -15,5 +16,5 @@ pub mod prelude {
 use bevy::prelude::*;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_plugins(interaction::plugin);
+    app.add_plugins((interaction::plugin, text_entry::plugin));
 }
@@ -0,0 +1,149 @@
+//! Input handling for the [`text_entry`](super::widget::text_entry) widget.
+//!
+//! Keyboard: type to add characters, Backspace deletes, Enter submits.
+//! Gamepad: D-pad up/down changes the last character, right adds one, left deletes,
+//! and the south button submits.
+
+use bevy::{
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<TextEntry>();
+    app.add_systems(
+        Update,
+        (
+            (type_with_keyboard, type_with_gamepad),
+            update_text_entry_display,
+        )
+            .chain(),
+    );
+}
+
+/// Characters a gamepad can cycle through, in order.
+const GAMEPAD_CHARACTERS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 ";
+
+/// Editable single-line text. Triggers [`TextEntrySubmitted`] on its entity when confirmed.
+#[derive(Component, Debug, Default, Reflect)]
+#[reflect(Component)]
+#[require(Text)]
+pub struct TextEntry {
+    pub value: String,
+    pub max_len: usize,
+    /// Punctuation that may be typed besides letters, digits and spaces.
+    pub symbols: &'static str,
+}
+
+impl TextEntry {
+    fn push(&mut self, character: char) {
+        if self.value.chars().count() < self.max_len {
+            self.value.push(character);
+        }
+    }
+
+    /// Move the last character forwards or backwards through [`GAMEPAD_CHARACTERS`].
+    fn cycle_last(&mut self, forwards: bool) {
+        let characters: Vec<char> = GAMEPAD_CHARACTERS.chars().collect();
+        let Some(last) = self.value.pop() else {
+            self.push(characters[0]);
+            return;
+        };
+        let index = characters
+            .iter()
+            .position(|&c| c == last.to_ascii_uppercase())
+            .unwrap_or(0);
+        let next = if forwards {
+            (index + 1) % characters.len()
+        } else {
+            (index + characters.len() - 1) % characters.len()
+        };
+        self.value.push(characters[next]);
+    }
+}
+
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct TextEntrySubmitted {
+    pub value: String,
+}
+
+fn type_with_keyboard(
+    mut commands: Commands,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut entry_query: Query<(Entity, &mut TextEntry)>,
+) {
+    for event in keyboard_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        for (entity, mut entry) in &mut entry_query {
+            match &event.logical_key {
+                Key::Character(text) => {
+                    let symbols = entry.symbols;
+                    for character in text
+                        .chars()
+                        .filter(|c| c.is_alphanumeric() || *c == ' ' || symbols.contains(*c))
+                    {
+                        entry.push(character);
+                    }
+                }
+                Key::Space => entry.push(' '),
+                Key::Backspace => {
+                    entry.value.pop();
+                }
+                Key::Enter => commands.trigger_targets(
+                    TextEntrySubmitted {
+                        value: entry.value.clone(),
+                    },
+                    entity,
+                ),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn type_with_gamepad(
+    mut commands: Commands,
+    gamepad_query: Query<&Gamepad>,
+    mut entry_query: Query<(Entity, &mut TextEntry)>,
+) {
+    for gamepad in &gamepad_query {
+        for (entity, mut entry) in &mut entry_query {
+            if gamepad.just_pressed(GamepadButton::DPadUp) {
+                entry.cycle_last(true);
+            }
+            if gamepad.just_pressed(GamepadButton::DPadDown) {
+                entry.cycle_last(false);
+            }
+            if gamepad.just_pressed(GamepadButton::DPadRight) {
+                entry.push(GAMEPAD_CHARACTERS.chars().next().unwrap());
+            }
+            if gamepad.just_pressed(GamepadButton::DPadLeft) {
+                entry.value.pop();
+            }
+            if gamepad.just_pressed(GamepadButton::South) {
+                commands.trigger_targets(
+                    TextEntrySubmitted {
+                        value: entry.value.clone(),
+                    },
+                    entity,
+                );
+            }
+        }
+    }
+}
+
+fn update_text_entry_display(mut entry_query: Query<(&TextEntry, &mut Text), Changed<TextEntry>>) {
+    for (entry, mut text) in &mut entry_query {
+        // Show a cursor while there is room for more characters.
+        text.0 = if entry.value.chars().count() < entry.max_len {
+            format!("{}_", entry.value)
+        } else {
+            entry.value.clone()
+        };
+    }
+}
@@ -8,7 +8,7 @@ use bevy::{
     ui::Val::*,
 };
 
-use crate::theme::{interaction::InteractionPalette, palette::*};
+use crate::theme::{interaction::InteractionPalette, palette::*, text_entry::TextEntry};
 
 /// A root UI node that fills the window and centers its content.
 pub fn ui_root(name: impl Into<Cow<'static, str>>) -> impl Bundle {
@@ -49,6 +49,56 @@ pub fn label(text: impl Into<String>) -> impl Bundle {
     )
 }
 
+/// A text box the player can type into with a keyboard or gamepad.
+/// `action` observes [`TextEntrySubmitted`](super::text_entry::TextEntrySubmitted).
+pub fn text_entry<E, B, M, I>(max_len: usize, action: I) -> impl Bundle
+where
+    E: Event,
+    B: Bundle,
+    I: IntoObserverSystem<E, B, M>,
+{
+    text_entry_with(
+        TextEntry {
+            max_len,
+            ..default()
+        },
+        action,
+    )
+}
+
+/// A [`text_entry`] starting from `entry`, e.g. to fill in a value or allow punctuation.
+pub fn text_entry_with<E, B, M, I>(entry: TextEntry, action: I) -> impl Bundle
+where
+    E: Event,
+    B: Bundle,
+    I: IntoObserverSystem<E, B, M>,
+{
+    let action = IntoObserverSystem::into_system(action);
+    (
+        Name::new("Text Entry"),
+        Node::default(),
+        Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
+            parent
+                .spawn((
+                    Name::new("Text Entry Inner"),
+                    Node {
+                        width: Px(380.0),
+                        height: Px(60.0),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    BorderRadius::all(Px(10.0)),
+                    BackgroundColor(BUTTON_PRESSED_BACKGROUND),
+                    entry,
+                    TextFont::from_font_size(40.0),
+                    TextColor(BUTTON_TEXT),
+                ))
+                .observe(action);
+        })),
+    )
+}
+
 /// A large rounded button with text and an action defined as an [`Observer`].
 pub fn button<E, B, M, I>(text: impl Into<String>, action: I) -> impl Bundle
 where